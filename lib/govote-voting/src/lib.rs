@@ -9,45 +9,405 @@
 //! - You can change the options until the voting is completed.
 //!
 //! **WARNING** In this version you can do the following for testing:
-//! - Even after the deadline has passed, you can vote if the data is not counted.
 //! - Anyone can execute the aggregation method.
 //! - Aggregation is possible even before the deadline.
+//!
+//! **Migration** Vote weights and vote counts are `u64`. The state layout
+//! differs from instances deployed with `u32` counts, so such instances
+//! cannot be upgraded in place; deploy a new instance instead.
+//! The state starts with a layout version byte. A state in the original
+//! `u32` layout, prefixed with version 1, is still deserialized and widened
+//! to the current layout, so exported state can be carried over.
+//! Contracts on this protocol version cannot replace their code, so there is
+//! no `migrate` entrypoint.
+
+use concordium_std::{
+    collections::{HashMap as Map, HashSet as Set},
+    *,
+};
+use core::{cmp::Ordering, convert::TryFrom};
 
-use concordium_std::{collections::HashMap as Map, *};
+mod sha256;
+
+use sha256::sha256;
 
 type ProposalId = u8;
 type ProposalNames = Vec<String>;
+type ProposalDescriptions = Vec<String>;
+type ProposalMetadataUrls = Vec<Option<String>>;
+type ProposalGroups = Vec<u8>;
 type Title = String;
 type Description = String;
+type HashSha256 = [u8; 32];
 
-#[derive(Debug, Serialize, SchemaType, Default, PartialEq)]
+/// Version of the `InitParams` layout that this contract accepts.
+const CONTRACT_VERSION: u8 = 1;
+
+/// Version of the `State` layout written by this contract.
+const STATE_VERSION: u8 = 2;
+
+/// Maximum length of a proposal's metadata URL in bytes.
+const MAX_METADATA_URL_LENGTH: usize = 2048;
+
+/// Maximum length of the title in characters.
+const MAX_TITLE_LENGTH: usize = 100;
+
+/// Maximum length of the description in characters.
+const MAX_DESCRIPTION_LENGTH: usize = 1000;
+
+/// Maximum length of a proposal's name in bytes. A proposal then always fits
+/// in one `Event::Created` log entry next to the longest title: the event
+/// takes 17 bytes besides the title, which is at most 4 bytes per character,
+/// and each proposal 5 bytes besides its name.
+const MAX_PROPOSAL_NAME_LENGTH: usize = constants::MAX_LOG_SIZE - (17 + 4 * MAX_TITLE_LENGTH) - 5;
+
+/// Units a ballot counts per unit of weight under `WeightPolicy::LinearDecay`.
+const DECAY_SCALE: u64 = 10_000;
+
+/// Maximum number of links in a delegation chain. Bounds the cost of
+/// following a chain when counting weights.
+const MAX_DELEGATION_DEPTH: usize = 16;
+
+/// Receive function the token contract reports balances to.
+const BALANCE_CALLBACK: &str = "govote_voting.receiveBalance";
+
+/// Maximum number of proposals in a page from `getProposalsPage`.
+const MAX_PROPOSALS_PAGE_SIZE: u8 = 16;
+
+#[derive(Debug, Serialize, SchemaType, Default, PartialEq, Clone)]
 struct VoterState {
-    weight: u32,
+    weight: u64,
     voted: bool,
-    vote: ProposalId,
+    /// The proposal currently voted for, `None` if there is no active vote.
+    vote: Option<ProposalId>,
+    /// The address this voter's weight is delegated to.
+    delegate: Option<Address>,
+    /// When the active vote was cast.
+    voted_at: Option<Timestamp>,
+    /// Commitment to a hidden vote in commit-reveal mode.
+    commitment: Option<HashSha256>,
+    /// Whether the active vote is an abstention.
+    abstained: bool,
+    /// Weight currently counted towards `vote`, delegated weight included.
+    counted_weight: u64,
+    /// How many times the voter switched to another proposal.
+    change_count: u32,
+    /// The proposal of the last vote cast, kept when the vote is cancelled,
+    /// replaced by an abstention or delegated away, so a later vote for
+    /// another proposal still counts as a change.
+    last_vote: Option<ProposalId>,
+    /// When `last_vote` was cast. Kept like `last_vote`, so the cooldown also
+    /// applies to a vote after `cancelVote`.
+    last_voted_at: Option<Timestamp>,
+    /// Whether the last vote was cancelled with `cancelVote`.
+    cancelled: bool,
+    /// Whether `vote` is for or against the proposal.
+    direction: VoteDirection,
+    /// Whether the voter was granted the right to vote and is counted in
+    /// `eligible_count`.
+    granted: bool,
+}
+
+/// Whether a ballot supports or opposes the proposal.
+#[derive(Debug, Serialize, SchemaType, Default, PartialEq, Clone, Copy)]
+enum VoteDirection {
+    #[default]
+    For,
+    Against,
 }
 
 #[derive(Debug, Serialize, SchemaType, Default, PartialEq)]
 struct Proposal {
     name: String,
-    vote_count: u32,
+    description: String,
+    vote_count: u64,
+    /// Link to off-chain content about the proposal.
+    metadata_url: Option<String>,
+    /// Position in proposal lists. Proposals with the same position are
+    /// ordered by id.
+    display_order: u8,
+    /// When the vote count last grew, i.e. when it reached its current count
+    /// unless votes were withdrawn since.
+    reached_at: Option<Timestamp>,
+    /// Category of the proposal, e.g. to separate budget from policy items.
+    group: Option<u8>,
+    /// Weight of the ballots against the proposal.
+    against_count: u64,
+}
+
+/// A proposal on the ballot with its current vote count.
+#[derive(Debug, Serialize, SchemaType, PartialEq)]
+struct ProposalEntry {
+    proposal_id: ProposalId,
+    name: String,
+    vote_count: u64,
+}
+
+/// A proposal's vote count and its participation in basis points: the
+/// share of the eligible weight, see `getParticipationByProposal`.
+#[derive(Debug, Serialize, SchemaType, PartialEq)]
+struct ProposalParticipation {
+    proposal_id: ProposalId,
+    vote_count: u64,
+    participation_bp: u32,
+}
+
+/// Time remaining until expiry, broken into whole units.
+#[derive(Debug, Serialize, SchemaType, PartialEq)]
+struct Countdown {
+    days: u64,
+    hours: u8,
+    minutes: u8,
+    seconds: u8,
+}
+
+/// Thresholds that must all hold for the tally to declare a winner.
+/// A rule set to `None` is not applied.
+#[derive(Debug, Serialize, SchemaType, Default, PartialEq)]
+struct FinalizationRules {
+    /// Minimum number of votes cast, abstentions included.
+    quorum: Option<u64>,
+    /// Minimum share of the votes cast the winner must receive, in basis
+    /// points.
+    win_threshold_bp: Option<u16>,
+    /// Minimum lead of the winner over the runner-up.
+    min_margin: Option<u64>,
+}
+
+/// A rule of `FinalizationRules`.
+#[derive(Debug, Serialize, SchemaType, PartialEq, Clone, Copy)]
+enum FinalizationRule {
+    Quorum,
+    WinThreshold,
+    MinMargin,
+}
+
+impl FinalizationRules {
+    /// Return the first rule the outcome fails, if any.
+    fn first_failed(
+        &self,
+        participation: u64,
+        total_votes: u64,
+        winning_vote_count: u64,
+        runner_up_vote_count: u64,
+    ) -> Option<FinalizationRule> {
+        if let Some(quorum) = self.quorum {
+            if participation < quorum {
+                return Some(FinalizationRule::Quorum);
+            }
+        }
+        if let Some(win_threshold_bp) = self.win_threshold_bp {
+            if (winning_vote_count as u128) * 10_000
+                < (win_threshold_bp as u128) * (total_votes as u128)
+                || (total_votes == 0 && win_threshold_bp > 0)
+            {
+                return Some(FinalizationRule::WinThreshold);
+            }
+        }
+        if let Some(min_margin) = self.min_margin {
+            if winning_vote_count.saturating_sub(runner_up_vote_count) < min_margin {
+                return Some(FinalizationRule::MinMargin);
+            }
+        }
+        None
+    }
+}
+
+/// How the tally resolves a tie for the lead.
+#[derive(Debug, Serialize, SchemaType, PartialEq, Clone, Copy)]
+enum TieBreak {
+    /// Every proposal tied for the lead is a winner.
+    ReturnAll,
+    /// The tied proposal with the smallest id wins.
+    LowestId,
+    /// The tied proposal whose name sorts first wins, by byte-wise
+    /// comparison. Equal names fall back to the smallest id.
+    AlphabeticalName,
+    /// The tied proposal that reached its vote count first wins. Equal times
+    /// fall back to the smallest id.
+    EarliestReached,
+}
+
+/// A CIS-1 token whose balance at vote time is the voter's weight.
+/// CIS-2 answers `balanceOf` through the return value, which a V0 contract
+/// cannot read, so only tokens with the CIS-1 callback are supported.
+#[derive(Debug, Serialize, SchemaType, PartialEq, Clone)]
+struct TokenWeight {
+    contract: ContractAddress,
+    /// The token contract's `balanceOf` receive function, e.g.
+    /// `"my_token.balanceOf"`.
+    balance_of: OwnedReceiveName,
+    #[concordium(size_length = 1)]
+    token_id: Vec<u8>,
+}
+
+/// A query in a CIS-1 `balanceOf` request and response.
+#[derive(Debug, Serialize, SchemaType, PartialEq)]
+struct BalanceOfQuery {
+    #[concordium(size_length = 1)]
+    token_id: Vec<u8>,
+    address: Address,
+}
+
+/// Parameter of a CIS-1 `balanceOf` request.
+#[derive(Debug, Serialize)]
+struct BalanceOfQueryParams {
+    #[concordium(size_length = 2)]
+    queries: Vec<BalanceOfQuery>,
+    callback: OwnedReceiveName,
+}
+
+/// Parameter the token contract calls `receiveBalance` with.
+#[derive(Debug, Serialize, SchemaType)]
+struct BalanceOfQueryResponse {
+    #[concordium(size_length = 2)]
+    balances: Vec<(BalanceOfQuery, u64)>,
+}
+
+/// How a ballot's weight counts towards the proposal.
+#[derive(Debug, Serialize, SchemaType, PartialEq, Clone, Copy)]
+enum WeightPolicy {
+    /// Every ballot counts its full weight.
+    Flat,
+    /// A ballot counts `DECAY_SCALE` per unit of weight when cast at least
+    /// `window` before the expiry, decreasing linearly to 0 at the expiry.
+    LinearDecay {
+        window: Duration,
+    },
 }
 
 #[derive(Serialize, SchemaType)]
 struct InitParams {
+    /// Must be `CONTRACT_VERSION`.
+    version: u8,
     title: Title,
     description: Description,
     proposal_names: ProposalNames,
+    /// One description per proposal, in the order of `proposal_names`.
+    proposal_descriptions: ProposalDescriptions,
+    /// Optional metadata URL for each proposal, in the same order as the
+    /// names.
+    proposal_metadata_urls: ProposalMetadataUrls,
+    /// The group of each proposal, in the same order as the names. Empty for
+    /// a ballot without groups.
+    proposal_groups: ProposalGroups,
     expiry: Timestamp,
+    finalization_rules: FinalizationRules,
+    tie_break: TieBreak,
+    max_voters: Option<u32>,
+    /// Hide ballots with commit-reveal voting until the expiry.
+    commit_reveal: bool,
+    /// Maximum number of votes a single proposal can receive.
+    max_votes_per_proposal: Option<u64>,
+    /// Open voting at init. Otherwise the owner opens it with `openVoting`.
+    start_open: bool,
+    /// How long after the tally the owner can reopen it with `reopenTally`.
+    correction_window: Duration,
+    /// Log `Event::AnonymousVote` with a hash of the voter's address instead
+    /// of `Event::FirstVote` or `Event::VoteChanged`, and likewise
+    /// `Event::AnonymousDelegated` and `Event::AnonymousDelegationRevoked`
+    /// instead of `Event::Delegated` and `Event::DelegationRevoked`.
+    anonymize_events: bool,
+    /// Let a voter change their vote with another `vote`. Without it, a
+    /// voter's first vote, abstention or delegation is final: `vote`,
+    /// `abstain`, `delegate` and `cancelVote` reject with `AlreadyVoted`.
+    allow_revote: bool,
+    weight_policy: WeightPolicy,
+    /// Let voters add a proposal with `voteWriteIn`.
+    allow_write_ins: bool,
+    /// Let the owner vote. Otherwise the owner stays neutral.
+    owner_can_vote: bool,
+    /// Maximum number of times a voter can switch to another proposal.
+    max_changes: Option<u32>,
+    /// Run a yes/no referendum: the first proposal wins if it receives at
+    /// least this share of the votes in basis points, otherwise the second.
+    approval_threshold_bp: Option<u16>,
+    /// How long after the expiry votes are still accepted, in milliseconds.
+    grace_period_ms: u64,
+    /// Number of proposals to elect, at least 1. All proposals tied at the
+    /// last seat are elected, so there can be more winners than seats.
+    seats: u8,
+    /// Only count the votes, without storing or logging which proposal each
+    /// voter picked. Votes cannot be changed, cancelled or delegated, and
+    /// `commit_reveal` is rejected with `IncompatibleModes`.
+    aggregate_only: bool,
+    /// Weight votes with the voter's balance of a token, queried when voting.
+    /// Only `vote` queries the balance, so `voteWriteIn`, `voteBatch`,
+    /// `delegate` and `commitVote` reject with `WrongVotingMode`.
+    token_weight: Option<TokenWeight>,
+    /// Minimum time in milliseconds between a vote and switching it to
+    /// another proposal.
+    cooldown_ms: u64,
+    /// Addresses given the right to vote with weight 1 at init. If not
+    /// empty, only voters with the right to vote can `vote`.
+    eligible: Vec<Address>,
 }
 
 impl Proposal {
-    fn new(name: String) -> Self {
+    fn new(name: String, description: String, metadata_url: Option<String>) -> Self {
         Proposal {
             name,
+            description,
             vote_count: 0,
+            metadata_url,
+            display_order: 0,
+            reached_at: None,
+            group: None,
+            against_count: 0,
         }
     }
+
+    /// The score the tally ranks the proposal by: the votes for it minus the
+    /// votes against it, saturating at 0.
+    fn net_score(&self) -> u64 {
+        self.vote_count.saturating_sub(self.against_count)
+    }
+}
+
+/// The parts of the `InitParams` kept for auditing the deployed
+/// configuration. Later changes, e.g. with `setExpiry`, do not affect it.
+#[derive(Debug, Serialize, SchemaType, PartialEq)]
+struct InitParamsRecord {
+    title: Title,
+    description: Description,
+    proposal_names: ProposalNames,
+    expiry: Timestamp,
+}
+
+/// Outcome of the tally.
+#[derive(Debug, Serialize, SchemaType, PartialEq)]
+struct TallyResult {
+    winning_proposal_id: Vec<ProposalId>,
+    winning_vote_count: u64,
+    /// No winner was declared because a finalization rule failed.
+    indecisive: bool,
+}
+
+/// Self-contained record of the outcome, for archival.
+#[derive(Debug, Serialize, SchemaType, PartialEq)]
+struct FinalResult {
+    winning_proposal_ids: Vec<ProposalId>,
+    /// Names of the winners, in the order of `winning_proposal_ids`.
+    winning_names: Vec<String>,
+    winning_vote_count: u64,
+    total_votes: u64,
+    /// Voters with an active vote relative to the voters granted the right to
+    /// vote, in basis points. 0 without eligibility tracking.
+    turnout_bp: u16,
+    /// Each proposal's share of the weight, ordered by proposal id.
+    shares: Vec<WeightShare>,
+}
+
+/// A proposal's vote count relative to the cast and the eligible weight,
+/// in basis points.
+#[derive(Debug, Serialize, SchemaType, PartialEq)]
+struct WeightShare {
+    proposal_id: ProposalId,
+    /// Share of the votes cast, as in `getDistribution`.
+    cast_share_bp: u32,
+    /// Share of the eligible weight, see `State::eligible_weight`. 0 if no
+    /// voter was granted the right to vote.
+    eligible_share_bp: u32,
 }
 
 #[derive(Serialize, SchemaType)]
@@ -58,38 +418,221 @@ struct GetVoterParams {
 #[derive(Serialize, SchemaType)]
 struct GetVoteParams {
     proposal_id: ProposalId,
+    direction: VoteDirection,
+}
+
+#[derive(Serialize, SchemaType)]
+struct GetProposalParams {
+    proposal_id: ProposalId,
+}
+
+/// Ballots collected off-chain, as (voter, proposal) pairs.
+#[derive(Serialize, SchemaType)]
+struct VoteBatchParams {
+    ballots: Vec<(Address, ProposalId)>,
+}
+
+#[derive(Serialize, SchemaType)]
+struct GiveRightToVoteParams {
+    voter_address: Address,
+    /// Weight of the voter's ballot, must be positive.
+    weight: u64,
+}
+
+/// Addresses to grant the right to vote in one transaction.
+#[derive(Serialize, SchemaType)]
+struct GiveRightToVoteBatchParams {
+    voters: Vec<Address>,
+}
+
+#[derive(Serialize, SchemaType)]
+struct CommitVoteParams {
+    /// `sha256` of the serialized `(proposal_id, nonce)` pair.
+    commitment: HashSha256,
+}
+
+#[derive(Serialize, SchemaType)]
+struct RevealVoteParams {
+    proposal_id: ProposalId,
+    nonce: [u8; 32],
+}
+
+#[derive(Serialize, SchemaType)]
+struct ForceFinishParams {
+    reason: String,
+}
+
+#[derive(Serialize, SchemaType)]
+struct WriteInParams {
+    name: String,
+}
+
+#[derive(Serialize, SchemaType)]
+struct GetProposalByNameParams {
+    name: String,
+}
+
+#[derive(Serialize, SchemaType)]
+struct EditProposalParams {
+    proposal_id: ProposalId,
+    new_name: String,
+}
+
+#[derive(Serialize, SchemaType)]
+struct SetDisplayOrderParams {
+    /// The new `display_order` of each listed proposal.
+    orders: Vec<(ProposalId, u8)>,
+}
+
+#[derive(Serialize, SchemaType)]
+struct SetExpiryParams {
+    expiry: Timestamp,
+}
+
+#[derive(Serialize, SchemaType)]
+struct DelegateParams {
+    to: Address,
+}
+
+#[derive(Serialize, SchemaType)]
+struct GetVotersParams {
+    start: u32,
+    limit: u32,
+}
+
+#[derive(Serialize, SchemaType)]
+struct GetProposalsByGroupParams {
+    group: u8,
+}
+
+#[derive(Serialize, SchemaType)]
+struct GetProposalsPageParams {
+    /// The smallest proposal id to include.
+    start: ProposalId,
+    limit: u8,
+}
+
+#[derive(Serialize, SchemaType)]
+struct SampleVotersParams {
+    count: u32,
+}
+
+#[derive(Serialize, SchemaType)]
+struct CompareProposalsParams {
+    a: ProposalId,
+    b: ProposalId,
 }
 
 /// Contract error type
-#[derive(Debug, PartialEq, Eq, Reject)]
+/// The discriminant of each variant is its reject code. The codes are fixed
+/// so that clients can map them to messages; a new variant takes the next
+/// unused code and existing codes never change.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[repr(i32)]
 enum ContractError {
     /// Failed parsing the parameter.
-    #[from(ParseError)]
-    ParseParams,
+    ParseParams = -1,
     /// Failed logging: Log is full.
-    LogFull,
+    LogFull = -2,
     /// Failed logging: Log is malformed.
-    LogMalformed,
+    LogMalformed = -3,
     /// The transfer is not from the owner of the vote.
-    // FromIsNotTheOwner,
-    /// The voter already voted.
-    // AlreadyVoted,
+    FromIsNotTheOwner = -4,
+    /// The voter already voted, abstained or delegated and re-voting is
+    /// disabled.
+    AlreadyVoted = -25,
     /// The voter already has right to vote.
-    // AlreadyHasRightToVote,
-    /// The voter doesn't have right to vote.
-    // NoRightToVote,
+    AlreadyHasRightToVote = -26,
     /// Already finished.
-    AlreadyFinished,
+    AlreadyFinished = -5,
     /// exipred for voting.
-    // Expired,
+    Expired = -6,
     /// not exipred for tallying.
-    // NotExpired,
+    NotExpired = -7,
     /// Voter is not found.
-    VoterIsNotFound,
+    VoterIsNotFound = -8,
     /// Voter did not vote.
-    NotVoted,
+    NotVoted = -9,
     /// Proposal is not found.
-    ProposalIsNotFound,
+    ProposalIsNotFound = -10,
+    /// The delegation would create a cycle.
+    DelegationCycle = -11,
+    /// The maximum number of voters has been reached.
+    VoterLimitReached = -12,
+    /// The entrypoint does not match the voting mode of the contract.
+    WrongVotingMode = -13,
+    /// The revealed vote does not match the commitment.
+    CommitmentMismatch = -14,
+    /// The committed vote has already been revealed.
+    AlreadyRevealed = -15,
+    /// The new expiry is before the current time.
+    ExpiryInPast = -16,
+    /// The voter doesn't have right to vote.
+    NoRightToVote = -17,
+    /// The proposal has reached its vote cap; choose another proposal.
+    ProposalFull = -18,
+    /// Voting has not been opened yet.
+    VotingNotStarted = -19,
+    /// The votes have not been tallied yet.
+    NotYetTallied = -20,
+    /// A vote count or weight does not fit in a `u64`.
+    VoteCountOverflow = -21,
+    /// The proposal names and descriptions differ in length.
+    ProposalListMismatch = -22,
+    /// The tally can no longer be reopened.
+    CorrectionWindowClosed = -23,
+    /// The init parameter was built for another contract version.
+    UnsupportedVersion = -24,
+    /// A proposal's metadata URL exceeds `MAX_METADATA_URL_LENGTH`.
+    MetadataUrlTooLong = -27,
+    /// The delegation chain would exceed `MAX_DELEGATION_DEPTH` links.
+    DelegationTooDeep = -28,
+    /// Votes have already been cast.
+    VotingAlreadyStarted = -30,
+    /// Voters cannot add proposals to this poll.
+    WriteInsDisabled = -31,
+    /// Every proposal id is in use.
+    ProposalLimitReached = -32,
+    /// The owner is neutral and cannot vote in this poll.
+    OwnerCannotVote = -33,
+    /// The voter has used up `max_changes`.
+    TooManyChanges = -34,
+    /// The voter already cancelled the vote.
+    AlreadyCancelled = -35,
+    /// A right to vote must be granted with a positive weight.
+    InvalidWeight = -36,
+    /// A voter cannot delegate to itself.
+    SelfDelegation = -37,
+    /// An approval threshold needs exactly two proposals, no write-ins and at
+    /// most 10000 basis points.
+    InvalidApprovalThreshold = -38,
+    /// At least one seat is needed, and an approval threshold allows only one.
+    InvalidSeats = -39,
+    /// The poll has no proposals to vote for.
+    NoProposals = -40,
+    /// The title exceeds `MAX_TITLE_LENGTH`.
+    TitleTooLong = -41,
+    /// The description exceeds `MAX_DESCRIPTION_LENGTH`.
+    DescriptionTooLong = -42,
+    /// The poll has been sealed and can no longer change.
+    ContractSealed = -43,
+    /// Votes cannot be changed, cancelled or delegated in an
+    /// `aggregate_only` poll.
+    ChangesDisabled = -44,
+    /// Only the token contract can report balances.
+    NotTheTokenContract = -45,
+    /// The voter changed their vote less than `cooldown_ms` ago.
+    CooldownActive = -46,
+    /// The voters were removed with `clearVoters`.
+    VotersCleared = -47,
+    /// `aggregate_only` cannot be combined with `commit_reveal`, whose
+    /// reveals store each voter's choice.
+    IncompatibleModes = -48,
+    /// A proposal name exceeds `MAX_PROPOSAL_NAME_LENGTH`.
+    ProposalNameTooLong = -49,
+    /// The counted weights of the voters do not add up to the vote counts.
+    #[cfg(any(test, feature = "testing"))]
+    InvariantViolation = -29,
 }
 
 // [TODO]: ロギング用のイベントの定義をする。
@@ -98,13 +641,115 @@ enum ContractError {
 enum Event {
     GiveRightToVote {
         to: Address,
-        added_weight: u32,
-        total_weight: u32,
+        added_weight: u64,
+        total_weight: u64,
+    },
+    /// Head-to-head comparison of two proposals. `leader` is `None` on a tie.
+    ProposalComparison {
+        a: ProposalId,
+        a_vote_count: u64,
+        b: ProposalId,
+        b_vote_count: u64,
+        difference: u64,
+        leader: Option<ProposalId>,
+    },
+    /// The tally declared no winner because a finalization rule failed.
+    NoWinner {
+        failed_rule: FinalizationRule,
+    },
+    /// A ballot was cast by a voter without an active vote for a proposal.
+    /// `vote_count` is the proposal's count after the vote.
+    FirstVote {
+        voter: Address,
+        proposal_id: ProposalId,
+        vote_count: u64,
+    },
+    /// Weight was delegated. `weight` is the delegator's effective weight.
+    Delegated {
+        from: Address,
+        to: Address,
+        weight: u64,
+    },
+    /// A delegation was revoked by voting, abstaining or delegating again.
+    DelegationRevoked {
+        from: Address,
+        to: Address,
+        weight: u64,
+    },
+    /// The contract was created. Proposals that do not fit in a single log
+    /// entry are spread over several `Created` events, ordered by proposal id.
+    Created {
+        title: Title,
+        proposal_ids_and_names: Vec<(ProposalId, String)>,
+        expiry: Timestamp,
+    },
+    /// The current standings, ordered by proposal id.
+    Snapshot {
+        counts: Vec<(ProposalId, u64)>,
+        total: u64,
+    },
+    /// The owner finished the poll early.
+    ForceFinished {
+        reason: String,
+    },
+    /// A ballot was cast. `voter_hash` is the `sha256` of the serialized
+    /// voter address.
+    AnonymousVote {
+        voter_hash: HashSha256,
+        proposal_id: ProposalId,
+        vote_count: u64,
+    },
+    /// The right to vote was revoked. `removed_weight` is the voter's own
+    /// weight before the revocation.
+    RightRevoked {
+        from: Address,
+        removed_weight: u64,
+    },
+    /// A voter moved their ballot from one proposal to another, or cast it
+    /// again for the same one. `vote_count` is the new proposal's count after
+    /// the change.
+    VoteChanged {
+        voter: Address,
+        old_proposal_id: ProposalId,
+        new_proposal_id: ProposalId,
+        vote_count: u64,
+    },
+    /// Receipt for a ballot cast with `vote`, see `vote_receipt`.
+    VoteReceipt {
+        receipt: HashSha256,
+    },
+    /// `Delegated` with the `sha256` of the serialized addresses, logged
+    /// when `anonymize_events` is set.
+    AnonymousDelegated {
+        from_hash: HashSha256,
+        to_hash: HashSha256,
+        weight: u64,
+    },
+    /// `DelegationRevoked` with the `sha256` of the serialized addresses,
+    /// logged when `anonymize_events` is set.
+    AnonymousDelegationRevoked {
+        from_hash: HashSha256,
+        to_hash: HashSha256,
+        weight: u64,
     },
 }
 
 type ContractResult<A> = Result<A, ContractError>;
 
+impl From<ContractError> for Reject {
+    fn from(e: ContractError) -> Self {
+        Reject {
+            error_code: num::NonZeroI32::new(e as i32).unwrap(),
+        }
+    }
+}
+
+impl From<ParseError> for ContractError {
+    fn from(_: ParseError) -> Self {
+        Self::ParseParams
+    }
+}
+
 impl From<LogError> for ContractError {
     fn from(le: LogError) -> Self {
         match le {
@@ -118,40 +763,324 @@ impl From<LogError> for ContractError {
 enum Status {
     InProcess,
     Finished,
+    NotStarted,
+}
+
+/// A voter in the version 1 state layout.
+#[cfg_attr(test, derive(Serial))]
+#[derive(Deserial)]
+struct VoterStateV1 {
+    weight: u32,
+    voted: bool,
+    vote: ProposalId,
+}
+
+/// A proposal in the version 1 state layout.
+#[cfg_attr(test, derive(Serial))]
+#[derive(Deserial)]
+struct ProposalV1 {
+    name: String,
+    vote_count: u32,
+}
+
+/// The version 1 state layout with `u32` weights and vote counts.
+#[cfg_attr(test, derive(Serial))]
+#[derive(Deserial)]
+struct StateV1 {
+    voters: Map<Address, VoterStateV1>,
+    proposals: Map<ProposalId, ProposalV1>,
+    status: Status,
+    winning_proposal_id: Vec<ProposalId>,
+    title: Title,
+    description: Description,
+    expiry: Timestamp,
 }
 
 #[contract_state(contract = "govote_voting")]
-#[derive(Debug, Serialize, SchemaType, PartialEq)]
+#[derive(Debug, Serial, SchemaType, PartialEq)]
 struct State {
+    /// Layout version, always `STATE_VERSION` when written.
+    layout_version: u8,
     voters: Map<Address, VoterState>,
     proposals: Map<ProposalId, Proposal>,
     status: Status,
     winning_proposal_id: Vec<ProposalId>,
+    winning_vote_count: u64,
     title: Title,
     description: Description,
     expiry: Timestamp,
+    finalization_rules: FinalizationRules,
+    tie_break: TieBreak,
+    max_voters: Option<u32>,
+    commit_reveal: bool,
+    abstain_count: u32,
+    max_votes_per_proposal: Option<u64>,
+    /// The tally failed a finalization rule, e.g. the winner's margin over
+    /// the runner-up was below `min_margin`.
+    indecisive: bool,
+    correction_window: Duration,
+    /// When the votes were tallied.
+    finished_at: Option<Timestamp>,
+    anonymize_events: bool,
+    allow_revote: bool,
+    /// Number of voters granted the right to vote with `giveRightToVote`.
+    eligible_count: u32,
+    weight_policy: WeightPolicy,
+    allow_write_ins: bool,
+    owner_can_vote: bool,
+    max_changes: Option<u32>,
+    approval_threshold_bp: Option<u16>,
+    grace_period_ms: u64,
+    /// The account that created the contract. `None` for states written in
+    /// version 1, where only the contract owner records it.
+    owner: Option<AccountAddress>,
+    seats: u8,
+    /// The finished poll was sealed with `seal` and can no longer change.
+    sealed: bool,
+    aggregate_only: bool,
+    /// Addresses that voted in an `aggregate_only` poll. Their choices are
+    /// not stored.
+    aggregate_voters: Set<Address>,
+    token_weight: Option<TokenWeight>,
+    /// Votes waiting for the voter's token balance.
+    pending_votes: Map<Address, (ProposalId, VoteDirection)>,
+    cooldown_ms: u64,
+    /// The init parameters as supplied. `None` for states written in
+    /// version 1.
+    init_params: Option<InitParamsRecord>,
+    /// Only addresses with a voter entry can `vote`. Set if the init
+    /// parameters had an `eligible` allowlist.
+    allowlist_only: bool,
+    /// When voting opened, at init with `start_open` or with `openVoting`.
+    started_at: Option<Timestamp>,
+    /// The voters were removed with `clearVoters`, so the vote counts can no
+    /// longer be rebuilt from the ballots.
+    voters_cleared: bool,
+}
+
+impl Deserial for State {
+    /// Read a state in any supported layout, selected by its version byte.
+    fn deserial<R: Read>(source: &mut R) -> ParseResult<Self> {
+        let layout_version: u8 = source.get()?;
+        match layout_version {
+            1 => Ok(State::from_v1(source.get()?)),
+            STATE_VERSION => Ok(State {
+                layout_version,
+                voters: source.get()?,
+                proposals: source.get()?,
+                status: source.get()?,
+                winning_proposal_id: source.get()?,
+                winning_vote_count: source.get()?,
+                title: source.get()?,
+                description: source.get()?,
+                expiry: source.get()?,
+                finalization_rules: source.get()?,
+                tie_break: source.get()?,
+                max_voters: source.get()?,
+                commit_reveal: source.get()?,
+                abstain_count: source.get()?,
+                max_votes_per_proposal: source.get()?,
+                indecisive: source.get()?,
+                correction_window: source.get()?,
+                finished_at: source.get()?,
+                anonymize_events: source.get()?,
+                allow_revote: source.get()?,
+                eligible_count: source.get()?,
+                weight_policy: source.get()?,
+                allow_write_ins: source.get()?,
+                owner_can_vote: source.get()?,
+                max_changes: source.get()?,
+                approval_threshold_bp: source.get()?,
+                grace_period_ms: source.get()?,
+                owner: source.get()?,
+                seats: source.get()?,
+                sealed: source.get()?,
+                aggregate_only: source.get()?,
+                aggregate_voters: source.get()?,
+                token_weight: source.get()?,
+                pending_votes: source.get()?,
+                cooldown_ms: source.get()?,
+                init_params: source.get()?,
+                allowlist_only: source.get()?,
+                started_at: source.get()?,
+                voters_cleared: source.get()?,
+            }),
+            _ => Err(ParseError::default()),
+        }
+    }
 }
 
 impl State {
-    fn new(
-        title: Title,
-        description: Description,
-        proposal_names: ProposalNames,
-        expiry: Timestamp,
-    ) -> Self {
+    fn new(params: InitParams, owner: AccountAddress, now: Timestamp) -> Self {
         let mut proposals = Map::default();
-        for (i, proposal_name) in proposal_names.iter().enumerate() {
-            proposals.insert(i as ProposalId, Proposal::new(proposal_name.to_string()));
+        for (i, ((proposal_name, proposal_description), metadata_url)) in params
+            .proposal_names
+            .iter()
+            .zip(params.proposal_descriptions.iter())
+            .zip(params.proposal_metadata_urls.iter())
+            .enumerate()
+        {
+            proposals.insert(
+                i as ProposalId,
+                Proposal {
+                    group: params.proposal_groups.get(i).copied(),
+                    ..Proposal::new(
+                        proposal_name.to_string(),
+                        proposal_description.to_string(),
+                        metadata_url.clone(),
+                    )
+                },
+            );
         }
 
+        let init_params = InitParamsRecord {
+            title: params.title.clone(),
+            description: params.description.clone(),
+            proposal_names: params.proposal_names.clone(),
+            expiry: params.expiry,
+        };
         State {
+            layout_version: STATE_VERSION,
             voters: Map::default(),
             proposals,
-            status: Status::InProcess,
+            status: if params.start_open {
+                Status::InProcess
+            } else {
+                Status::NotStarted
+            },
             winning_proposal_id: vec![],
-            title,
-            description,
-            expiry,
+            winning_vote_count: 0,
+            title: params.title,
+            description: params.description,
+            expiry: params.expiry,
+            finalization_rules: params.finalization_rules,
+            tie_break: params.tie_break,
+            max_voters: params.max_voters,
+            commit_reveal: params.commit_reveal,
+            abstain_count: 0,
+            max_votes_per_proposal: params.max_votes_per_proposal,
+            indecisive: false,
+            correction_window: params.correction_window,
+            finished_at: None,
+            anonymize_events: params.anonymize_events,
+            allow_revote: params.allow_revote,
+            eligible_count: 0,
+            weight_policy: params.weight_policy,
+            allow_write_ins: params.allow_write_ins,
+            owner_can_vote: params.owner_can_vote,
+            max_changes: params.max_changes,
+            approval_threshold_bp: params.approval_threshold_bp,
+            grace_period_ms: params.grace_period_ms,
+            owner: Some(owner),
+            seats: params.seats,
+            sealed: false,
+            aggregate_only: params.aggregate_only,
+            aggregate_voters: Set::default(),
+            token_weight: params.token_weight,
+            pending_votes: Map::default(),
+            cooldown_ms: params.cooldown_ms,
+            init_params: Some(init_params),
+            allowlist_only: !params.eligible.is_empty(),
+            started_at: if params.start_open {
+                Some(now)
+            } else {
+                None
+            },
+            voters_cleared: false,
+        }
+    }
+
+    /// Widen a version 1 state to the current layout. Settings that did not
+    /// exist in version 1 get the values matching its behaviour.
+    fn from_v1(state: StateV1) -> Self {
+        let voters = state
+            .voters
+            .into_iter()
+            .map(|(address, voter)| {
+                let weight = u64::from(voter.weight);
+                let vote = if voter.voted {
+                    Some(voter.vote)
+                } else {
+                    None
+                };
+                (
+                    address,
+                    VoterState {
+                        weight,
+                        voted: voter.voted,
+                        vote,
+                        last_vote: vote,
+                        counted_weight: if voter.voted {
+                            weight
+                        } else {
+                            0
+                        },
+                        ..VoterState::default()
+                    },
+                )
+            })
+            .collect();
+        let proposals: Map<ProposalId, Proposal> = state
+            .proposals
+            .into_iter()
+            .map(|(proposal_id, p)| {
+                (
+                    proposal_id,
+                    Proposal {
+                        vote_count: u64::from(p.vote_count),
+                        ..Proposal::new(p.name, String::new(), None)
+                    },
+                )
+            })
+            .collect();
+        let winning_vote_count = state
+            .winning_proposal_id
+            .iter()
+            .filter_map(|proposal_id| proposals.get(proposal_id))
+            .map(|p| p.vote_count)
+            .max()
+            .unwrap_or(0);
+
+        State {
+            layout_version: STATE_VERSION,
+            voters,
+            proposals,
+            status: state.status,
+            winning_proposal_id: state.winning_proposal_id,
+            winning_vote_count,
+            title: state.title,
+            description: state.description,
+            expiry: state.expiry,
+            finalization_rules: FinalizationRules::default(),
+            tie_break: TieBreak::ReturnAll,
+            max_voters: None,
+            commit_reveal: false,
+            abstain_count: 0,
+            max_votes_per_proposal: None,
+            indecisive: false,
+            correction_window: Duration::from_millis(0),
+            finished_at: None,
+            anonymize_events: false,
+            allow_revote: true,
+            eligible_count: 0,
+            weight_policy: WeightPolicy::Flat,
+            allow_write_ins: false,
+            owner_can_vote: true,
+            max_changes: None,
+            approval_threshold_bp: None,
+            grace_period_ms: 0,
+            owner: None,
+            seats: 1,
+            sealed: false,
+            aggregate_only: false,
+            aggregate_voters: Set::default(),
+            token_weight: None,
+            pending_votes: Map::default(),
+            cooldown_ms: 0,
+            init_params: None,
+            allowlist_only: false,
+            started_at: None,
+            voters_cleared: false,
         }
     }
 
@@ -160,690 +1089,8737 @@ impl State {
         self.voters.get(voter_address)
     }
 
-    fn add_vote_count(&mut self, proposal_id: &ProposalId, weight: u32) {
-        let proposal = self.proposals.entry(*proposal_id).or_insert_with(Proposal::default);
-        proposal.vote_count += weight;
+    /// Get a proposal, or `ProposalIsNotFound` if the id is unknown.
+    fn require_proposal(&self, proposal_id: &ProposalId) -> ContractResult<&Proposal> {
+        self.proposals.get(proposal_id).ok_or(ContractError::ProposalIsNotFound)
     }
 
-    fn subtract_vote_count(&mut self, proposal_id: &ProposalId, weight: u32) {
-        let proposal = self.proposals.entry(*proposal_id).or_insert_with(Proposal::default);
-        proposal.vote_count -= weight;
+    /// Time left until voting closes at the end of the grace period,
+    /// saturating at zero once it has passed.
+    fn countdown(&self, now: Timestamp) -> Countdown {
+        let remaining =
+            self.closes_at().duration_since(now).unwrap_or_else(|| Duration::from_millis(0));
+        Countdown {
+            days: remaining.days(),
+            hours: (remaining.hours() % 24) as u8,
+            minutes: (remaining.minutes() % 60) as u8,
+            seconds: (remaining.seconds() % 60) as u8,
+        }
     }
-}
 
-/// Init function that creates a new contract.
-#[init(contract = "govote_voting", parameter = "InitParams")]
-fn contract_init(ctx: &impl HasInitContext) -> InitResult<State> {
-    let params: InitParams = ctx.parameter_cursor().get()?;
-    let state = State::new(params.title, params.description, params.proposal_names, params.expiry);
-    Ok(state)
-}
+    /// Whether any proposal has votes for or against it.
+    fn has_votes(&self) -> bool {
+        self.proposals.values().any(|p| p.vote_count > 0 || p.against_count > 0)
+    }
 
-/// Add right to vote.
-/// Only be called by owner.
-// #[receive(contract = "govote_voting", name = "giveRightToVote", parameter = "GetVoterParams")]
-// fn contract_give_right_to_vote<A: HasActions>(
-//     ctx: &impl HasReceiveContext,
-//     state: &mut State,
-// ) -> ContractResult<A> {
-//     let params: GetVoterParams = ctx.parameter_cursor().get()?;
-//     let owner = ctx.owner();
-//     let sender = ctx.sender();
+    /// Sum of the net scores of all proposals.
+    fn total_net_score(&self) -> u128 {
+        self.proposals.values().map(|p| u128::from(p.net_score())).sum()
+    }
 
-//     // 集計が終わってなければ実行できる。
-//     ensure!(state.status != Status::Finished, ContractError::AlreadyFinished);
+    /// Sum of the vote counts of all proposals.
+    fn total_votes(&self) -> ContractResult<u64> {
+        self.proposals.values().try_fold(0u64, |total, p| {
+            total.checked_add(p.vote_count).ok_or(ContractError::VoteCountOverflow)
+        })
+    }
 
-//     // expiryを超えていなければ実行できる。
-//     let slot_time = ctx.metadata().slot_time();
-//     ensure!(slot_time <= state.expiry, ContractError::Expired);
+    /// Whether ballots can be cast or changed at `now`: voting has been
+    /// opened, the votes have not been tallied and the expiry has not passed.
+    fn is_open(&self, now: Timestamp) -> bool {
+        self.status == Status::InProcess && !self.is_expired(now)
+    }
 
-//     // ownerだけが実行できる。
-//     ensure!(sender.matches_account(&owner), ContractError::FromIsNotTheOwner);
+    /// When voting closes: the expiry plus the grace period, saturating at
+    /// the largest timestamp.
+    fn closes_at(&self) -> Timestamp {
+        self.expiry
+            .checked_add(Duration::from_millis(self.grace_period_ms))
+            .unwrap_or_else(|| Timestamp::from_timestamp_millis(u64::MAX))
+    }
 
-//     // votersをアドレスから取得。なければvotersに新規追加。
-//     let voter_state =
-//         &mut state.voters.entry(params.voter_address).or_insert_with(VoterState::default);
+    /// Whether the expiry and the grace period have passed at `now`. Voting
+    /// is still possible at the end of the grace period itself.
+    fn is_expired(&self, now: Timestamp) -> bool {
+        self.closes_at() < now
+    }
 
-//     // 投票済みならエラー。
-//     ensure!(!voter_state.voted, ContractError::AlreadyVoted);
+    /// Ensure the ballot is open at `now`, rejecting with the reason if not.
+    fn ensure_open(&self, now: Timestamp) -> ContractResult<()> {
+        if self.is_open(now) {
+            return Ok(());
+        }
+        Err(match self.status {
+            Status::NotStarted => ContractError::VotingNotStarted,
+            Status::Finished => ContractError::AlreadyFinished,
+            Status::InProcess => ContractError::Expired,
+        })
+    }
 
-//     // weightが0以上でエラー。
-//     ensure!(voter_state.weight == 0, ContractError::AlreadyHasRightToVote);
+    /// Ensure the poll has not been sealed with `seal`.
+    fn ensure_unsealed(&self) -> ContractResult<()> {
+        ensure!(!self.sealed, ContractError::ContractSealed);
+        Ok(())
+    }
 
-//     voter_state.weight = 1;
+    /// The first reason the address cannot vote at `now`, if any.
+    /// Runs the checks `vote` runs before a proposal is picked, so a vote for
+    /// an existing proposal with room left succeeds exactly when this is
+    /// `None`.
+    fn voting_blocker(
+        &self,
+        voter_address: &Address,
+        owner: &AccountAddress,
+        now: Timestamp,
+    ) -> ContractResult<Option<&'static str>> {
+        let checks = self
+            .ensure_unsealed()
+            .and_then(|_| {
+                ensure!(!self.proposals.is_empty(), ContractError::NoProposals);
+                ensure!(!self.commit_reveal, ContractError::WrongVotingMode);
+                Ok(())
+            })
+            .and_then(|_| self.ensure_open(now))
+            .and_then(|_| self.ensure_owner_may_vote(voter_address, owner))
+            .and_then(|_| self.ensure_voter_capacity(voter_address))
+            .and_then(|_| self.ensure_right_to_vote(voter_address))
+            .and_then(|_| {
+                ensure!(
+                    !self.aggregate_voters.contains(voter_address),
+                    ContractError::ChangesDisabled
+                );
+                self.ensure_ballot_replaceable(voter_address)
+            })
+            .and_then(|_| self.ensure_vote_changeable(voter_address, now));
+        Ok(match checks {
+            Ok(()) => None,
+            Err(ContractError::ContractSealed) => Some("The poll has been sealed."),
+            Err(ContractError::NoProposals) => Some("There are no proposals to vote for."),
+            Err(ContractError::WrongVotingMode) => Some("Votes must be committed and revealed."),
+            Err(ContractError::VotingNotStarted) => Some("Voting has not started yet."),
+            Err(ContractError::AlreadyFinished) => Some("Voting has already finished."),
+            Err(ContractError::Expired) => Some("Voting has expired."),
+            Err(ContractError::OwnerCannotVote) => Some("The owner cannot vote in this poll."),
+            Err(ContractError::VoterLimitReached) => Some("The poll has reached its voter limit."),
+            Err(ContractError::NoRightToVote) if self.get_voter(voter_address).is_none() => {
+                Some("You are not on the list of eligible voters.")
+            }
+            Err(ContractError::NoRightToVote) => Some("Your right to vote has been revoked."),
+            Err(ContractError::ChangesDisabled) => Some("Votes cannot be changed in this poll."),
+            Err(ContractError::AlreadyVoted) => Some("You have already voted."),
+            Err(ContractError::TooManyChanges) => {
+                Some("You have changed your vote the maximum number of times.")
+            }
+            Err(ContractError::CooldownActive) => Some("Your last vote was cast too recently."),
+            Err(error) => return Err(error),
+        })
+    }
 
-//     // [TODO]: AddWeightログを追加
+    /// Ensure the address's vote for a proposal can be moved to another one at
+    /// `now`: the cooldown since the last vote has passed and `max_changes` is
+    /// not used up. Addresses that never voted for a proposal always can.
+    fn ensure_vote_changeable(
+        &self,
+        voter_address: &Address,
+        now: Timestamp,
+    ) -> ContractResult<()> {
+        if let Some(voter) = self.get_voter(voter_address) {
+            if voter.last_vote.is_some() {
+                // 前回の投票から待機時間が過ぎていれば変更できる。
+                if let Some(voted_at) = voter.last_voted_at {
+                    let elapsed = now.duration_since(voted_at).map(|d| d.millis()).unwrap_or(0);
+                    ensure!(elapsed >= self.cooldown_ms, ContractError::CooldownActive);
+                }
+                if let Some(max_changes) = self.max_changes {
+                    ensure!(voter.change_count < max_changes, ContractError::TooManyChanges);
+                }
+            }
+        }
+        Ok(())
+    }
 
-//     Ok(A::accept())
-// }
+    /// Ensure the address may replace its ballot: re-voting is enabled, or it
+    /// has not voted, abstained or delegated yet.
+    fn ensure_ballot_replaceable(&self, voter_address: &Address) -> ContractResult<()> {
+        let has_ballot = self
+            .get_voter(voter_address)
+            .map(|voter| voter.voted || voter.delegate.is_some())
+            .unwrap_or(false);
+        ensure!(self.allow_revote || !has_ballot, ContractError::AlreadyVoted);
+        Ok(())
+    }
 
-/// Vote to proposal.
-#[receive(contract = "govote_voting", name = "vote", parameter = "GetVoteParams")]
-fn contract_vote<A: HasActions>(
-    ctx: &impl HasReceiveContext,
-    state: &mut State,
-) -> ContractResult<A> {
-    let params: GetVoteParams = ctx.parameter_cursor().get()?;
-    let sender_address = ctx.sender();
+    /// Whether the address currently has an active vote.
+    fn has_voted(&self, voter_address: &Address) -> bool {
+        self.get_voter(voter_address).map(|a| a.voted).unwrap_or(false)
+    }
 
-    // proposalが存在すれば実行できる。
-    state.proposals.get_mut(&params.proposal_id).ok_or(ContractError::ProposalIsNotFound)?;
+    /// Add the weight to the proposal's vote count, or to its against count
+    /// for a ballot against it.
+    fn add_vote_count(
+        &mut self,
+        proposal_id: &ProposalId,
+        direction: VoteDirection,
+        weight: u64,
+        now: Timestamp,
+    ) -> ContractResult<()> {
+        let proposal =
+            self.proposals.get_mut(proposal_id).ok_or(ContractError::ProposalIsNotFound)?;
+        if direction == VoteDirection::Against {
+            proposal.against_count = proposal
+                .against_count
+                .checked_add(weight)
+                .ok_or(ContractError::VoteCountOverflow)?;
+            return Ok(());
+        }
+        proposal.vote_count =
+            proposal.vote_count.checked_add(weight).ok_or(ContractError::VoteCountOverflow)?;
+        if weight > 0 {
+            proposal.reached_at = Some(now);
+        }
+        Ok(())
+    }
 
-    // 集計が終わってなければ実行できる。
-    ensure!(state.status != Status::Finished, ContractError::AlreadyFinished);
+    fn subtract_vote_count(
+        &mut self,
+        proposal_id: &ProposalId,
+        direction: VoteDirection,
+        weight: u64,
+    ) -> ContractResult<()> {
+        let proposal =
+            self.proposals.get_mut(proposal_id).ok_or(ContractError::ProposalIsNotFound)?;
+        let count = match direction {
+            VoteDirection::For => &mut proposal.vote_count,
+            VoteDirection::Against => &mut proposal.against_count,
+        };
+        *count = count.checked_sub(weight).ok_or(ContractError::VoteCountOverflow)?;
+        Ok(())
+    }
 
-    // expiryを超えていなければ実行できる。
-    // let slot_time = ctx.metadata().slot_time();
-    // ensure!(slot_time <= state.expiry, ContractError::Expired);
-
-    if state.get_voter(&sender_address) != None {
-        // 投票済みならweight分のvote_countを引く
-        if state.get_voter(&sender_address).map(|a| a.voted) == Some(true) {
-            state.subtract_vote_count(
-                &state.get_voter(&sender_address).map(|a| a.vote).unwrap(),
-                state.get_voter(&sender_address).map(|a| a.weight).unwrap(),
-            );
+    /// The direct delegators of every address with at least one. Built with
+    /// a single scan of the voters, so following delegations through it
+    /// costs no further scans.
+    fn delegators(&self) -> Map<Address, Vec<Address>> {
+        let mut delegators: Map<Address, Vec<Address>> = Map::default();
+        for (delegator, voter) in self.voters.iter() {
+            if let Some(delegate) = voter.delegate {
+                delegators.entry(delegate).or_default().push(*delegator);
+            }
         }
-        // ensure!(
-        //     state.get_voter(&sender_address).map(|a| a.voted) == Some(false),
-        //     ContractError::AlreadyVoted
-        // );
+        delegators
     }
 
-    // ensure!(
-    //     state.get_voter(&sender_address).map(|a| a.weight) != Some(0),
-    //     ContractError::NoRightToVote
-    // );
+    /// Weight delegated to the address, directly or through a chain of
+    /// delegations, followed through the given `delegators` index.
+    fn delegated_weight(
+        &self,
+        delegators: &Map<Address, Vec<Address>>,
+        address: &Address,
+    ) -> ContractResult<u64> {
+        let mut total = 0u64;
+        let mut pending: Vec<&Address> = delegators.get(address).into_iter().flatten().collect();
+        while let Some(delegator) = pending.pop() {
+            let weight = self.get_voter(delegator).map(|a| a.weight).unwrap_or(0);
+            total = total.checked_add(weight).ok_or(ContractError::VoteCountOverflow)?;
+            pending.extend(delegators.get(delegator).into_iter().flatten());
+        }
+        Ok(total)
+    }
 
-    let voter_state = state.voters.entry(sender_address).or_insert_with(VoterState::default);
-    voter_state.voted = true;
-    voter_state.weight = 1;
-    voter_state.vote = params.proposal_id;
+    /// Number of links in the longest delegation chain ending at the address.
+    fn delegators_depth(&self, address: &Address) -> usize {
+        let delegators = self.delegators();
+        let mut depth = 0;
+        let mut level: Vec<&Address> = delegators.get(address).into_iter().flatten().collect();
+        while !level.is_empty() {
+            depth += 1;
+            level = level
+                .into_iter()
+                .flat_map(|delegator| delegators.get(delegator).into_iter().flatten())
+                .collect();
+        }
+        depth
+    }
 
-    state.add_vote_count(
-        &params.proposal_id,
-        state.get_voter(&sender_address).map(|a| a.weight).unwrap(),
-    );
+    /// Own weight of the address plus all weight delegated to it.
+    fn effective_weight(&self, address: &Address) -> ContractResult<u64> {
+        self.effective_weight_in(&self.delegators(), address)
+    }
 
-    Ok(A::accept())
-}
+    /// Like `effective_weight`, but resolved through a `delegators` index
+    /// built once by the caller, so weighing many voters costs one scan.
+    fn effective_weight_in(
+        &self,
+        delegators: &Map<Address, Vec<Address>>,
+        address: &Address,
+    ) -> ContractResult<u64> {
+        self.get_voter(address)
+            .map(|a| a.weight)
+            .unwrap_or(0)
+            .checked_add(self.delegated_weight(delegators, address)?)
+            .ok_or(ContractError::VoteCountOverflow)
+    }
 
-/// 集計
-#[receive(contract = "govote_voting", name = "winningProposal")]
-fn contract_winning_proposal<A: HasActions>(
-    _ctx: &impl HasReceiveContext,
-    state: &mut State,
-) -> ContractResult<A> {
-    let mut winning_vote_count = 0;
-    let mut winning_proposal_id = vec![];
+    /// The proposal the address's weight currently counts towards: its own
+    /// vote, or the vote at the end of its delegation chain.
+    fn counted_proposal(&self, address: &Address) -> Option<ProposalId> {
+        self.counted_root(address).map(|(_, proposal_id)| proposal_id)
+    }
 
-    // 集計が終わってなければ実行できる。
-    ensure!(state.status != Status::Finished, ContractError::AlreadyFinished);
+    /// The voter at the end of the address's delegation chain together with
+    /// its vote, if it has one.
+    fn counted_root(&self, address: &Address) -> Option<(Address, ProposalId)> {
+        let mut current = *address;
+        while let Some(voter) = self.get_voter(&current) {
+            match voter.delegate {
+                Some(next) => current = next,
+                None => return voter.vote.map(|proposal_id| (current, proposal_id)),
+            }
+        }
+        None
+    }
 
-    // expiryを超えていれば実行できる。
+    /// The event to log if the address's current delegation gets revoked,
+    /// hiding both addresses if `anonymize_events` is set.
+    fn delegation_revocation(&self, address: &Address) -> ContractResult<Option<Event>> {
+        let to = match self.get_voter(address).and_then(|a| a.delegate) {
+            Some(to) => to,
+            None => return Ok(None),
+        };
+        let weight = self.effective_weight(address)?;
+        if self.anonymize_events {
+            return Ok(Some(Event::AnonymousDelegationRevoked {
+                from_hash: sha256(&to_bytes(address)),
+                to_hash: sha256(&to_bytes(&to)),
+                weight,
+            }));
+        }
+        Ok(Some(Event::DelegationRevoked {
+            from: *address,
+            to,
+            weight,
+        }))
+    }
+
+    /// The event to log for the address's current delegation, hiding both
+    /// addresses if `anonymize_events` is set.
+    fn delegation_event(&self, address: &Address, to: Address) -> ContractResult<Event> {
+        let weight = self.effective_weight(address)?;
+        if self.anonymize_events {
+            return Ok(Event::AnonymousDelegated {
+                from_hash: sha256(&to_bytes(address)),
+                to_hash: sha256(&to_bytes(&to)),
+                weight,
+            });
+        }
+        Ok(Event::Delegated {
+            from: *address,
+            to,
+            weight,
+        })
+    }
+
+    /// Remove the address's weight from the proposal it counts towards. A
+    /// voter's own vote gives back exactly the weight it was counted with,
+    /// even if its weight changed since.
+    fn retract_weight(&mut self, address: &Address) -> ContractResult<()> {
+        if let Some((root, proposal_id)) = self.counted_root(address) {
+            let weight = if root == *address {
+                self.voters.get(&root).map(|a| a.counted_weight).unwrap_or(0)
+            } else {
+                self.ballot_weight(&root, self.effective_weight(address)?)?
+            };
+            let direction = self.voters[&root].direction;
+            self.subtract_vote_count(&proposal_id, direction, weight)?;
+            let voter = self.voters.get_mut(&root).unwrap();
+            voter.counted_weight =
+                voter.counted_weight.checked_sub(weight).ok_or(ContractError::VoteCountOverflow)?;
+        }
+        Ok(())
+    }
+
+    /// Replace the address's own weight, creating its voter entry if missing.
+    /// The old weight is taken off the proposal it counts towards before the
+    /// new one is added.
+    fn set_weight(&mut self, address: Address, weight: u64, now: Timestamp) -> ContractResult<()> {
+        self.retract_weight(&address)?;
+        self.voter_entry(address).weight = weight;
+        self.apply_weight(&address, now)
+    }
+
+    /// Add the address's effective weight to the proposal it counts towards.
+    fn apply_weight(&mut self, address: &Address, now: Timestamp) -> ContractResult<()> {
+        if let Some((root, proposal_id)) = self.counted_root(address) {
+            let weight = self.ballot_weight(&root, self.effective_weight(address)?)?;
+            let direction = self.voters[&root].direction;
+            self.add_vote_count(&proposal_id, direction, weight, now)?;
+            let voter = self.voters.get_mut(&root).unwrap();
+            voter.counted_weight =
+                voter.counted_weight.checked_add(weight).ok_or(ContractError::VoteCountOverflow)?;
+        }
+        Ok(())
+    }
+
+    /// The weight counted for the root's ballot under the weight policy,
+    /// based on when the root voted.
+    fn ballot_weight(&self, root: &Address, weight: u64) -> ContractResult<u64> {
+        self.decayed_weight(weight, self.get_voter(root).and_then(|a| a.voted_at))
+    }
+
+    /// The weight counted for a ballot cast at `voted_at` under the weight
+    /// policy. Only integer math is used, so the result is deterministic.
+    fn decayed_weight(&self, weight: u64, voted_at: Option<Timestamp>) -> ContractResult<u64> {
+        match self.weight_policy {
+            WeightPolicy::Flat => Ok(weight),
+            WeightPolicy::LinearDecay {
+                window,
+            } => {
+                let remaining = voted_at
+                    .and_then(|voted_at| self.expiry.duration_since(voted_at))
+                    .map(|d| d.millis())
+                    .unwrap_or(0);
+                let window = window.millis();
+                let scaled = if remaining >= window {
+                    u128::from(weight) * u128::from(DECAY_SCALE)
+                } else {
+                    u128::from(weight) * u128::from(DECAY_SCALE) * u128::from(remaining)
+                        / u128::from(window)
+                };
+                u64::try_from(scaled).map_err(|_| ContractError::VoteCountOverflow)
+            }
+        }
+    }
+
+    /// Rebuild every vote count from the voters' ballots. Each voter with a
+    /// vote counts its effective weight under the weight policy, and the
+    /// counted weights are reset to match. Proposals whose count did not grow
+    /// keep their `reached_at`.
+    fn recount(&mut self, now: Timestamp) -> ContractResult<()> {
+        let delegators = self.delegators();
+        let mut ballots = Vec::new();
+        for (address, voter) in self.voters.iter() {
+            let weight = match voter.vote {
+                Some(proposal_id) if voter.voted && self.proposals.contains_key(&proposal_id) => {
+                    let weight = self
+                        .ballot_weight(address, self.effective_weight_in(&delegators, address)?)?;
+                    Some((proposal_id, voter.direction, weight))
+                }
+                _ => None,
+            };
+            ballots.push((*address, weight));
+        }
+
+        let previous: Vec<(ProposalId, u64, Option<Timestamp>)> = self
+            .proposals
+            .iter()
+            .map(|(proposal_id, p)| (*proposal_id, p.vote_count, p.reached_at))
+            .collect();
+        for proposal in self.proposals.values_mut() {
+            proposal.vote_count = 0;
+            proposal.against_count = 0;
+        }
+        for (address, ballot) in ballots {
+            let voter = self.voters.get_mut(&address).unwrap();
+            voter.counted_weight = ballot.map(|(_, _, weight)| weight).unwrap_or(0);
+            if let Some((proposal_id, direction, weight)) = ballot {
+                self.add_vote_count(&proposal_id, direction, weight, now)?;
+            }
+        }
+        // 得票数が増えていなければ到達時刻は元のまま。
+        for (proposal_id, vote_count, reached_at) in previous {
+            let proposal = self.proposals.get_mut(&proposal_id).unwrap();
+            if proposal.vote_count <= vote_count {
+                proposal.reached_at = reached_at;
+            }
+        }
+        Ok(())
+    }
+
+    /// Withdraw the address's abstention, if it has one.
+    fn clear_abstention(&mut self, address: &Address) {
+        if let Some(voter) = self.voters.get_mut(address) {
+            if voter.abstained {
+                voter.abstained = false;
+                self.abstain_count -= 1;
+            }
+        }
+    }
+
+    /// The voter entry of the address, created with weight 1 if missing.
+    fn voter_entry(&mut self, voter_address: Address) -> &mut VoterState {
+        self.voters.entry(voter_address).or_insert_with(|| VoterState {
+            weight: 1,
+            ..VoterState::default()
+        })
+    }
+
+    /// Create a voter entry with the weight for an address without one and
+    /// count it as eligible. Returns the event to log.
+    fn grant_right_to_vote(
+        &mut self,
+        voter_address: Address,
+        weight: u64,
+    ) -> ContractResult<Event> {
+        // 投票者数の上限を超えなければ実行できる。
+        self.ensure_voter_capacity(&voter_address)?;
+
+        let voter_state = self.voter_entry(voter_address);
+        voter_state.weight = weight;
+        voter_state.granted = true;
+        self.eligible_count += 1;
+
+        Ok(Event::GiveRightToVote {
+            to: voter_address,
+            added_weight: weight,
+            total_weight: weight,
+        })
+    }
+
+    /// Ensure the address is not the owner of a poll with a neutral owner.
+    fn ensure_owner_may_vote(
+        &self,
+        voter_address: &Address,
+        owner: &AccountAddress,
+    ) -> ContractResult<()> {
+        ensure!(
+            self.owner_can_vote || !voter_address.matches_account(owner),
+            ContractError::OwnerCannotVote
+        );
+        Ok(())
+    }
+
+    /// Ensure the address's right to vote has not been revoked and, with an
+    /// `eligible` allowlist, that it was granted one.
+    fn ensure_right_to_vote(&self, voter_address: &Address) -> ContractResult<()> {
+        let voter = self.get_voter(voter_address);
+        ensure!(voter.map(|a| a.weight) != Some(0), ContractError::NoRightToVote);
+        // 許可リストがあれば、投票権のあるアドレスだけが投票できる。
+        ensure!(!self.allowlist_only || voter.is_some(), ContractError::NoRightToVote);
+        Ok(())
+    }
+
+    /// Ensure the address can get a voter entry without exceeding
+    /// `max_voters`. Existing voters always can.
+    fn ensure_voter_capacity(&self, voter_address: &Address) -> ContractResult<()> {
+        if let Some(max_voters) = self.max_voters {
+            ensure!(
+                self.voters.contains_key(voter_address) || self.voters.len() < max_voters as usize,
+                ContractError::VoterLimitReached
+            );
+        }
+        Ok(())
+    }
+
+    /// Ensure the proposal can take the address's vote without exceeding
+    /// `max_votes_per_proposal`. A vote that already counts towards the
+    /// proposal always can.
+    fn ensure_proposal_capacity(
+        &self,
+        voter_address: &Address,
+        proposal_id: &ProposalId,
+    ) -> ContractResult<()> {
+        if let Some(max_votes) = self.max_votes_per_proposal {
+            if self.counted_proposal(voter_address) != Some(*proposal_id) {
+                let weight = if self.voters.contains_key(voter_address) {
+                    self.effective_weight(voter_address)?
+                } else {
+                    1
+                };
+                let vote_count = self
+                    .require_proposal(proposal_id)?
+                    .vote_count
+                    .checked_add(weight)
+                    .ok_or(ContractError::VoteCountOverflow)?;
+                ensure!(vote_count <= max_votes, ContractError::ProposalFull);
+            }
+        }
+        Ok(())
+    }
+
+    /// Cast a ballot for `voter_address`, replacing any earlier vote or
+    /// delegation. Returns the proposal's new vote count.
+    fn cast_vote(
+        &mut self,
+        voter_address: Address,
+        proposal_id: ProposalId,
+        direction: VoteDirection,
+        now: Timestamp,
+    ) -> ContractResult<u64> {
+        // proposalが存在すれば実行できる。
+        self.require_proposal(&proposal_id)?;
+
+        // commit-revealモードではcommitVoteとrevealVoteを使う。
+        ensure!(!self.commit_reveal, ContractError::WrongVotingMode);
+
+        // 投票期間中なら実行できる。
+        self.ensure_open(now)?;
+
+        // 投票者数の上限を超えなければ実行できる。
+        self.ensure_voter_capacity(&voter_address)?;
+
+        // 投票権を取り消されていなければ実行できる。
+        self.ensure_right_to_vote(&voter_address)?;
+
+        // proposalの得票数の上限を超えなければ実行できる。
+        if direction == VoteDirection::For {
+            self.ensure_proposal_capacity(&voter_address, &proposal_id)?;
+        }
+
+        // 集計のみのモードでは投票者ごとの選択を記録しない。
+        if self.aggregate_only {
+            ensure!(
+                !self.aggregate_voters.contains(&voter_address),
+                ContractError::ChangesDisabled
+            );
+            let weight = self.get_voter(&voter_address).map(|a| a.weight).unwrap_or(1);
+            let weight = self.decayed_weight(weight, Some(now))?;
+            self.add_vote_count(&proposal_id, direction, weight, now)?;
+            self.aggregate_voters.insert(voter_address);
+            return Ok(self.require_proposal(&proposal_id)?.vote_count);
+        }
+
+        // 再投票が許可されていなければ、投票済みならエラー。
+        self.ensure_ballot_replaceable(&voter_address)?;
+
+        // 同じproposalへの同じ向きの再投票で重みも変わっていなければ何もしない。
+        if let Some(voter) = self.get_voter(&voter_address) {
+            if voter.voted
+                && voter.vote == Some(proposal_id)
+                && voter.direction == direction
+                && voter.delegate.is_none()
+                && voter.counted_weight == self.effective_weight(&voter_address)?
+            {
+                return Ok(self.require_proposal(&proposal_id)?.vote_count);
+            }
+        }
+
+        // 別のproposalに変えるなら変更回数の上限と待機時間を守れば実行できる。
+        // 取り消しや棄権を挟んでも前回の投票先と比べる。
+        if let Some(voter) = self.get_voter(&voter_address) {
+            if voter.last_vote.is_some() && voter.last_vote != Some(proposal_id) {
+                self.ensure_vote_changeable(&voter_address, now)?;
+                self.voters.get_mut(&voter_address).unwrap().change_count += 1;
+            }
+        }
+
+        if self.get_voter(&voter_address).is_some() {
+            // 投票済み、または委任済みならその分のvote_countを引く
+            self.retract_weight(&voter_address)?;
+        }
+
+        self.record_vote(voter_address, proposal_id, direction, now)
+    }
+
+    /// Record a validated vote and count it. Returns the proposal's new vote
+    /// count.
+    fn record_vote(
+        &mut self,
+        voter_address: Address,
+        proposal_id: ProposalId,
+        direction: VoteDirection,
+        now: Timestamp,
+    ) -> ContractResult<u64> {
+        self.clear_abstention(&voter_address);
+
+        let voter_state = self.voter_entry(voter_address);
+        voter_state.voted = true;
+        voter_state.vote = Some(proposal_id);
+        voter_state.last_vote = Some(proposal_id);
+        voter_state.last_voted_at = Some(now);
+        voter_state.direction = direction;
+        voter_state.voted_at = Some(now);
+        voter_state.cancelled = false;
+        // 直接投票すると委任は取り消される。
+        voter_state.delegate = None;
+
+        self.apply_weight(&voter_address, now)?;
+
+        Ok(self.require_proposal(&proposal_id)?.vote_count)
+    }
+
+    /// The stored outcome of the tally.
+    fn tally_result(&self) -> TallyResult {
+        TallyResult {
+            winning_proposal_id: self.winning_proposal_id.clone(),
+            winning_vote_count: self.winning_vote_count,
+            indecisive: self.indecisive,
+        }
+    }
+
+    /// Number of voters with an active vote. Voters whose right to vote was
+    /// revoked are not counted.
+    fn voted_count(&self) -> u32 {
+        self.voters.values().filter(|voter| voter.voted && voter.weight > 0).count() as u32
+    }
+
+    /// The outcome of a finished poll as a `FinalResult`.
+    fn final_result(&self) -> ContractResult<FinalResult> {
+        // 集計が終わっていれば実行できる。
+        ensure!(self.status == Status::Finished, ContractError::NotYetTallied);
+
+        let mut winning_proposal_ids = self.winning_proposal_id.clone();
+        winning_proposal_ids.sort();
+        let winning_names = winning_proposal_ids
+            .iter()
+            .filter_map(|proposal_id| self.proposals.get(proposal_id))
+            .map(|p| p.name.clone())
+            .collect();
+        let turnout_bp = if self.eligible_count == 0 {
+            0
+        } else {
+            (self.voted_count() as u64 * 10_000 / self.eligible_count as u64).min(10_000) as u16
+        };
+        Ok(FinalResult {
+            winning_proposal_ids,
+            winning_names,
+            winning_vote_count: self.winning_vote_count,
+            total_votes: self.total_votes()?,
+            turnout_bp,
+            shares: self.weight_shares()?,
+        })
+    }
+
+    /// Sum of the weights of the voters granted the right to vote, whether
+    /// they voted or not, in the units of the vote counts. Voters only get a
+    /// grant from `giveRightToVote`, `giveRightToVoteBatch` or the `eligible`
+    /// allowlist; entries created by voting in an open poll and revoked
+    /// voters do not count. Under `LinearDecay` a unit of weight counts
+    /// `DECAY_SCALE`, like a ballot cast before the decay window.
+    fn eligible_weight(&self) -> u128 {
+        let weight: u128 = self
+            .voters
+            .values()
+            .filter(|voter| voter.granted)
+            .map(|voter| u128::from(voter.weight))
+            .sum();
+        match self.weight_policy {
+            WeightPolicy::Flat => weight,
+            WeightPolicy::LinearDecay {
+                ..
+            } => weight * u128::from(DECAY_SCALE),
+        }
+    }
+
+    /// Each proposal's share of the cast and of the eligible weight, ordered
+    /// by proposal id. A few high-weight voters can win a large share of the
+    /// votes cast with a small share of the eligible weight. Votes from
+    /// voters without a grant can push a share past 100%; a share that does
+    /// not fit in a `u32` fails with `VoteCountOverflow`.
+    fn weight_shares(&self) -> ContractResult<Vec<WeightShare>> {
+        let eligible_weight = self.eligible_weight();
+        self.distribution()
+            .into_iter()
+            .map(|(proposal_id, cast_share_bp)| {
+                let vote_count = u128::from(self.proposals[&proposal_id].vote_count);
+                let eligible_share_bp = match (vote_count * 10_000).checked_div(eligible_weight) {
+                    Some(bp) => u32::try_from(bp).map_err(|_| ContractError::VoteCountOverflow)?,
+                    None => 0,
+                };
+                Ok(WeightShare {
+                    proposal_id,
+                    cast_share_bp,
+                    eligible_share_bp,
+                })
+            })
+            .collect()
+    }
+
+    /// The result the tally would declare now, without changing anything,
+    /// together with the failed finalization rule if there is no winner.
+    /// Proposals are ranked by their `net_score`.
+    fn compute_tally(&self) -> ContractResult<(TallyResult, Option<FinalizationRule>)> {
+        // 最多得票数、その得票数のproposalの数、次点の得票数を一度で求める。
+        let mut winning_vote_count = 0;
+        let mut leader_count = 0;
+        let mut runner_up_vote_count = 0;
+        for proposal in self.proposals.values() {
+            match proposal.net_score().cmp(&winning_vote_count) {
+                Ordering::Greater => {
+                    runner_up_vote_count = winning_vote_count;
+                    winning_vote_count = proposal.net_score();
+                    leader_count = 1;
+                }
+                Ordering::Equal => leader_count += 1,
+                Ordering::Less => {
+                    runner_up_vote_count = runner_up_vote_count.max(proposal.net_score())
+                }
+            }
+        }
+        // 同票の場合は次点も同じ得票数になる。
+        if leader_count > 1 {
+            runner_up_vote_count = winning_vote_count;
+        }
+
+        let leaders = self
+            .proposals
+            .iter()
+            .filter(|(_, p)| p.net_score() == winning_vote_count)
+            .map(|(proposal_id, _)| *proposal_id);
+        let winning_proposal_id = match self.tie_break {
+            // 複数の当選枠では最後の枠と同票のproposalもすべて当選する。
+            _ if self.seats > 1 => {
+                let mut vote_counts: Vec<u64> =
+                    self.proposals.values().map(|p| p.net_score()).collect();
+                vote_counts.sort_unstable_by(|a, b| b.cmp(a));
+                let cutoff = vote_counts
+                    .get(self.seats as usize - 1)
+                    .or_else(|| vote_counts.last())
+                    .copied()
+                    .unwrap_or(0);
+                let mut winners: Vec<ProposalId> = self
+                    .proposals
+                    .iter()
+                    .filter(|(_, p)| p.net_score() >= cutoff)
+                    .map(|(proposal_id, _)| *proposal_id)
+                    .collect();
+                winners.sort();
+                winners
+            }
+            TieBreak::ReturnAll => {
+                let mut winners = Vec::with_capacity(leader_count);
+                winners.extend(leaders);
+                winners
+            }
+            TieBreak::LowestId => leaders.min().into_iter().collect(),
+            TieBreak::AlphabeticalName => leaders
+                .min_by(|a, b| self.proposals[a].name.cmp(&self.proposals[b].name).then(a.cmp(b)))
+                .into_iter()
+                .collect(),
+            TieBreak::EarliestReached => leaders
+                .min_by_key(|proposal_id| {
+                    let reached_at = self.proposals[proposal_id].reached_at;
+                    (reached_at.map(|t| t.timestamp_millis()).unwrap_or(u64::MAX), *proposal_id)
+                })
+                .into_iter()
+                .collect(),
+        };
+
+        // 賛否を問うモードでは、1つ目のproposalが閾値に届かなければ2つ目が勝つ。
+        let (winning_proposal_id, winning_vote_count, runner_up_vote_count) =
+            match self.approval_threshold_bp {
+                Some(approval_threshold_bp) => {
+                    let yes = self.proposals.get(&0).map(|p| p.net_score()).unwrap_or(0);
+                    let no = self.proposals.get(&1).map(|p| p.net_score()).unwrap_or(0);
+                    let total = yes as u128 + no as u128;
+                    if total > 0 && yes as u128 * 10_000 >= approval_threshold_bp as u128 * total {
+                        (vec![0], yes, no)
+                    } else {
+                        (vec![1], no, yes)
+                    }
+                }
+                None => (winning_proposal_id, winning_vote_count, runner_up_vote_count),
+            };
+
+        let total_votes = self.total_votes()?;
+        let participation = total_votes
+            .checked_add(self.abstain_count as u64)
+            .ok_or(ContractError::VoteCountOverflow)?;
+        let failed_rule = self.finalization_rules.first_failed(
+            participation,
+            total_votes,
+            winning_vote_count,
+            runner_up_vote_count,
+        );
+        let result = if failed_rule.is_some() {
+            TallyResult {
+                winning_proposal_id: vec![],
+                winning_vote_count: 0,
+                indecisive: true,
+            }
+        } else {
+            TallyResult {
+                winning_proposal_id,
+                winning_vote_count,
+                indecisive: false,
+            }
+        };
+        Ok((result, failed_rule))
+    }
+
+    /// Tally the votes and finish the poll. Returns the failed finalization
+    /// rule if no winner could be declared.
+    fn tally(&mut self, now: Timestamp) -> ContractResult<Option<FinalizationRule>> {
+        // 合計があふれたら状態を変えずにエラーを返す。
+        let (result, failed_rule) = self.compute_tally()?;
+        self.status = Status::Finished;
+        self.finished_at = Some(now);
+        self.winning_proposal_id = result.winning_proposal_id;
+        self.winning_vote_count = result.winning_vote_count;
+        self.indecisive = result.indecisive;
+        Ok(failed_rule)
+    }
+
+    /// 1-based rank of the proposal by net score, as in the tally. Tied
+    /// proposals share the best rank, so the next one skips ahead, e.g.
+    /// 1, 1, 3.
+    fn rank(&self, proposal_id: &ProposalId) -> ContractResult<u32> {
+        let net_score = self.require_proposal(proposal_id)?.net_score();
+        let ahead = self.proposals.values().filter(|p| p.net_score() > net_score).count();
+        Ok(ahead as u32 + 1)
+    }
+
+    /// The smallest id of a proposal with exactly the given name. Names are
+    /// not indexed, so this scans every proposal.
+    fn proposal_id_by_name(&self, name: &str) -> Option<ProposalId> {
+        self.proposals
+            .iter()
+            .filter(|(_, p)| p.name == name)
+            .map(|(proposal_id, _)| *proposal_id)
+            .min()
+    }
+
+    /// The proposal with the given name, added with the next free id if
+    /// there is none and write-ins are allowed.
+    fn write_in(&mut self, name: String) -> ContractResult<ProposalId> {
+        if let Some(proposal_id) = self.proposal_id_by_name(&name) {
+            return Ok(proposal_id);
+        }
+
+        // write-inが許可されていれば新しいproposalを追加できる。
+        ensure!(self.allow_write_ins, ContractError::WriteInsDisabled);
+        ensure!(name.len() <= MAX_PROPOSAL_NAME_LENGTH, ContractError::ProposalNameTooLong);
+        let proposal_id = (0..=ProposalId::MAX)
+            .find(|proposal_id| !self.proposals.contains_key(proposal_id))
+            .ok_or(ContractError::ProposalLimitReached)?;
+        self.proposals.insert(proposal_id, Proposal::new(name, String::new(), None));
+        Ok(proposal_id)
+    }
+
+    /// The key proposal lists are sorted by: the display order, then the id.
+    fn display_key(&self, proposal_id: ProposalId) -> (u8, ProposalId) {
+        (self.proposals[&proposal_id].display_order, proposal_id)
+    }
+
+    /// Every proposal with its current vote count, in display order.
+    fn proposal_entries(&self) -> Vec<ProposalEntry> {
+        let mut entries: Vec<ProposalEntry> = self
+            .proposals
+            .iter()
+            .map(|(proposal_id, p)| ProposalEntry {
+                proposal_id: *proposal_id,
+                name: p.name.clone(),
+                vote_count: p.vote_count,
+            })
+            .collect();
+        entries.sort_by_key(|entry| self.display_key(entry.proposal_id));
+        entries
+    }
+
+    /// The proposals in the group with their current vote counts, in display
+    /// order.
+    fn group_entries(&self, group: u8) -> Vec<ProposalEntry> {
+        let mut entries: Vec<ProposalEntry> = self
+            .proposals
+            .iter()
+            .filter(|(_, p)| p.group == Some(group))
+            .map(|(proposal_id, p)| ProposalEntry {
+                proposal_id: *proposal_id,
+                name: p.name.clone(),
+                vote_count: p.vote_count,
+            })
+            .collect();
+        entries.sort_by_key(|entry| self.display_key(entry.proposal_id));
+        entries
+    }
+
+    /// Up to `limit` proposals with an id of at least `start`, ordered by
+    /// proposal id. The limit is clamped to `MAX_PROPOSALS_PAGE_SIZE`.
+    fn proposals_page(&self, start: ProposalId, limit: u8) -> Vec<ProposalEntry> {
+        let mut entries: Vec<ProposalEntry> = self
+            .proposals
+            .iter()
+            .filter(|(proposal_id, _)| **proposal_id >= start)
+            .map(|(proposal_id, p)| ProposalEntry {
+                proposal_id: *proposal_id,
+                name: p.name.clone(),
+                vote_count: p.vote_count,
+            })
+            .collect();
+        entries.sort_by_key(|entry| entry.proposal_id);
+        entries.truncate(limit.min(MAX_PROPOSALS_PAGE_SIZE) as usize);
+        entries
+    }
+
+    /// Each proposal's share of the net scores in basis points, ordered by
+    /// proposal id. The shares are rounded with the largest remainder method
+    /// so that they sum to 10000, or are all zero if no proposal has a
+    /// positive net score.
+    fn distribution(&self) -> Vec<(ProposalId, u32)> {
+        let mut counts: Vec<(ProposalId, u64)> =
+            self.proposals.iter().map(|(proposal_id, p)| (*proposal_id, p.net_score())).collect();
+        counts.sort();
+        let total = counts.iter().map(|(_, count)| *count as u128).sum::<u128>();
+        if total == 0 {
+            return counts.into_iter().map(|(proposal_id, _)| (proposal_id, 0)).collect();
+        }
+
+        let mut shares: Vec<(ProposalId, u32)> = Vec::with_capacity(counts.len());
+        let mut remainders: Vec<(u128, usize)> = Vec::with_capacity(counts.len());
+        for (i, (proposal_id, count)) in counts.iter().enumerate() {
+            let scaled = *count as u128 * 10_000;
+            shares.push((*proposal_id, (scaled / total) as u32));
+            remainders.push((scaled % total, i));
+        }
+        // 端数の大きい順に、同じなら小さいidから1ずつ配る。
+        remainders.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+        let assigned: u32 = shares.iter().map(|(_, share)| share).sum();
+        for (_, i) in remainders.iter().take((10_000 - assigned) as usize) {
+            shares[*i].1 += 1;
+        }
+        shares
+    }
+
+    /// Each proposal's vote count relative to the eligible weight in basis
+    /// points, in display order. Both are weighted, see `eligible_weight`.
+    /// Without eligibility tracking, i.e. no right granted, the share of the
+    /// votes from `distribution` is used instead. A participation that does
+    /// not fit in a `u32` fails with `VoteCountOverflow`.
+    fn participation(&self) -> ContractResult<Vec<ProposalParticipation>> {
+        let eligible_weight = self.eligible_weight();
+        let mut shares = self.distribution();
+        shares.sort_by_key(|(proposal_id, _)| self.display_key(*proposal_id));
+        shares
+            .into_iter()
+            .map(|(proposal_id, share)| {
+                let vote_count = self.proposals[&proposal_id].vote_count;
+                let participation_bp = match (u128::from(vote_count) * 10_000)
+                    .checked_div(eligible_weight)
+                {
+                    Some(bp) => u32::try_from(bp).map_err(|_| ContractError::VoteCountOverflow)?,
+                    None => share,
+                };
+                Ok(ProposalParticipation {
+                    proposal_id,
+                    vote_count,
+                    participation_bp,
+                })
+            })
+            .collect()
+    }
+
+    /// The proposal the address currently votes for, if any.
+    fn current_vote(&self, voter_address: &Address) -> Option<ProposalId> {
+        self.get_voter(voter_address).filter(|voter| voter.voted).and_then(|voter| voter.vote)
+    }
+
+    /// The event to log for a ballot, hiding the voter's address if
+    /// `anonymize_events` is set. `previous` is the proposal the voter voted
+    /// for before the ballot, if any.
+    fn vote_event(
+        &self,
+        voter: Address,
+        previous: Option<ProposalId>,
+        proposal_id: ProposalId,
+        vote_count: u64,
+    ) -> Event {
+        if self.anonymize_events {
+            return Event::AnonymousVote {
+                voter_hash: sha256(&to_bytes(&voter)),
+                proposal_id,
+                vote_count,
+            };
+        }
+        match previous {
+            Some(old_proposal_id) => Event::VoteChanged {
+                voter,
+                old_proposal_id,
+                new_proposal_id: proposal_id,
+                vote_count,
+            },
+            None => Event::FirstVote {
+                voter,
+                proposal_id,
+                vote_count,
+            },
+        }
+    }
+
+    /// Voters with an active vote for the proposal, sorted by address.
+    fn proposal_voters(&self, proposal_id: &ProposalId) -> Vec<Address> {
+        let mut voters: Vec<Address> = self
+            .voters
+            .iter()
+            .filter(|(_, voter)| voter.voted && voter.vote.as_ref() == Some(proposal_id))
+            .map(|(address, _)| *address)
+            .collect();
+        voters.sort();
+        voters
+    }
+
+    /// A page of voters, sorted by address.
+    fn voters_page(&self, start: u32, limit: u32) -> Vec<(Address, VoterState)> {
+        let mut voters: Vec<(&Address, &VoterState)> = self.voters.iter().collect();
+        voters.sort_by(|a, b| a.0.cmp(b.0));
+        voters
+            .into_iter()
+            .skip(start as usize)
+            .take(limit as usize)
+            .map(|(address, voter)| (*address, voter.clone()))
+            .collect()
+    }
+
+    /// Draw a deterministic pseudo-random sample of up to `count` voters.
+    ///
+    /// The voter addresses are sorted, and a partial Fisher-Yates shuffle
+    /// driven by a xorshift64 generator seeded with `seed` moves the sampled
+    /// addresses to the front. The same voters and seed always give the same
+    /// sample, so an auditor can reproduce it off-chain.
+    fn sample_voters(&self, count: u32, seed: u64) -> Vec<Address> {
+        let mut addresses: Vec<Address> = self.voters.keys().copied().collect();
+        addresses.sort();
+        let count = (count as usize).min(addresses.len());
+
+        // xorshift64 must not start from zero.
+        let mut rng = seed ^ 0x9E37_79B9_7F4A_7C15;
+        if rng == 0 {
+            rng = 0x9E37_79B9_7F4A_7C15;
+        }
+        for i in 0..count {
+            rng ^= rng << 13;
+            rng ^= rng >> 7;
+            rng ^= rng << 17;
+            let j = i + (rng % (addresses.len() - i) as u64) as usize;
+            addresses.swap(i, j);
+        }
+        addresses.truncate(count);
+        addresses
+    }
+}
+
+/// Init function that creates a new contract.
+/// The parameter must have version `CONTRACT_VERSION`. The expiry must be
+/// after the current time, and every proposal needs a description. Proposal
+/// names are limited to `MAX_PROPOSAL_NAME_LENGTH` bytes. The ballot is
+/// logged as `Event::Created`, split over several entries if needed.
+#[init(contract = "govote_voting", parameter = "InitParams", enable_logger)]
+fn contract_init(ctx: &impl HasInitContext, logger: &mut impl HasLogger) -> ContractResult<State> {
+    let params: InitParams = ctx.parameter_cursor().get()?;
+
+    // 対応するバージョンのパラメータだけを受け付ける。
+    ensure!(params.version == CONTRACT_VERSION, ContractError::UnsupportedVersion);
+
+    // 過去の日時は期限にできない。
+    ensure!(ctx.metadata().slot_time() < params.expiry, ContractError::ExpiryInPast);
+
+    // タイトルと説明は文字数の上限以下でなければならない。
+    ensure!(params.title.chars().count() <= MAX_TITLE_LENGTH, ContractError::TitleTooLong);
+    ensure!(
+        params.description.chars().count() <= MAX_DESCRIPTION_LENGTH,
+        ContractError::DescriptionTooLong
+    );
+
+    // proposalごとに説明が必要。
+    ensure!(
+        params.proposal_names.len() == params.proposal_descriptions.len()
+            && params.proposal_names.len() == params.proposal_metadata_urls.len()
+            && (params.proposal_groups.is_empty()
+                || params.proposal_names.len() == params.proposal_groups.len()),
+        ContractError::ProposalListMismatch
+    );
+
+    // 賛否を問うモードではproposalはちょうど2つ。
+    if let Some(approval_threshold_bp) = params.approval_threshold_bp {
+        ensure!(
+            params.proposal_names.len() == 2
+                && !params.allow_write_ins
+                && approval_threshold_bp <= 10_000,
+            ContractError::InvalidApprovalThreshold
+        );
+    }
+
+    // 定数は1以上、賛否を問うモードでは1つだけ。
+    ensure!(
+        params.seats >= 1 && (params.approval_threshold_bp.is_none() || params.seats == 1),
+        ContractError::InvalidSeats
+    );
+
+    // proposalの名前は一つのログに収まる長さでなければならない。
+    ensure!(
+        params.proposal_names.iter().all(|name| name.len() <= MAX_PROPOSAL_NAME_LENGTH),
+        ContractError::ProposalNameTooLong
+    );
+
+    // メタデータのURLは長さの上限以下でなければならない。
+    ensure!(
+        params
+            .proposal_metadata_urls
+            .iter()
+            .flatten()
+            .all(|metadata_url| metadata_url.len() <= MAX_METADATA_URL_LENGTH),
+        ContractError::MetadataUrlTooLong
+    );
+
+    // 集計のみのモードでは公開した投票を記録するcommit-revealは使えない。
+    ensure!(!(params.aggregate_only && params.commit_reveal), ContractError::IncompatibleModes);
+
+    let eligible = params.eligible.clone();
+    let mut state = State::new(params, ctx.init_origin(), ctx.metadata().slot_time());
+
+    // 許可リストのアドレスに重み1の投票権を与える。
+    for address in eligible {
+        if !state.voters.contains_key(&address) {
+            state.grant_right_to_vote(address, 1)?;
+        }
+    }
+
+    let created = |proposal_ids_and_names| Event::Created {
+        title: state.title.clone(),
+        proposal_ids_and_names,
+        expiry: state.expiry,
+    };
+    let mut proposals: Vec<(ProposalId, String)> =
+        state.proposals.iter().map(|(proposal_id, p)| (*proposal_id, p.name.clone())).collect();
+    proposals.sort();
+
+    // 一つのログに収まるようにproposalを分けて記録する。
+    let base_size = to_bytes(&created(Vec::new())).len();
+    let mut size = base_size;
+    let mut chunk = Vec::new();
+    for (proposal_id, name) in proposals {
+        // idが1バイト、名前が長さ4バイトと本体。
+        let entry_size = 1 + 4 + name.len();
+        if !chunk.is_empty() && size + entry_size > constants::MAX_LOG_SIZE {
+            logger.log(&created(core::mem::take(&mut chunk)))?;
+            size = base_size;
+        }
+        size += entry_size;
+        chunk.push((proposal_id, name));
+    }
+    logger.log(&created(chunk))?;
+
+    Ok(state)
+}
+
+/// Add right to vote.
+/// Only be called by owner.
+/// Creates a voter entry with the given weight and counts it as eligible for
+/// `getTurnout`. Logs `Event::GiveRightToVote`.
+#[receive(
+    contract = "govote_voting",
+    name = "giveRightToVote",
+    parameter = "GiveRightToVoteParams",
+    enable_logger
+)]
+fn contract_give_right_to_vote<A: HasActions>(
+    ctx: &impl HasReceiveContext,
+    logger: &mut impl HasLogger,
+    state: &mut State,
+) -> ContractResult<A> {
+    // 封印されていなければ実行できる。
+    state.ensure_unsealed()?;
+
+    let params: GiveRightToVoteParams = ctx.parameter_cursor().get()?;
+    let voter_address = params.voter_address;
+
+    // ownerだけが実行できる。
+    ensure!(ctx.sender().matches_account(&ctx.owner()), ContractError::FromIsNotTheOwner);
+
+    // 集計が終わってなければ実行できる。
+    ensure!(state.status != Status::Finished, ContractError::AlreadyFinished);
+
+    // 重みは0より大きくなければならない。
+    ensure!(params.weight > 0, ContractError::InvalidWeight);
+
+    // votersに登録済みならエラー。
+    ensure!(state.get_voter(&voter_address).is_none(), ContractError::AlreadyHasRightToVote);
+
+    logger.log(&state.grant_right_to_vote(voter_address, params.weight)?)?;
+
+    Ok(A::accept())
+}
+
+/// Add right to vote for several addresses.
+/// Only be called by owner.
+/// Every address is granted weight 1. Addresses that already have a voter
+/// entry, including repeats within the batch, are skipped rather than
+/// failing the batch. Logs one
+/// `Event::GiveRightToVote` per new grant, so a batch is limited by the
+/// number of log entries a transaction may produce.
+#[receive(
+    contract = "govote_voting",
+    name = "giveRightToVoteBatch",
+    parameter = "GiveRightToVoteBatchParams",
+    enable_logger
+)]
+fn contract_give_right_to_vote_batch<A: HasActions>(
+    ctx: &impl HasReceiveContext,
+    logger: &mut impl HasLogger,
+    state: &mut State,
+) -> ContractResult<A> {
+    // 封印されていなければ実行できる。
+    state.ensure_unsealed()?;
+
+    let params: GiveRightToVoteBatchParams = ctx.parameter_cursor().get()?;
+
+    // ownerだけが実行できる。
+    ensure!(ctx.sender().matches_account(&ctx.owner()), ContractError::FromIsNotTheOwner);
+
+    // 集計が終わってなければ実行できる。
+    ensure!(state.status != Status::Finished, ContractError::AlreadyFinished);
+
+    for voter_address in params.voters {
+        // votersに登録済みなら飛ばす。
+        if state.get_voter(&voter_address).is_some() {
+            continue;
+        }
+        logger.log(&state.grant_right_to_vote(voter_address, 1)?)?;
+    }
+
+    Ok(A::accept())
+}
+
+/// The receipt for a ballot: the `sha256` of the serialized voter address,
+/// proposal id, counted weight and slot time, concatenated in that order.
+fn vote_receipt(
+    voter: &Address,
+    proposal_id: ProposalId,
+    weight: u64,
+    slot_time: Timestamp,
+) -> HashSha256 {
+    let mut preimage = to_bytes(voter);
+    preimage.extend(to_bytes(&proposal_id));
+    preimage.extend(to_bytes(&weight));
+    preimage.extend(to_bytes(&slot_time));
+    sha256(&preimage)
+}
+
+/// Cast the voter's ballot and log it like `vote` does.
+fn vote_and_log(
+    state: &mut State,
+    logger: &mut impl HasLogger,
+    voter: Address,
+    proposal_id: ProposalId,
+    direction: VoteDirection,
+    slot_time: Timestamp,
+) -> ContractResult<()> {
+    let previous = state.current_vote(&voter);
+    let revocation = state.delegation_revocation(&voter)?;
+    let vote_count = state.cast_vote(voter, proposal_id, direction, slot_time)?;
+    if let Some(event) = revocation {
+        logger.log(&event)?;
+    }
+    log_vote(state, logger, voter, previous, proposal_id, vote_count, slot_time)
+}
+
+/// Log a recorded ballot: its vote event followed by `Event::VoteReceipt`.
+/// Nothing is logged in an `aggregate_only` poll, where both would pair the
+/// voter with the proposal.
+fn log_vote(
+    state: &State,
+    logger: &mut impl HasLogger,
+    voter: Address,
+    previous: Option<ProposalId>,
+    proposal_id: ProposalId,
+    vote_count: u64,
+    slot_time: Timestamp,
+) -> ContractResult<()> {
+    // 集計のみのモードでは誰がどれに投票したかを記録しない。
+    if state.aggregate_only {
+        return Ok(());
+    }
+
+    logger.log(&state.vote_event(voter, previous, proposal_id, vote_count))?;
+
+    // 投票者が後で検証できるように受領証を記録する。
+    let weight = state.get_voter(&voter).map(|voter| voter.counted_weight).unwrap_or(0);
+    logger.log(&Event::VoteReceipt {
+        receipt: vote_receipt(&voter, proposal_id, weight, slot_time),
+    })?;
+    Ok(())
+}
+
+/// Vote to proposal.
+/// A vote `Against` the proposal counts towards its `against_count`, which
+/// the tally subtracts from its vote count.
+/// With an `eligible` allowlist at init, only voters with the right to vote
+/// can vote, abstain, delegate or commit; others are rejected with
+/// `NoRightToVote`.
+/// The proposal's new vote count is logged as `Event::FirstVote`, or as
+/// `Event::VoteChanged` if the sender already voted, since receive functions
+/// cannot return values. It is followed by `Event::VoteReceipt`, which the
+/// voter can keep and later check against a recomputed `vote_receipt`.
+/// In an `aggregate_only` poll neither is logged.
+/// With `token_weight`, the vote is only recorded here and the token
+/// contract's `balanceOf` is queried. The vote is cast in `receiveBalance`
+/// with the balance as the voter's weight.
+#[receive(contract = "govote_voting", name = "vote", parameter = "GetVoteParams", enable_logger)]
+fn contract_vote<A: HasActions>(
+    ctx: &impl HasReceiveContext,
+    logger: &mut impl HasLogger,
+    state: &mut State,
+) -> ContractResult<A> {
+    // 封印されていなければ実行できる。
+    state.ensure_unsealed()?;
+
+    let params: GetVoteParams = ctx.parameter_cursor().get()?;
+    let sender_address = ctx.sender();
+
+    // proposalが一つもなければ投票できない。
+    ensure!(!state.proposals.is_empty(), ContractError::NoProposals);
+
+    // ownerが中立なら投票できない。
+    state.ensure_owner_may_vote(&sender_address, &ctx.owner())?;
+
+    // 投票権を取り消されていなければ実行できる。
+    state.ensure_right_to_vote(&sender_address)?;
+
+    let slot_time = ctx.metadata().slot_time();
+    if let Some(token_weight) = state.token_weight.clone() {
+        // 残高が届く前に分かるエラーはここで返す。
+        state.require_proposal(&params.proposal_id)?;
+        state.ensure_open(slot_time)?;
+
+        // 残高を問い合わせ、receiveBalanceで投票する。
+        state.pending_votes.insert(sender_address, (params.proposal_id, params.direction));
+        let query = BalanceOfQueryParams {
+            queries: vec![BalanceOfQuery {
+                token_id: token_weight.token_id,
+                address: sender_address,
+            }],
+            callback: OwnedReceiveName::new_unchecked(BALANCE_CALLBACK.to_string()),
+        };
+        return Ok(A::send_raw(
+            &token_weight.contract,
+            token_weight.balance_of.as_ref(),
+            Amount::zero(),
+            &to_bytes(&query),
+        ));
+    }
+
+    vote_and_log(state, logger, sender_address, params.proposal_id, params.direction, slot_time)?;
+
+    Ok(A::accept())
+}
+
+/// Receive token balances for votes waiting for them.
+/// Only be called by the `token_weight` contract, in response to the
+/// `balanceOf` query sent by `vote`. Each balance becomes the voter's weight
+/// and the waiting vote is cast and logged like with `vote`. A zero balance
+/// rejects with `NoRightToVote`. Weight the voter already counts, through
+/// an earlier vote or a delegation, is taken off at the old weight first.
+/// The response follows CIS-1, with `u64` amounts.
+#[receive(
+    contract = "govote_voting",
+    name = "receiveBalance",
+    parameter = "BalanceOfQueryResponse",
+    enable_logger
+)]
+fn contract_receive_balance<A: HasActions>(
+    ctx: &impl HasReceiveContext,
+    logger: &mut impl HasLogger,
+    state: &mut State,
+) -> ContractResult<A> {
+    // 封印されていなければ実行できる。
+    state.ensure_unsealed()?;
+
+    let params: BalanceOfQueryResponse = ctx.parameter_cursor().get()?;
+
+    // tokenのコントラクトだけが実行できる。
+    let token_contract = state.token_weight.as_ref().map(|token_weight| token_weight.contract);
+    ensure!(
+        token_contract.map(Address::Contract) == Some(ctx.sender()),
+        ContractError::NotTheTokenContract
+    );
+
+    let slot_time = ctx.metadata().slot_time();
+    for (query, balance) in params.balances {
+        let voter = query.address;
+        let (proposal_id, direction) =
+            state.pending_votes.remove(&voter).ok_or(ContractError::VoterIsNotFound)?;
+
+        // 残高がなければ投票できない。
+        ensure!(balance > 0, ContractError::NoRightToVote);
+
+        // 投票者数の上限を超えなければ実行できる。
+        state.ensure_voter_capacity(&voter)?;
+
+        // 投票権を取り消されていなければ実行できる。
+        state.ensure_right_to_vote(&voter)?;
+
+        state.set_weight(voter, balance, slot_time)?;
+        vote_and_log(state, logger, voter, proposal_id, direction, slot_time)?;
+    }
+
+    Ok(A::accept())
+}
+
+/// Vote to a proposal by name.
+/// If no proposal has the name and `allow_write_ins` is set, a proposal with
+/// the name is added first. The vote is logged like with `vote`.
+#[receive(
+    contract = "govote_voting",
+    name = "voteWriteIn",
+    parameter = "WriteInParams",
+    enable_logger
+)]
+fn contract_vote_write_in<A: HasActions>(
+    ctx: &impl HasReceiveContext,
+    logger: &mut impl HasLogger,
+    state: &mut State,
+) -> ContractResult<A> {
+    // 封印されていなければ実行できる。
+    state.ensure_unsealed()?;
+
+    let params: WriteInParams = ctx.parameter_cursor().get()?;
+    let sender_address = ctx.sender();
+
+    // tokenの重みは残高を問い合わせるvoteでしか数えられない。
+    ensure!(state.token_weight.is_none(), ContractError::WrongVotingMode);
+
+    // ownerが中立なら投票できない。
+    state.ensure_owner_may_vote(&sender_address, &ctx.owner())?;
+
+    let proposal_id = state.write_in(params.name)?;
+    let slot_time = ctx.metadata().slot_time();
+    vote_and_log(state, logger, sender_address, proposal_id, VoteDirection::For, slot_time)?;
+
+    Ok(A::accept())
+}
+
+/// Commit to a hidden vote in commit-reveal mode.
+/// The commitment is the `sha256` of the serialized `(proposal_id, nonce)`
+/// pair. It can be replaced until the expiry and is revealed afterwards with
+/// `revealVote`.
+#[receive(contract = "govote_voting", name = "commitVote", parameter = "CommitVoteParams")]
+fn contract_commit_vote<A: HasActions>(
+    ctx: &impl HasReceiveContext,
+    state: &mut State,
+) -> ContractResult<A> {
+    // 封印されていなければ実行できる。
+    state.ensure_unsealed()?;
+
+    let params: CommitVoteParams = ctx.parameter_cursor().get()?;
+    let sender_address = ctx.sender();
+
+    ensure!(state.commit_reveal, ContractError::WrongVotingMode);
+
+    // tokenの重みは残高を問い合わせるvoteでしか数えられない。
+    ensure!(state.token_weight.is_none(), ContractError::WrongVotingMode);
+
+    // ownerが中立なら投票できない。
+    state.ensure_owner_may_vote(&sender_address, &ctx.owner())?;
+
+    // 投票期間中なら実行できる。
+    state.ensure_open(ctx.metadata().slot_time())?;
+
+    // 投票者数の上限を超えなければ実行できる。
+    state.ensure_voter_capacity(&sender_address)?;
+
+    // 投票権を取り消されていなければ実行できる。
+    state.ensure_right_to_vote(&sender_address)?;
+
+    let voter_state = state.voter_entry(sender_address);
+    ensure!(!voter_state.voted, ContractError::AlreadyRevealed);
+    voter_state.commitment = Some(params.commitment);
+
+    Ok(A::accept())
+}
+
+/// Reveal a committed vote after the expiry and count it.
+/// The vote is logged like with `vote`, receipt included.
+#[receive(
+    contract = "govote_voting",
+    name = "revealVote",
+    parameter = "RevealVoteParams",
+    enable_logger
+)]
+fn contract_reveal_vote<A: HasActions>(
+    ctx: &impl HasReceiveContext,
+    logger: &mut impl HasLogger,
+    state: &mut State,
+) -> ContractResult<A> {
+    // 封印されていなければ実行できる。
+    state.ensure_unsealed()?;
+
+    let params: RevealVoteParams = ctx.parameter_cursor().get()?;
+    let sender_address = ctx.sender();
+
+    ensure!(state.commit_reveal, ContractError::WrongVotingMode);
+
+    // proposalが存在すれば実行できる。
+    state.require_proposal(&params.proposal_id)?;
+
+    // 集計が終わってなければ実行できる。
+    ensure!(state.status != Status::Finished, ContractError::AlreadyFinished);
+
+    // expiryと猶予期間を超えていれば実行できる。
+    let slot_time = ctx.metadata().slot_time();
+    ensure!(state.is_expired(slot_time), ContractError::NotExpired);
+
+    let voter = state.get_voter(&sender_address).ok_or(ContractError::VoterIsNotFound)?;
+    ensure!(voter.weight != 0, ContractError::NoRightToVote);
+    ensure!(!voter.voted, ContractError::AlreadyRevealed);
+    let commitment = voter.commitment.ok_or(ContractError::NotVoted)?;
+    ensure!(
+        sha256(&to_bytes(&(params.proposal_id, params.nonce))) == commitment,
+        ContractError::CommitmentMismatch
+    );
+
+    // proposalの得票数の上限を超えなければ実行できる。
+    state.ensure_proposal_capacity(&sender_address, &params.proposal_id)?;
+
+    let revocation = state.delegation_revocation(&sender_address)?;
+    // 委任済みならその分のvote_countを引く
+    state.retract_weight(&sender_address)?;
+    let vote_count =
+        state.record_vote(sender_address, params.proposal_id, VoteDirection::For, slot_time)?;
+    if let Some(event) = revocation {
+        logger.log(&event)?;
+    }
+    log_vote(state, logger, sender_address, None, params.proposal_id, vote_count, slot_time)?;
+
+    Ok(A::accept())
+}
+
+/// Abstain from the vote.
+/// An abstention counts towards the quorum but not towards any proposal.
+/// Like a vote, it replaces any earlier vote or delegation and can be
+/// cancelled with `cancelVote`. A revoked delegation is logged as
+/// `Event::DelegationRevoked`.
+#[receive(contract = "govote_voting", name = "abstain", enable_logger)]
+fn contract_abstain<A: HasActions>(
+    ctx: &impl HasReceiveContext,
+    logger: &mut impl HasLogger,
+    state: &mut State,
+) -> ContractResult<A> {
+    // 封印されていなければ実行できる。
+    state.ensure_unsealed()?;
+
+    let sender_address = ctx.sender();
+
+    // 集計のみのモードでは棄権を記録しない。
+    ensure!(!state.aggregate_only, ContractError::ChangesDisabled);
+
+    // commit-revealモードではcommitVoteとrevealVoteを使う。
+    ensure!(!state.commit_reveal, ContractError::WrongVotingMode);
+
+    // 投票期間中なら実行できる。
+    state.ensure_open(ctx.metadata().slot_time())?;
+
+    // 投票者数の上限を超えなければ実行できる。
+    state.ensure_voter_capacity(&sender_address)?;
+
+    // 投票権を取り消されていなければ実行できる。
+    state.ensure_right_to_vote(&sender_address)?;
+
+    // 棄権済みなら何もしない。
+    if state.get_voter(&sender_address).map(|a| a.abstained) == Some(true) {
+        return Ok(A::accept());
+    }
+
+    // 再投票が許可されていなければ、投票済みや委任済みならエラー。
+    state.ensure_ballot_replaceable(&sender_address)?;
+
+    if let Some(event) = state.delegation_revocation(&sender_address)? {
+        logger.log(&event)?;
+    }
+    state.retract_weight(&sender_address)?;
+
+    let voter_state = state.voter_entry(sender_address);
+    voter_state.voted = true;
+    voter_state.vote = None;
+    voter_state.voted_at = Some(ctx.metadata().slot_time());
+    voter_state.cancelled = false;
+    voter_state.delegate = None;
+    voter_state.abstained = true;
+    state.abstain_count += 1;
+
+    Ok(A::accept())
+}
+
+/// Submit ballots collected off-chain in one transaction.
+/// Only be called by owner.
+/// Every ballot is applied with the voter's effective weight, i.e. the weight
+/// granted with `giveRightToVote` and any decay, and logged like with `vote`.
+/// If any ballot fails, e.g. for an unknown proposal, the whole batch is
+/// rejected and no ballot is applied. A batch is limited by the number of log entries a
+/// transaction may produce.
+#[receive(
+    contract = "govote_voting",
+    name = "voteBatch",
+    parameter = "VoteBatchParams",
+    enable_logger
+)]
+fn contract_vote_batch<A: HasActions>(
+    ctx: &impl HasReceiveContext,
+    logger: &mut impl HasLogger,
+    state: &mut State,
+) -> ContractResult<A> {
+    // 封印されていなければ実行できる。
+    state.ensure_unsealed()?;
+
+    let params: VoteBatchParams = ctx.parameter_cursor().get()?;
+
+    // ownerだけが実行できる。
+    ensure!(ctx.sender().matches_account(&ctx.owner()), ContractError::FromIsNotTheOwner);
+
+    // tokenの重みは残高を問い合わせるvoteでしか数えられない。
+    ensure!(state.token_weight.is_none(), ContractError::WrongVotingMode);
+
+    let slot_time = ctx.metadata().slot_time();
+    for (voter, proposal_id) in params.ballots {
+        state.ensure_owner_may_vote(&voter, &ctx.owner())?;
+        vote_and_log(state, logger, voter, proposal_id, VoteDirection::For, slot_time)?;
+    }
+
+    Ok(A::accept())
+}
+
+/// Open voting for a contract initialized with `start_open` false.
+/// Only be called by owner.
+#[receive(contract = "govote_voting", name = "openVoting")]
+fn contract_open_voting<A: HasActions>(
+    ctx: &impl HasReceiveContext,
+    state: &mut State,
+) -> ContractResult<A> {
+    // 封印されていなければ実行できる。
+    state.ensure_unsealed()?;
+
+    // ownerだけが実行できる。
+    ensure!(ctx.sender().matches_account(&ctx.owner()), ContractError::FromIsNotTheOwner);
+
+    // 集計が終わってなければ実行できる。
+    ensure!(state.status != Status::Finished, ContractError::AlreadyFinished);
+
+    // 開始済みなら開始時刻は元のまま。
+    if state.status == Status::NotStarted {
+        state.started_at = Some(ctx.metadata().slot_time());
+    }
+    state.status = Status::InProcess;
+
+    Ok(A::accept())
+}
+
+/// Revoke the right to vote.
+/// Only be called by owner.
+/// The voter's own weight is removed from the proposal it counts towards and
+/// set to 0. Weight delegated to the voter keeps counting. Logs
+/// `Event::RightRevoked` once the counts are updated.
+#[receive(
+    contract = "govote_voting",
+    name = "revokeRightToVote",
+    parameter = "GetVoterParams",
+    enable_logger
+)]
+fn contract_revoke_right_to_vote<A: HasActions>(
+    ctx: &impl HasReceiveContext,
+    logger: &mut impl HasLogger,
+    state: &mut State,
+) -> ContractResult<A> {
+    // 封印されていなければ実行できる。
+    state.ensure_unsealed()?;
+
+    let params: GetVoterParams = ctx.parameter_cursor().get()?;
+    let voter_address = params.voter_address;
+
+    // ownerだけが実行できる。
+    ensure!(ctx.sender().matches_account(&ctx.owner()), ContractError::FromIsNotTheOwner);
+
+    // 集計が終わってなければ実行できる。
+    ensure!(state.status != Status::Finished, ContractError::AlreadyFinished);
+
+    let removed_weight =
+        state.get_voter(&voter_address).ok_or(ContractError::VoterIsNotFound)?.weight;
+
+    // 投票済みならその分のvote_countを引く
+    state.retract_weight(&voter_address)?;
+    let voter = state.voters.get_mut(&voter_address).unwrap();
+    voter.weight = 0;
+    // 投票権を与えられた投票者なら対象者の数からも外す。
+    if voter.granted {
+        voter.granted = false;
+        state.eligible_count -= 1;
+    }
+    state.apply_weight(&voter_address, ctx.metadata().slot_time())?;
+
+    logger.log(&Event::RightRevoked {
+        from: voter_address,
+        removed_weight,
+    })?;
+
+    Ok(A::accept())
+}
+
+/// Log the current standings as `Event::Snapshot`.
+/// Only be called by owner.
+/// Gives indexers a checkpoint without reading the contract state. A snapshot
+/// must fit in a single log entry, which limits it to about 55 proposals.
+#[receive(contract = "govote_voting", name = "emitSnapshot", enable_logger)]
+fn contract_emit_snapshot<A: HasActions>(
+    ctx: &impl HasReceiveContext,
+    logger: &mut impl HasLogger,
+    state: &mut State,
+) -> ContractResult<A> {
+    // ownerだけが実行できる。
+    ensure!(ctx.sender().matches_account(&ctx.owner()), ContractError::FromIsNotTheOwner);
+
+    let mut counts: Vec<(ProposalId, u64)> =
+        state.proposals.iter().map(|(proposal_id, p)| (*proposal_id, p.vote_count)).collect();
+    counts.sort();
+    logger.log(&Event::Snapshot {
+        counts,
+        total: state.total_votes()?,
+    })?;
+
+    Ok(A::accept())
+}
+
+/// Remove all voters after the tally to free up state.
+/// Only be called by owner.
+/// The proposals and the outcome of the tally are kept. The eligibility and
+/// the votes waiting for a token balance go with the voters, and the tally
+/// can no longer be reopened since its counts cannot be rebuilt.
+#[receive(contract = "govote_voting", name = "clearVoters")]
+fn contract_clear_voters<A: HasActions>(
+    ctx: &impl HasReceiveContext,
+    state: &mut State,
+) -> ContractResult<A> {
+    // 封印されていなければ実行できる。
+    state.ensure_unsealed()?;
+
+    // ownerだけが実行できる。
+    ensure!(ctx.sender().matches_account(&ctx.owner()), ContractError::FromIsNotTheOwner);
+
+    // 集計が終わっていれば実行できる。
+    ensure!(state.status == Status::Finished, ContractError::NotYetTallied);
+
+    state.voters.clear();
+    state.eligible_count = 0;
+    state.aggregate_voters.clear();
+    state.pending_votes.clear();
+    state.voters_cleared = true;
+
+    Ok(A::accept())
+}
+
+/// Change the expiry.
+/// Only be called by owner.
+/// The new expiry must be in the future, as at init, and cannot be changed
+/// after the tally.
+#[receive(contract = "govote_voting", name = "setExpiry", parameter = "SetExpiryParams")]
+fn contract_set_expiry<A: HasActions>(
+    ctx: &impl HasReceiveContext,
+    state: &mut State,
+) -> ContractResult<A> {
+    // 封印されていなければ実行できる。
+    state.ensure_unsealed()?;
+
+    let params: SetExpiryParams = ctx.parameter_cursor().get()?;
+
+    // ownerだけが実行できる。
+    ensure!(ctx.sender().matches_account(&ctx.owner()), ContractError::FromIsNotTheOwner);
+
+    // 集計が終わってなければ実行できる。
+    ensure!(state.status != Status::Finished, ContractError::AlreadyFinished);
+
+    // 過去の日時には変更できない。
+    ensure!(ctx.metadata().slot_time() < params.expiry, ContractError::ExpiryInPast);
+
+    state.expiry = params.expiry;
+
+    Ok(A::accept())
+}
+
+/// Rename a proposal, e.g. to fix a typo.
+/// Only be called by owner.
+/// Only possible while no votes have been counted.
+#[receive(contract = "govote_voting", name = "editProposal", parameter = "EditProposalParams")]
+fn contract_edit_proposal<A: HasActions>(
+    ctx: &impl HasReceiveContext,
+    state: &mut State,
+) -> ContractResult<A> {
+    // 封印されていなければ実行できる。
+    state.ensure_unsealed()?;
+
+    let params: EditProposalParams = ctx.parameter_cursor().get()?;
+
+    // ownerだけが実行できる。
+    ensure!(ctx.sender().matches_account(&ctx.owner()), ContractError::FromIsNotTheOwner);
+
+    // 集計が終わってなければ実行できる。
+    ensure!(state.status != Status::Finished, ContractError::AlreadyFinished);
+
+    // proposalが存在すれば実行できる。
+    state.require_proposal(&params.proposal_id)?;
+
+    // 反対票も含めて票が入る前なら実行できる。
+    ensure!(!state.has_votes(), ContractError::VotingAlreadyStarted);
+
+    // proposalの名前は長さの上限以下でなければならない。
+    ensure!(params.new_name.len() <= MAX_PROPOSAL_NAME_LENGTH, ContractError::ProposalNameTooLong);
+
+    state.proposals.get_mut(&params.proposal_id).unwrap().name = params.new_name;
+
+    Ok(A::accept())
+}
+
+/// Set the order in which proposals are listed.
+/// Only be called by owner.
+/// Only possible while no votes have been counted. Proposals that are not
+/// listed keep their `display_order`.
+#[receive(
+    contract = "govote_voting",
+    name = "setDisplayOrder",
+    parameter = "SetDisplayOrderParams"
+)]
+fn contract_set_display_order<A: HasActions>(
+    ctx: &impl HasReceiveContext,
+    state: &mut State,
+) -> ContractResult<A> {
+    // 封印されていなければ実行できる。
+    state.ensure_unsealed()?;
+
+    let params: SetDisplayOrderParams = ctx.parameter_cursor().get()?;
+
+    // ownerだけが実行できる。
+    ensure!(ctx.sender().matches_account(&ctx.owner()), ContractError::FromIsNotTheOwner);
+
+    // 集計が終わってなければ実行できる。
+    ensure!(state.status != Status::Finished, ContractError::AlreadyFinished);
+
+    // 反対票も含めて票が入る前なら実行できる。
+    ensure!(!state.has_votes(), ContractError::VotingAlreadyStarted);
+
+    // すべてのproposalが存在すれば実行できる。
+    for (proposal_id, _) in params.orders.iter() {
+        state.require_proposal(proposal_id)?;
+    }
+
+    for (proposal_id, display_order) in params.orders {
+        state.proposals.get_mut(&proposal_id).unwrap().display_order = display_order;
+    }
+
+    Ok(A::accept())
+}
+
+/// Rebuild the vote counts from the voters.
+/// Only be called by owner.
+/// Guards against count drift, e.g. before the tally. The total is the sum
+/// of the rebuilt counts.
+#[receive(contract = "govote_voting", name = "recount")]
+fn contract_recount<A: HasActions>(
+    ctx: &impl HasReceiveContext,
+    state: &mut State,
+) -> ContractResult<A> {
+    // 封印されていなければ実行できる。
+    state.ensure_unsealed()?;
+
+    // ownerだけが実行できる。
+    ensure!(ctx.sender().matches_account(&ctx.owner()), ContractError::FromIsNotTheOwner);
+
+    // 集計が終わってなければ実行できる。
+    ensure!(state.status != Status::Finished, ContractError::AlreadyFinished);
+
+    state.recount(ctx.metadata().slot_time())?;
+
+    Ok(A::accept())
+}
+
+/// 集計
+/// If a finalization rule fails, no winner is declared, the result is marked
+/// as indecisive and the failed rule is logged as `Event::NoWinner`.
+/// Once the votes are tallied, calling it again does not recompute anything
+/// and logs the stored `TallyResult` like `getWinningProposal`, so clients can
+/// safely retry.
+/// With several `seats`, every proposal with at least the vote count of the
+/// last seat wins, ordered by proposal id. A tie at the last seat elects all
+/// tied proposals, so there can be more winners than seats.
+#[receive(contract = "govote_voting", name = "winningProposal", enable_logger)]
+fn contract_winning_proposal<A: HasActions>(
+    ctx: &impl HasReceiveContext,
+    logger: &mut impl HasLogger,
+    state: &mut State,
+) -> ContractResult<A> {
+    // 集計済みなら結果を返すだけ。
+    if state.status == Status::Finished {
+        logger.log(&state.tally_result())?;
+        return Ok(A::accept());
+    }
+
+    // expiryと猶予期間を超えていれば実行できる。
     // let slot_time = ctx.metadata().slot_time();
-    // ensure!(state.expiry < slot_time, ContractError::NotExpired);
+    // ensure!(state.is_expired(slot_time), ContractError::NotExpired);
+
+    if let Some(failed_rule) = state.tally(ctx.metadata().slot_time())? {
+        logger.log(&Event::NoWinner {
+            failed_rule,
+        })?;
+    }
+
+    Ok(A::accept())
+}
+
+/// Finish the poll early and tally the current votes.
+/// Only be called by owner.
+/// Unlike a regular tally this intentionally ignores the expiry. The reason
+/// is logged as `Event::ForceFinished`, preceded by `Event::NoWinner` if a
+/// finalization rule fails.
+#[receive(
+    contract = "govote_voting",
+    name = "forceFinish",
+    parameter = "ForceFinishParams",
+    enable_logger
+)]
+fn contract_force_finish<A: HasActions>(
+    ctx: &impl HasReceiveContext,
+    logger: &mut impl HasLogger,
+    state: &mut State,
+) -> ContractResult<A> {
+    // 封印されていなければ実行できる。
+    state.ensure_unsealed()?;
+
+    let params: ForceFinishParams = ctx.parameter_cursor().get()?;
+
+    // ownerだけが実行できる。
+    ensure!(ctx.sender().matches_account(&ctx.owner()), ContractError::FromIsNotTheOwner);
+
+    // 集計が終わってなければ実行できる。
+    ensure!(state.status != Status::Finished, ContractError::AlreadyFinished);
+
+    if let Some(failed_rule) = state.tally(ctx.metadata().slot_time())? {
+        logger.log(&Event::NoWinner {
+            failed_rule,
+        })?;
+    }
+    logger.log(&Event::ForceFinished {
+        reason: params.reason,
+    })?;
+
+    Ok(A::accept())
+}
+
+/// Reopen the tally to correct the votes.
+/// Only be called by owner.
+/// Possible until `correction_window` after the tally. Voting continues and
+/// the outcome is cleared until the votes are tallied again.
+#[receive(contract = "govote_voting", name = "reopenTally")]
+fn contract_reopen_tally<A: HasActions>(
+    ctx: &impl HasReceiveContext,
+    state: &mut State,
+) -> ContractResult<A> {
+    // 封印されていなければ実行できる。
+    state.ensure_unsealed()?;
+
+    // ownerだけが実行できる。
+    ensure!(ctx.sender().matches_account(&ctx.owner()), ContractError::FromIsNotTheOwner);
+
+    // 集計が終わっていれば実行できる。
+    ensure!(state.status == Status::Finished, ContractError::NotYetTallied);
+
+    // 投票者を消していれば票を数え直せないので実行できない。
+    ensure!(!state.voters_cleared, ContractError::VotersCleared);
+
+    // 修正期間内なら実行できる。
+    let finished_at = state.finished_at.ok_or(ContractError::NotYetTallied)?;
+    let window_end = finished_at
+        .checked_add(state.correction_window)
+        .ok_or(ContractError::CorrectionWindowClosed)?;
+    ensure!(ctx.metadata().slot_time() <= window_end, ContractError::CorrectionWindowClosed);
+
+    state.status = Status::InProcess;
+    state.winning_proposal_id = vec![];
+    state.winning_vote_count = 0;
+    state.indecisive = false;
+    state.finished_at = None;
+
+    Ok(A::accept())
+}
+
+/// Preview the outcome of the tally.
+/// Logs the `TallyResult` `winningProposal` would store now, without
+/// finishing the poll or storing anything. Works in any status.
+#[receive(contract = "govote_voting", name = "previewWinner", enable_logger)]
+fn contract_preview_winner<A: HasActions>(
+    _ctx: &impl HasReceiveContext,
+    logger: &mut impl HasLogger,
+    state: &mut State,
+) -> ContractResult<A> {
+    let (result, _) = state.compute_tally()?;
+    logger.log(&result)?;
+    Ok(A::accept())
+}
+
+/// Get the outcome of the tally.
+/// Logs the winning proposal ids together with their vote count.
+#[receive(contract = "govote_voting", name = "getWinningProposal", enable_logger)]
+fn contract_get_winning_proposal<A: HasActions>(
+    _ctx: &impl HasReceiveContext,
+    logger: &mut impl HasLogger,
+    state: &mut State,
+) -> ContractResult<A> {
+    logger.log(&state.tally_result())?;
+    Ok(A::accept())
+}
+
+/// Get the names of the winning proposals.
+/// Logs the names as a `Vec<String>` ordered by proposal id. Fails with
+/// `NotYetTallied` before the tally.
+#[receive(contract = "govote_voting", name = "getWinnerNames", enable_logger)]
+fn contract_get_winner_names<A: HasActions>(
+    _ctx: &impl HasReceiveContext,
+    logger: &mut impl HasLogger,
+    state: &mut State,
+) -> ContractResult<A> {
+    // 集計が終わっていれば実行できる。
+    ensure!(state.status == Status::Finished, ContractError::NotYetTallied);
+
+    let mut winners = state.winning_proposal_id.clone();
+    winners.sort();
+    let names: Vec<String> = winners
+        .iter()
+        .filter_map(|proposal_id| state.proposals.get(proposal_id))
+        .map(|p| p.name.clone())
+        .collect();
+    logger.log(&names)?;
+    Ok(A::accept())
+}
+
+/// Get the final result.
+/// Logs a `FinalResult` with the winners, their names and vote count, the
+/// total votes, the turnout and each proposal's share of the cast and the
+/// eligible weight. Fails with `NotYetTallied` before the tally.
+#[receive(contract = "govote_voting", name = "getFinalResult", enable_logger)]
+fn contract_get_final_result<A: HasActions>(
+    _ctx: &impl HasReceiveContext,
+    logger: &mut impl HasLogger,
+    state: &mut State,
+) -> ContractResult<A> {
+    logger.log(&state.final_result()?)?;
+    Ok(A::accept())
+}
+
+/// Seal a finished poll.
+/// Only be called by owner.
+/// Afterwards every entrypoint that changes the state rejects with
+/// `ContractSealed`, so the result is final. Views keep working.
+#[receive(contract = "govote_voting", name = "seal")]
+fn contract_seal<A: HasActions>(
+    ctx: &impl HasReceiveContext,
+    state: &mut State,
+) -> ContractResult<A> {
+    // ownerだけが実行できる。
+    ensure!(ctx.sender().matches_account(&ctx.owner()), ContractError::FromIsNotTheOwner);
+
+    // 封印されていなければ実行できる。
+    state.ensure_unsealed()?;
+
+    // 集計が終わっていれば実行できる。
+    ensure!(state.status == Status::Finished, ContractError::NotYetTallied);
+
+    state.sealed = true;
+
+    Ok(A::accept())
+}
+
+/// 投票のキャンセル
+/// A vote for a proposal that no longer exists is cleared without touching
+/// any vote count. The counted weight is subtracted with checked arithmetic,
+/// so a vote counted with weight 0, e.g. after a revocation, is cleared
+/// as well. Rejects with `AlreadyVoted` if re-voting is disabled.
+#[receive(contract = "govote_voting", name = "cancelVote")]
+fn cancel_vote<A: HasActions>(
+    ctx: &impl HasReceiveContext,
+    state: &mut State,
+) -> ContractResult<A> {
+    // 封印されていなければ実行できる。
+    state.ensure_unsealed()?;
+
+    let sender_address = ctx.sender();
+
+    // 集計のみのモードでは取り消せない。
+    ensure!(!state.aggregate_only, ContractError::ChangesDisabled);
+
+    // 再投票が許可されていなければ取り消せない。
+    ensure!(state.allow_revote, ContractError::AlreadyVoted);
+
+    // 投票期間中なら実行できる。
+    state.ensure_open(ctx.metadata().slot_time())?;
+
+    let voter = state.get_voter(&sender_address).ok_or(ContractError::VoterIsNotFound)?;
+    // 取り消し済みなら、投票していない場合と区別する。
+    ensure!(!voter.cancelled, ContractError::AlreadyCancelled);
+    ensure!(voter.voted, ContractError::NotVoted);
+    if voter.abstained {
+        state.clear_abstention(&sender_address);
+    } else {
+        let vote = voter.vote.ok_or(ContractError::NotVoted)?;
+        // 削除されたproposalへの投票ならvote_countは触らずに取り消す。
+        if state.proposals.contains_key(&vote) {
+            state.retract_weight(&sender_address)?;
+        }
+    }
+
+    let voter = state.voters.get_mut(&sender_address).unwrap();
+    voter.voted = false;
+    voter.vote = None;
+    voter.voted_at = None;
+    voter.counted_weight = 0;
+    voter.cancelled = true;
+
+    Ok(A::accept())
+}
+
+/// Delegate the sender's weight to another address.
+/// The weight counts towards whatever the end of the delegation chain votes
+/// for. Voting directly afterwards revokes the delegation.
+/// The delegation is logged as `Event::Delegated`, preceded by
+/// `Event::DelegationRevoked` if it replaces an earlier delegation, or their
+/// anonymous forms if `anonymize_events` is set.
+#[receive(
+    contract = "govote_voting",
+    name = "delegate",
+    parameter = "DelegateParams",
+    enable_logger
+)]
+fn contract_delegate<A: HasActions>(
+    ctx: &impl HasReceiveContext,
+    logger: &mut impl HasLogger,
+    state: &mut State,
+) -> ContractResult<A> {
+    // 封印されていなければ実行できる。
+    state.ensure_unsealed()?;
+
+    let params: DelegateParams = ctx.parameter_cursor().get()?;
+    let sender_address = ctx.sender();
+
+    // 集計のみのモードでは委任できない。
+    ensure!(!state.aggregate_only, ContractError::ChangesDisabled);
+
+    // tokenの重みは残高を問い合わせるvoteでしか数えられない。
+    ensure!(state.token_weight.is_none(), ContractError::WrongVotingMode);
+
+    // 自分自身には委任できない。
+    ensure!(params.to != sender_address, ContractError::SelfDelegation);
+
+    // ownerが中立なら投票できない。
+    state.ensure_owner_may_vote(&sender_address, &ctx.owner())?;
+
+    // 投票期間中なら実行できる。
+    state.ensure_open(ctx.metadata().slot_time())?;
+
+    // 委任先をたどって送信者に戻るなら循環になる。
+    // 登録済みのvoterだけをたどり、委任していないvoterで止まる。
+    let mut current = params.to;
+    let mut depth = state.delegators_depth(&sender_address) + 1;
+    loop {
+        ensure!(current != sender_address, ContractError::DelegationCycle);
+        ensure!(depth <= MAX_DELEGATION_DEPTH, ContractError::DelegationTooDeep);
+        match state.get_voter(&current).and_then(|a| a.delegate) {
+            Some(next) => current = next,
+            None => break,
+        }
+        depth += 1;
+    }
+
+    // 投票者数の上限を超えなければ実行できる。
+    state.ensure_voter_capacity(&sender_address)?;
+
+    // 投票権を取り消されていなければ実行できる。
+    state.ensure_right_to_vote(&sender_address)?;
+
+    // 再投票が許可されていなければ、投票済みや委任済みならエラー。
+    state.ensure_ballot_replaceable(&sender_address)?;
+
+    if let Some(event) = state.delegation_revocation(&sender_address)? {
+        logger.log(&event)?;
+    }
+    state.retract_weight(&sender_address)?;
+    state.clear_abstention(&sender_address);
+
+    let voter_state = state.voter_entry(sender_address);
+    voter_state.voted = false;
+    voter_state.vote = None;
+    voter_state.voted_at = None;
+    voter_state.cancelled = false;
+    voter_state.delegate = Some(params.to);
+
+    state.apply_weight(&sender_address, ctx.metadata().slot_time())?;
+    logger.log(&state.delegation_event(&sender_address, params.to)?)?;
+
+    Ok(A::accept())
+}
+
+/// Explain why the sender cannot vote.
+/// Logs the first blocking reason as `Some(reason)`, or `None` if the sender
+/// can vote. A vote that only changes the sender's choice counts as voting,
+/// so its cooldown and `max_changes` block it too.
+#[receive(contract = "govote_voting", name = "whyCantIVote", enable_logger)]
+fn contract_why_cant_i_vote<A: HasActions>(
+    ctx: &impl HasReceiveContext,
+    logger: &mut impl HasLogger,
+    state: &mut State,
+) -> ContractResult<A> {
+    let reason = state
+        .voting_blocker(&ctx.sender(), &ctx.owner(), ctx.metadata().slot_time())?
+        .map(String::from);
+    logger.log(&reason)?;
+    Ok(A::accept())
+}
+
+/// Get the time remaining until voting closes.
+/// Voting closes at the end of the grace period after the expiry, like for
+/// `isExpired`. Logs a `Countdown`, which is all zeros once it has passed.
+#[receive(contract = "govote_voting", name = "getCountdown", enable_logger)]
+fn contract_get_countdown<A: HasActions>(
+    ctx: &impl HasReceiveContext,
+    logger: &mut impl HasLogger,
+    state: &mut State,
+) -> ContractResult<A> {
+    logger.log(&state.countdown(ctx.metadata().slot_time()))?;
+    Ok(A::accept())
+}
+
+/// Get the state of a voter.
+/// Logs the `VoterState` of the given address.
+#[receive(
+    contract = "govote_voting",
+    name = "getVoter",
+    parameter = "GetVoterParams",
+    enable_logger
+)]
+fn contract_get_voter<A: HasActions>(
+    ctx: &impl HasReceiveContext,
+    logger: &mut impl HasLogger,
+    state: &mut State,
+) -> ContractResult<A> {
+    let params: GetVoterParams = ctx.parameter_cursor().get()?;
+    let voter = state.get_voter(&params.voter_address).ok_or(ContractError::VoterIsNotFound)?;
+    logger.log(voter)?;
+    Ok(A::accept())
+}
+
+/// Get the time remaining until voting closes.
+/// Logs the milliseconds from now until the end of the grace period after
+/// the expiry as an `i64`, negative once voting has closed.
+#[receive(contract = "govote_voting", name = "timeRemaining", enable_logger)]
+fn contract_time_remaining<A: HasActions>(
+    ctx: &impl HasReceiveContext,
+    logger: &mut impl HasLogger,
+    state: &mut State,
+) -> ContractResult<A> {
+    let remaining = state.closes_at().timestamp_millis() as i64
+        - ctx.metadata().slot_time().timestamp_millis() as i64;
+    logger.log(&remaining)?;
+    Ok(A::accept())
+}
+
+/// Check whether voting has closed.
+/// Logs `true` once the current time is after the expiry and the grace
+/// period.
+#[receive(contract = "govote_voting", name = "isExpired", enable_logger)]
+fn contract_is_expired<A: HasActions>(
+    ctx: &impl HasReceiveContext,
+    logger: &mut impl HasLogger,
+    state: &mut State,
+) -> ContractResult<A> {
+    logger.log(&state.is_expired(ctx.metadata().slot_time()))?;
+    Ok(A::accept())
+}
+
+/// Get the contract version.
+/// Logs `CONTRACT_VERSION` as a `u8`, the version of the init parameter this
+/// build accepts.
+#[receive(contract = "govote_voting", name = "version", enable_logger)]
+fn contract_version<A: HasActions>(
+    _ctx: &impl HasReceiveContext,
+    logger: &mut impl HasLogger,
+    _state: &mut State,
+) -> ContractResult<A> {
+    logger.log(&CONTRACT_VERSION)?;
+    Ok(A::accept())
+}
+
+/// Get the expiry.
+/// Logs the `Timestamp` set at init or with `setExpiry`, without the grace
+/// period.
+#[receive(contract = "govote_voting", name = "getExpiry", enable_logger)]
+fn contract_get_expiry<A: HasActions>(
+    _ctx: &impl HasReceiveContext,
+    logger: &mut impl HasLogger,
+    state: &mut State,
+) -> ContractResult<A> {
+    logger.log(&state.expiry)?;
+    Ok(A::accept())
+}
+
+/// Get the start time.
+/// Logs the `Timestamp` voting opened at as an `Option`, `None` if it has not
+/// opened yet or the state was migrated from version 1.
+#[receive(contract = "govote_voting", name = "getStartTime", enable_logger)]
+fn contract_get_start_time<A: HasActions>(
+    _ctx: &impl HasReceiveContext,
+    logger: &mut impl HasLogger,
+    state: &mut State,
+) -> ContractResult<A> {
+    logger.log(&state.started_at)?;
+    Ok(A::accept())
+}
+
+/// Get the init parameters.
+/// Logs the `InitParamsRecord` stored at init as an `Option`, `None` for a
+/// state migrated from version 1.
+#[receive(contract = "govote_voting", name = "getInitParams", enable_logger)]
+fn contract_get_init_params<A: HasActions>(
+    _ctx: &impl HasReceiveContext,
+    logger: &mut impl HasLogger,
+    state: &mut State,
+) -> ContractResult<A> {
+    logger.log(&state.init_params)?;
+    Ok(A::accept())
+}
+
+/// Get the last moment a vote can be cancelled.
+/// Logs the `Timestamp` when voting closes, i.e. the expiry plus the grace
+/// period. Cancelling is possible up to and including it.
+#[receive(contract = "govote_voting", name = "cancellableUntil", enable_logger)]
+fn contract_cancellable_until<A: HasActions>(
+    _ctx: &impl HasReceiveContext,
+    logger: &mut impl HasLogger,
+    state: &mut State,
+) -> ContractResult<A> {
+    logger.log(&state.closes_at())?;
+    Ok(A::accept())
+}
+
+/// Get the owner.
+/// Logs the `AccountAddress` that created the contract.
+#[receive(contract = "govote_voting", name = "getOwner", enable_logger)]
+fn contract_get_owner<A: HasActions>(
+    ctx: &impl HasReceiveContext,
+    logger: &mut impl HasLogger,
+    state: &mut State,
+) -> ContractResult<A> {
+    logger.log(&state.owner.unwrap_or_else(|| ctx.owner()))?;
+    Ok(A::accept())
+}
+
+/// Get a proposal.
+/// Logs the `Proposal` with the given id.
+#[receive(
+    contract = "govote_voting",
+    name = "getProposal",
+    parameter = "GetProposalParams",
+    enable_logger
+)]
+fn contract_get_proposal<A: HasActions>(
+    ctx: &impl HasReceiveContext,
+    logger: &mut impl HasLogger,
+    state: &mut State,
+) -> ContractResult<A> {
+    let params: GetProposalParams = ctx.parameter_cursor().get()?;
+    logger.log(state.require_proposal(&params.proposal_id)?)?;
+    Ok(A::accept())
+}
+
+/// Get the rank of a proposal.
+/// Logs the 1-based rank of the proposal by net score as a `u32`, as in the
+/// tally. Tied proposals share the best rank.
+#[receive(
+    contract = "govote_voting",
+    name = "getRank",
+    parameter = "GetProposalParams",
+    enable_logger
+)]
+fn contract_get_rank<A: HasActions>(
+    ctx: &impl HasReceiveContext,
+    logger: &mut impl HasLogger,
+    state: &mut State,
+) -> ContractResult<A> {
+    let params: GetProposalParams = ctx.parameter_cursor().get()?;
+    logger.log(&state.rank(&params.proposal_id)?)?;
+    Ok(A::accept())
+}
+
+/// Check that the state is consistent.
+/// The weights counted for the voters' ballots must add up to the vote and
+/// against counts of the proposals, otherwise this fails with
+/// `InvariantViolation`. An `aggregate_only` poll stores no ballots and a poll
+/// after `clearVoters` no longer has them, so both always pass. Only built
+/// with the `testing` feature.
+#[cfg(any(test, feature = "testing"))]
+#[receive(contract = "govote_voting", name = "checkInvariants")]
+fn contract_check_invariants<A: HasActions>(
+    _ctx: &impl HasReceiveContext,
+    state: &mut State,
+) -> ContractResult<A> {
+    let counted: u128 = state.voters.values().map(|voter| u128::from(voter.counted_weight)).sum();
+    let vote_counts: u128 = state
+        .proposals
+        .values()
+        .map(|p| u128::from(p.vote_count) + u128::from(p.against_count))
+        .sum();
+    ensure!(
+        state.aggregate_only || state.voters_cleared || counted == vote_counts,
+        ContractError::InvariantViolation
+    );
+    Ok(A::accept())
+}
+
+/// Dump the whole state for test harnesses.
+/// Logs the serialized `State` split into entries of at most
+/// `MAX_LOG_SIZE` bytes; concatenate them to read it back. The state must fit
+/// in the log entries of a single transaction. Only built with the `testing`
+/// feature.
+#[cfg(any(test, feature = "testing"))]
+#[receive(contract = "govote_voting", name = "dumpState", enable_logger)]
+fn contract_dump_state<A: HasActions>(
+    _ctx: &impl HasReceiveContext,
+    logger: &mut impl HasLogger,
+    state: &mut State,
+) -> ContractResult<A> {
+    for chunk in to_bytes(state).chunks(constants::MAX_LOG_SIZE) {
+        logger.log_raw(chunk)?;
+    }
+    Ok(A::accept())
+}
+
+/// Get all proposals.
+/// Logs a `ProposalEntry` for every proposal, ordered by `display_order` and
+/// then by proposal id. Works in any status. The entries must fit in a single
+/// log entry.
+#[receive(contract = "govote_voting", name = "getProposals", enable_logger)]
+fn contract_get_proposals<A: HasActions>(
+    _ctx: &impl HasReceiveContext,
+    logger: &mut impl HasLogger,
+    state: &mut State,
+) -> ContractResult<A> {
+    logger.log(&state.proposal_entries())?;
+    Ok(A::accept())
+}
+
+/// Get the proposals of a group.
+/// Logs a `ProposalEntry` for every proposal in the group, ordered like
+/// `getProposals`. An unknown group logs an empty vector.
+#[receive(
+    contract = "govote_voting",
+    name = "getProposalsByGroup",
+    parameter = "GetProposalsByGroupParams",
+    enable_logger
+)]
+fn contract_get_proposals_by_group<A: HasActions>(
+    ctx: &impl HasReceiveContext,
+    logger: &mut impl HasLogger,
+    state: &mut State,
+) -> ContractResult<A> {
+    let params: GetProposalsByGroupParams = ctx.parameter_cursor().get()?;
+    logger.log(&state.group_entries(params.group))?;
+    Ok(A::accept())
+}
+
+/// Get a page of proposals.
+/// Logs a `ProposalEntry` for up to `limit` proposals with an id of at least
+/// `start`, ordered by proposal id. The limit is clamped to
+/// `MAX_PROPOSALS_PAGE_SIZE`, and a start past the last id logs an empty page.
+#[receive(
+    contract = "govote_voting",
+    name = "getProposalsPage",
+    parameter = "GetProposalsPageParams",
+    enable_logger
+)]
+fn contract_get_proposals_page<A: HasActions>(
+    ctx: &impl HasReceiveContext,
+    logger: &mut impl HasLogger,
+    state: &mut State,
+) -> ContractResult<A> {
+    let params: GetProposalsPageParams = ctx.parameter_cursor().get()?;
+    logger.log(&state.proposals_page(params.start, params.limit))?;
+    Ok(A::accept())
+}
+
+/// Look up a proposal by its exact name.
+/// Logs the `(ProposalId, u64)` id and net score of the proposal, the
+/// smallest id if several share the name. Names are not indexed, so this
+/// scans every proposal.
+#[receive(
+    contract = "govote_voting",
+    name = "getProposalByName",
+    parameter = "GetProposalByNameParams",
+    enable_logger
+)]
+fn contract_get_proposal_by_name<A: HasActions>(
+    ctx: &impl HasReceiveContext,
+    logger: &mut impl HasLogger,
+    state: &mut State,
+) -> ContractResult<A> {
+    let params: GetProposalByNameParams = ctx.parameter_cursor().get()?;
+    let proposal_id =
+        state.proposal_id_by_name(&params.name).ok_or(ContractError::ProposalIsNotFound)?;
+    logger.log(&(proposal_id, state.require_proposal(&proposal_id)?.net_score()))?;
+    Ok(A::accept())
+}
+
+/// Get the voters of a proposal for an audit.
+/// Logs the addresses with an active vote for the proposal, sorted by address.
+/// Delegators are not included. The voters are only indexed by address, so
+/// this scans every voter and its cost grows with the number of voters. The
+/// addresses must fit in a single log entry.
+#[receive(
+    contract = "govote_voting",
+    name = "getProposalVoters",
+    parameter = "GetProposalParams",
+    enable_logger
+)]
+fn contract_get_proposal_voters<A: HasActions>(
+    ctx: &impl HasReceiveContext,
+    logger: &mut impl HasLogger,
+    state: &mut State,
+) -> ContractResult<A> {
+    let params: GetProposalParams = ctx.parameter_cursor().get()?;
+    state.require_proposal(&params.proposal_id)?;
+    logger.log(&state.proposal_voters(&params.proposal_id))?;
+    Ok(A::accept())
+}
+
+/// Get the number of proposals.
+/// Logs the count as a `u32`; proposal ids run from `0` to `count - 1`.
+#[receive(contract = "govote_voting", name = "getProposalCount", enable_logger)]
+fn contract_get_proposal_count<A: HasActions>(
+    _ctx: &impl HasReceiveContext,
+    logger: &mut impl HasLogger,
+    state: &mut State,
+) -> ContractResult<A> {
+    logger.log(&(state.proposals.len() as u32))?;
+    Ok(A::accept())
+}
+
+/// Get the distribution of the votes.
+/// Logs each proposal's share of the net scores as `(ProposalId, basis
+/// points)` pairs ordered by proposal id. The shares sum to 10000, or are all
+/// zero if no proposal has a positive net score.
+#[receive(contract = "govote_voting", name = "getDistribution", enable_logger)]
+fn contract_get_distribution<A: HasActions>(
+    _ctx: &impl HasReceiveContext,
+    logger: &mut impl HasLogger,
+    state: &mut State,
+) -> ContractResult<A> {
+    logger.log(&state.distribution())?;
+    Ok(A::accept())
+}
+
+/// Get the share of a single proposal.
+/// Logs the proposal's share of the net scores in basis points as a `u32`, or
+/// 0 if no proposal has a positive net score. The share is rounded down, so
+/// it can be one less than in `getDistribution`, which distributes the
+/// remainders.
+#[receive(
+    contract = "govote_voting",
+    name = "getShare",
+    parameter = "GetProposalParams",
+    enable_logger
+)]
+fn contract_get_share<A: HasActions>(
+    ctx: &impl HasReceiveContext,
+    logger: &mut impl HasLogger,
+    state: &mut State,
+) -> ContractResult<A> {
+    let params: GetProposalParams = ctx.parameter_cursor().get()?;
+    let net_score = state.require_proposal(&params.proposal_id)?.net_score();
+    let total = state.total_net_score();
+    let share = (u128::from(net_score) * 10_000).checked_div(total).unwrap_or(0) as u32;
+    logger.log(&share)?;
+    Ok(A::accept())
+}
+
+/// Get the participation for each proposal.
+/// Logs a `ProposalParticipation` for every proposal, ordered by
+/// `display_order` and then by proposal id. The participation is the vote
+/// count relative to the summed weight of the voters granted the right to
+/// vote, or the share of the votes cast if no right was granted.
+#[receive(contract = "govote_voting", name = "getParticipationByProposal", enable_logger)]
+fn contract_get_participation_by_proposal<A: HasActions>(
+    _ctx: &impl HasReceiveContext,
+    logger: &mut impl HasLogger,
+    state: &mut State,
+) -> ContractResult<A> {
+    logger.log(&state.participation()?)?;
+    Ok(A::accept())
+}
+
+/// Get a page of voters.
+/// Logs up to `limit` `(Address, VoterState)` pairs starting at `start`,
+/// sorted by address. A start past the end logs an empty page. A page must
+/// fit in a single log entry, so keep `limit` small.
+#[receive(
+    contract = "govote_voting",
+    name = "getVoters",
+    parameter = "GetVotersParams",
+    enable_logger
+)]
+fn contract_get_voters<A: HasActions>(
+    ctx: &impl HasReceiveContext,
+    logger: &mut impl HasLogger,
+    state: &mut State,
+) -> ContractResult<A> {
+    let params: GetVotersParams = ctx.parameter_cursor().get()?;
+    logger.log(&state.voters_page(params.start, params.limit))?;
+    Ok(A::accept())
+}
+
+/// Get the turnout.
+/// Logs `(voted_count, eligible_count)`: the number of voters with an active
+/// vote and the number granted the right to vote with `giveRightToVote`.
+/// Voters whose right was revoked are in neither.
+#[receive(contract = "govote_voting", name = "getTurnout", enable_logger)]
+fn contract_get_turnout<A: HasActions>(
+    _ctx: &impl HasReceiveContext,
+    logger: &mut impl HasLogger,
+    state: &mut State,
+) -> ContractResult<A> {
+    logger.log(&(state.voted_count(), state.eligible_count))?;
+    Ok(A::accept())
+}
+
+/// Get the proposal a voter picked.
+/// Logs `Some(proposal_id)` for an active vote and `None` for a known voter
+/// without one, e.g. after cancelling, abstaining or delegating.
+#[receive(
+    contract = "govote_voting",
+    name = "getVoterChoice",
+    parameter = "GetVoterParams",
+    enable_logger
+)]
+fn contract_get_voter_choice<A: HasActions>(
+    ctx: &impl HasReceiveContext,
+    logger: &mut impl HasLogger,
+    state: &mut State,
+) -> ContractResult<A> {
+    let params: GetVoterParams = ctx.parameter_cursor().get()?;
+    let voter = state.get_voter(&params.voter_address).ok_or(ContractError::VoterIsNotFound)?;
+    logger.log(&voter.vote)?;
+    Ok(A::accept())
+}
+
+/// Check whether an address has voted.
+/// Logs `true` if the address has an active vote, `false` otherwise,
+/// including for unknown addresses.
+#[receive(
+    contract = "govote_voting",
+    name = "hasVoted",
+    parameter = "GetVoterParams",
+    enable_logger
+)]
+fn contract_has_voted<A: HasActions>(
+    ctx: &impl HasReceiveContext,
+    logger: &mut impl HasLogger,
+    state: &mut State,
+) -> ContractResult<A> {
+    let params: GetVoterParams = ctx.parameter_cursor().get()?;
+    logger.log(&state.has_voted(&params.voter_address))?;
+    Ok(A::accept())
+}
+
+/// Sample voters for a spot-check audit.
+/// The seed is the block time, so the sample can be reproduced from the block.
+/// The sampled addresses are logged; a sample must fit in a single log entry.
+#[receive(
+    contract = "govote_voting",
+    name = "sampleVoters",
+    parameter = "SampleVotersParams",
+    enable_logger
+)]
+fn contract_sample_voters<A: HasActions>(
+    ctx: &impl HasReceiveContext,
+    logger: &mut impl HasLogger,
+    state: &mut State,
+) -> ContractResult<A> {
+    let params: SampleVotersParams = ctx.parameter_cursor().get()?;
+    let seed = ctx.metadata().slot_time().timestamp_millis();
+    logger.log(&state.sample_voters(params.count, seed))?;
+    Ok(A::accept())
+}
+
+/// Compare two proposals head to head.
+/// Logs both net scores as the vote counts, their difference and the leading
+/// proposal.
+#[receive(
+    contract = "govote_voting",
+    name = "compareProposals",
+    parameter = "CompareProposalsParams",
+    enable_logger
+)]
+fn contract_compare_proposals<A: HasActions>(
+    ctx: &impl HasReceiveContext,
+    logger: &mut impl HasLogger,
+    state: &mut State,
+) -> ContractResult<A> {
+    let params: CompareProposalsParams = ctx.parameter_cursor().get()?;
+
+    // 両方のproposalが存在すれば実行できる。
+    let a_vote_count = state.require_proposal(&params.a)?.net_score();
+    let b_vote_count = state.require_proposal(&params.b)?.net_score();
+
+    let leader = match a_vote_count.cmp(&b_vote_count) {
+        Ordering::Greater => Some(params.a),
+        Ordering::Less => Some(params.b),
+        Ordering::Equal => None,
+    };
+
+    logger.log(&Event::ProposalComparison {
+        a: params.a,
+        a_vote_count,
+        b: params.b,
+        b_vote_count,
+        difference: a_vote_count.max(b_vote_count) - a_vote_count.min(b_vote_count),
+        leader,
+    })?;
+
+    Ok(A::accept())
+}
+
+#[concordium_cfg_test]
+mod tests {
+    use super::*;
+    use concordium_std::test_infrastructure::*;
+    use std::sync::atomic::{AtomicU8, Ordering};
+
+    static ADDRESS_COUNTER: AtomicU8 = AtomicU8::new(1);
+
+    const ACCOUNT_0: AccountAddress = AccountAddress([0u8; 32]);
+    // const ADDRESS_0: Address = Address::Account(ACCOUNT_0);
+    const ACCOUNT_1: AccountAddress = AccountAddress([1u8; 32]);
+    // const ADDRESS_1: Address = Address::Account(ACCOUNT_1);
+    const TITLE: &str = "Test Title";
+    const DESCRIPTION: &str = "This is test description.";
+    const PROPOSAL_NAME_1: &str = "This is first test proposal.";
+    const PROPOSAL_NAME_2: &str = "This is second test proposal.";
+    const PROPOSAL_DESCRIPTION_1: &str = "This is the description of the first proposal.";
+    const PROPOSAL_DESCRIPTION_2: &str = "This is the description of the second proposal.";
+    const EXPIRY: u64 = 1;
+
+    #[allow(unused)]
+    fn new_account() -> AccountAddress {
+        let account = AccountAddress([ADDRESS_COUNTER.load(Ordering::SeqCst); 32]);
+        ADDRESS_COUNTER.fetch_add(1, Ordering::SeqCst);
+        account
+    }
+
+    fn init_parameter() -> InitParams {
+        let init_vec = vec![PROPOSAL_NAME_1.to_string(), PROPOSAL_NAME_2.to_string()];
+
+        InitParams {
+            version: CONTRACT_VERSION,
+            title: TITLE.to_string(),
+            description: DESCRIPTION.to_string(),
+            proposal_names: init_vec,
+            proposal_descriptions: vec![
+                PROPOSAL_DESCRIPTION_1.to_string(),
+                PROPOSAL_DESCRIPTION_2.to_string(),
+            ],
+            proposal_metadata_urls: vec![None, None],
+            proposal_groups: vec![],
+            expiry: Timestamp::from_timestamp_millis(EXPIRY),
+            finalization_rules: FinalizationRules::default(),
+            tie_break: TieBreak::ReturnAll,
+            max_voters: None,
+            commit_reveal: false,
+            max_votes_per_proposal: None,
+            start_open: true,
+            correction_window: Duration::from_millis(0),
+            anonymize_events: false,
+            allow_revote: true,
+            weight_policy: WeightPolicy::Flat,
+            allow_write_ins: false,
+            owner_can_vote: true,
+            max_changes: None,
+            approval_threshold_bp: None,
+            grace_period_ms: 0,
+            seats: 1,
+            aggregate_only: false,
+            token_weight: None,
+            cooldown_ms: 0,
+            eligible: vec![],
+        }
+    }
+
+    fn create_parameter_bytes(parameter: &InitParams) -> Vec<u8> {
+        to_bytes(parameter)
+    }
+
+    fn receipt_event(
+        voter: Address,
+        proposal_id: ProposalId,
+        weight: u64,
+        slot_time: u64,
+    ) -> Vec<u8> {
+        to_bytes(&Event::VoteReceipt {
+            receipt: vote_receipt(
+                &voter,
+                proposal_id,
+                weight,
+                Timestamp::from_timestamp_millis(slot_time),
+            ),
+        })
+    }
+
+    fn parametrized_init_ctx<'a>(parameter_bytes: &'a [u8]) -> InitContextTest<'a> {
+        let mut ctx = InitContextTest::empty();
+        ctx.set_parameter(parameter_bytes);
+        ctx.set_init_origin(ACCOUNT_0);
+        ctx.set_metadata_slot_time(Timestamp::from_timestamp_millis(0));
+        ctx
+    }
+
+    fn receive_ctx<'a>(
+        owner: AccountAddress,
+        sender: AccountAddress,
+        slot_time: u64,
+        parameter_bytes: &'a [u8],
+    ) -> ReceiveContextTest<'a> {
+        let mut ctx = ReceiveContextTest::empty();
+        ctx.set_sender(Address::Account(sender));
+        ctx.set_owner(owner);
+        ctx.set_metadata_slot_time(Timestamp::from_timestamp_millis(slot_time));
+        ctx.set_parameter(parameter_bytes);
+        ctx
+    }
+
+    /// Initialize a contract with the parameters, set the vote counts of the
+    /// proposals in id order and tally at `slot_time`. Returns the finished
+    /// state together with the tally's logs.
+    fn tally_fixture(
+        params: InitParams,
+        vote_counts: &[u64],
+        slot_time: u64,
+    ) -> (State, LogRecorder) {
+        let parameter_bytes = create_parameter_bytes(&params);
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        let mut state = state_result.expect("Contract initialization results in error");
+        for (proposal_id, vote_count) in vote_counts.iter().enumerate() {
+            state.proposals.get_mut(&(proposal_id as ProposalId)).unwrap().vote_count = *vote_count;
+        }
+
+        let ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, slot_time, &[]);
+        let mut logger = LogRecorder::init();
+        let res: ContractResult<ActionsTree> =
+            contract_winning_proposal(&ctx, &mut logger, &mut state);
+        res.expect_report("contract winning proposal results in error.");
+        claim_eq!(state.status, Status::Finished, "Status should be Finished");
+        (state, logger)
+    }
+
+    #[concordium_test]
+    fn test_init() {
+        let init_vec = vec![PROPOSAL_NAME_1.to_string(), PROPOSAL_NAME_2.to_string()];
+
+        let parameter_bytes = create_parameter_bytes(&init_parameter());
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        let state = state_result.expect("Contract initialization results in error");
+
+        claim_eq!(
+            state,
+            State::new(
+                InitParams {
+                    title: TITLE.to_string(),
+                    description: DESCRIPTION.to_string(),
+                    proposal_names: init_vec,
+                    expiry: Timestamp::from_timestamp_millis(EXPIRY),
+                    ..init_parameter()
+                },
+                ACCOUNT_0,
+                Timestamp::from_timestamp_millis(0)
+            ),
+            "State is not equal."
+        );
+    }
+
+    #[concordium_test]
+    fn test_give_right_to_vote() {
+        let parameter_bytes = create_parameter_bytes(&init_parameter());
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        let mut state = state_result.expect("Contract initialization results in error");
+
+        let account1 = new_account();
+        let params = GiveRightToVoteParams {
+            voter_address: Address::Account(account1),
+            weight: 1,
+        };
+        let parameter_bytes = to_bytes(&params);
+        let slot_time = 0u64;
+        let ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, slot_time, &parameter_bytes);
+        let mut logger = LogRecorder::init();
+        let res: ContractResult<ActionsTree> =
+            contract_give_right_to_vote(&ctx, &mut logger, &mut state);
+        let actions = res.expect_report("giving right results in error.");
+        claim_eq!(actions, ActionsTree::accept(), "No action should be produced.");
+        claim_eq!(
+            logger.logs,
+            vec![to_bytes(&Event::GiveRightToVote {
+                to: Address::Account(account1),
+                added_weight: 1,
+                total_weight: 1,
+            })],
+            "something wrong with the logged grant"
+        );
+
+        let account2 = new_account();
+        let params = GiveRightToVoteParams {
+            voter_address: Address::Account(account2),
+            weight: 1,
+        };
+        let parameter_bytes = to_bytes(&params);
+        let slot_time = 0u64;
+        let ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, slot_time, &parameter_bytes);
+        let res: ContractResult<ActionsTree> =
+            contract_give_right_to_vote(&ctx, &mut LogRecorder::init(), &mut state);
+        let actions = res.expect_report("giving right results in error.");
+        claim_eq!(actions, ActionsTree::accept(), "No action should be produced.");
+
+        let res: ContractResult<ActionsTree> =
+            contract_give_right_to_vote(&ctx, &mut LogRecorder::init(), &mut state);
+        claim_eq!(
+            res,
+            Err(ContractError::AlreadyHasRightToVote),
+            "Result should be AlreadyHasRightToVote."
+        );
+
+        let mut voters = Map::default();
+        voters.insert(
+            Address::Account(account1),
+            VoterState {
+                weight: 1,
+                granted: true,
+                ..Default::default()
+            },
+        );
+        voters.insert(
+            Address::Account(account2),
+            VoterState {
+                weight: 1,
+                granted: true,
+                ..Default::default()
+            },
+        );
+        claim_eq!(state.voters, voters);
+        claim_eq!(state.eligible_count, 2, "something wrong with eligible_count");
+    }
+
+    // #[concordium_test]
+    // fn test_give_right_to_vote_expired() {
+    //     let parameter_bytes = create_parameter_bytes(&init_parameter());
+    //     let ctx = parametrized_init_ctx(&parameter_bytes);
+    //     let state_result = contract_init(&ctx, &mut LogRecorder::init());
+    //     let mut state = state_result.expect("Contract initialization results in error");
+
+    //     let account1 = new_account();
+    //     let params = GetVoterParams {
+    //         voter_address: Address::Account(account1),
+    //     };
+    //     let parameter_bytes = to_bytes(&params);
+    //     let slot_time = 10u64;
+    //     let ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, slot_time, &parameter_bytes);
+    //     let res: ContractResult<ActionsTree> = contract_give_right_to_vote(&ctx, &mut state);
+    //     let err = res.expect_err_report("Contract is expected to fail.");
+    //     claim_eq!(err, ContractError::Expired, "Expected to fail with error Expired");
+    // }
+
+    #[concordium_test]
+    fn test_give_right_to_vote_with_no_authority() {
+        let parameter_bytes = create_parameter_bytes(&init_parameter());
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        let mut state = state_result.expect("Contract initialization results in error");
+
+        let account1 = new_account();
+        let params = GiveRightToVoteParams {
+            voter_address: Address::Account(account1),
+            weight: 1,
+        };
+        let parameter_bytes = to_bytes(&params);
+        let slot_time = 0u64;
+        let ctx = receive_ctx(ACCOUNT_0, account1, slot_time, &parameter_bytes);
+        let res: ContractResult<ActionsTree> =
+            contract_give_right_to_vote(&ctx, &mut LogRecorder::init(), &mut state);
+        let err = res.expect_err_report("Contract is expected to fail.");
+        claim_eq!(
+            err,
+            ContractError::FromIsNotTheOwner,
+            "Expected to fail with error FromIsNotTheOwner"
+        );
+    }
+
+    #[concordium_test]
+    fn test_get_turnout() {
+        let parameter_bytes = create_parameter_bytes(&init_parameter());
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        let mut state = state_result.expect("Contract initialization results in error");
+
+        let accounts = [new_account(), new_account(), new_account()];
+        for account in accounts.iter() {
+            let parameter_bytes = to_bytes(&GiveRightToVoteParams {
+                voter_address: Address::Account(*account),
+                weight: 1,
+            });
+            let ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, 0u64, &parameter_bytes);
+            let res: ContractResult<ActionsTree> =
+                contract_give_right_to_vote(&ctx, &mut LogRecorder::init(), &mut state);
+            res.expect_report("giving right results in error.");
+        }
+        for account in accounts.iter().take(2) {
+            let parameter_bytes = to_bytes(&GetVoteParams {
+                proposal_id: 0 as ProposalId,
+                direction: VoteDirection::For,
+            });
+            let ctx = receive_ctx(ACCOUNT_0, *account, 0u64, &parameter_bytes);
+            let res: ContractResult<ActionsTree> =
+                contract_vote(&ctx, &mut LogRecorder::init(), &mut state);
+            res.expect_report("contract voting results in error.");
+        }
+
+        let ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, 0u64, &[]);
+        let mut logger = LogRecorder::init();
+        let res: ContractResult<ActionsTree> = contract_get_turnout(&ctx, &mut logger, &mut state);
+        res.expect_report("getting the turnout results in error.");
+        claim_eq!(logger.logs, vec![to_bytes(&(2u32, 3u32))], "turnout should be 2/3");
+
+        // 取り消された投票者は分母からも分子からも外れる。
+        for (account, turnout) in [(accounts[2], (2u32, 2u32)), (accounts[0], (1, 1))].iter() {
+            let parameter_bytes = to_bytes(&GetVoterParams {
+                voter_address: Address::Account(*account),
+            });
+            let ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, 0u64, &parameter_bytes);
+            let res: ContractResult<ActionsTree> =
+                contract_revoke_right_to_vote(&ctx, &mut LogRecorder::init(), &mut state);
+            res.expect_report("revoking right results in error.");
+
+            let ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, 0u64, &[]);
+            let mut logger = LogRecorder::init();
+            let res: ContractResult<ActionsTree> =
+                contract_get_turnout(&ctx, &mut logger, &mut state);
+            res.expect_report("getting the turnout results in error.");
+            claim_eq!(logger.logs, vec![to_bytes(turnout)], "revoked voters should not count");
+        }
+    }
+
+    #[concordium_test]
+    fn test_contract_vote() {
+        let parameter_bytes = create_parameter_bytes(&init_parameter());
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        let mut state = state_result.expect("Contract initialization results in error");
+
+        let account1 = new_account();
+        let params = GetVoteParams {
+            proposal_id: 1 as ProposalId,
+            direction: VoteDirection::For,
+        };
+        let parameter_bytes = to_bytes(&params);
+        let slot_time = 0u64;
+        let ctx = receive_ctx(ACCOUNT_0, account1, slot_time, &parameter_bytes);
+
+        let res: ContractResult<ActionsTree> =
+            contract_vote(&ctx, &mut LogRecorder::init(), &mut state);
+        let actions = res.expect_report("contract voting results in error.");
+        claim_eq!(actions, ActionsTree::accept(), "No action should be produced.");
+
+        claim_eq!(
+            state.voters.get(&Address::Account(account1)).unwrap().voted,
+            true,
+            "voted status should be true"
+        );
+
+        claim_eq!(
+            state.proposals.get(&1).unwrap().vote_count,
+            1,
+            "something wrong with vote_count"
+        );
+    }
+
+    #[concordium_test]
+    fn test_contract_vote_to_wrong_number() {
+        let parameter_bytes = create_parameter_bytes(&init_parameter());
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        let mut state = state_result.expect("Contract initialization results in error");
+
+        let account1 = new_account();
+        let params = GetVoteParams {
+            proposal_id: 2 as ProposalId,
+            direction: VoteDirection::For,
+        };
+        let parameter_bytes = to_bytes(&params);
+        let slot_time = 0u64;
+        let ctx = receive_ctx(ACCOUNT_0, account1, slot_time, &parameter_bytes);
+
+        let res_1: Result<ActionsTree, ContractError> =
+            contract_vote(&ctx, &mut LogRecorder::init(), &mut state);
+        claim_eq!(
+            res_1,
+            Err(ContractError::ProposalIsNotFound),
+            "Result should be ProposalIsNotFound."
+        );
+    }
+
+    #[concordium_test]
+    fn test_contract_vote_selection_change() {
+        let parameter_bytes = create_parameter_bytes(&init_parameter());
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        let mut state = state_result.expect("Contract initialization results in error");
+
+        let account1 = new_account();
+        let params = GetVoteParams {
+            proposal_id: 0 as ProposalId,
+            direction: VoteDirection::For,
+        };
+        let parameter_bytes = to_bytes(&params);
+        let slot_time = 0u64;
+        let ctx = receive_ctx(ACCOUNT_0, account1, slot_time, &parameter_bytes);
+
+        let res_1: ContractResult<ActionsTree> =
+            contract_vote(&ctx, &mut LogRecorder::init(), &mut state);
+        let actions_1 = res_1.expect_report("contract voting results in error.");
+        claim_eq!(actions_1, ActionsTree::accept(), "No action should be produced.");
+
+        claim_eq!(
+            state.proposals.get(&0).unwrap().vote_count,
+            1,
+            "something wrong with vote_count"
+        );
+
+        claim_eq!(
+            state.proposals.get(&1).unwrap().vote_count,
+            0,
+            "something wrong with vote_count"
+        );
+
+        let params = GetVoteParams {
+            proposal_id: 1 as ProposalId,
+            direction: VoteDirection::For,
+        };
+        let parameter_bytes = to_bytes(&params);
+        let slot_time = 0u64;
+        let ctx = receive_ctx(ACCOUNT_0, account1, slot_time, &parameter_bytes);
+        let res_2: ContractResult<ActionsTree> =
+            contract_vote(&ctx, &mut LogRecorder::init(), &mut state);
+        let actions_2 = res_2.expect_report("contract voting results in error.");
+        claim_eq!(actions_2, ActionsTree::accept(), "No action should be produced.");
+
+        claim_eq!(
+            state.voters.get(&Address::Account(account1)).unwrap().voted,
+            true,
+            "voted status should be true"
+        );
+
+        claim_eq!(
+            state.proposals.get(&0).unwrap().vote_count,
+            0,
+            "something wrong with vote_count"
+        );
+
+        claim_eq!(
+            state.proposals.get(&1).unwrap().vote_count,
+            1,
+            "something wrong with vote_count"
+        );
+    }
+
+    #[concordium_test]
+    fn test_contract_vote_same_selection() {
+        let parameter_bytes = create_parameter_bytes(&init_parameter());
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        let mut state = state_result.expect("Contract initialization results in error");
+
+        let account1 = new_account();
+        let params = GetVoteParams {
+            proposal_id: 1 as ProposalId,
+            direction: VoteDirection::For,
+        };
+        let parameter_bytes = to_bytes(&params);
+        let slot_time = 0u64;
+        let ctx = receive_ctx(ACCOUNT_0, account1, slot_time, &parameter_bytes);
+
+        let res_1: ContractResult<ActionsTree> =
+            contract_vote(&ctx, &mut LogRecorder::init(), &mut state);
+        let actions_1 = res_1.expect_report("contract voting results in error.");
+        claim_eq!(actions_1, ActionsTree::accept(), "No action should be produced.");
+
+        let slot_time = 0u64;
+        let ctx = receive_ctx(ACCOUNT_0, account1, slot_time, &parameter_bytes);
+
+        let res_2: ContractResult<ActionsTree> =
+            contract_vote(&ctx, &mut LogRecorder::init(), &mut state);
+        let actions_2 = res_2.expect_report("contract voting results in error.");
+        claim_eq!(actions_2, ActionsTree::accept(), "No action should be produced.");
+
+        claim_eq!(
+            state.voters.get(&Address::Account(account1)).unwrap().voted,
+            true,
+            "voted status should be true"
+        );
+
+        claim_eq!(
+            state.proposals.get(&1).unwrap().vote_count,
+            1,
+            "something wrong with vote_count"
+        );
+    }
+
+    #[concordium_test]
+    fn test_contract_winning_proposal_no_voters() {
+        let parameter_bytes = create_parameter_bytes(&init_parameter());
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        let mut state = state_result.expect("Contract initialization results in error");
+
+        let account1 = new_account();
+        let params = GetVoteParams {
+            proposal_id: 1 as ProposalId,
+            direction: VoteDirection::For,
+        };
+        let parameter_bytes = to_bytes(&params);
+        let slot_time = 0u64;
+        let ctx = receive_ctx(ACCOUNT_0, account1, slot_time, &parameter_bytes);
+        claim_eq!(state.status, Status::InProcess, "Status should be InProcess");
+        let res_1: ContractResult<ActionsTree> =
+            contract_winning_proposal(&ctx, &mut LogRecorder::init(), &mut state);
+        let actions_1 = res_1.expect_report("contract winning proposal results in error.");
+        claim_eq!(actions_1, ActionsTree::accept(), "No action should be produced.");
+        claim_eq!(state.status, Status::Finished, "Status should be Finished");
+        claim_eq!(
+            state.winning_proposal_id,
+            vec![1, 0],
+            "something wrong with winning_proposal_id"
+        );
+    }
+
+    #[concordium_test]
+    fn test_contract_winning_proposal_one_winning_proposal() {
+        let parameter_bytes = create_parameter_bytes(&init_parameter());
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        let mut state = state_result.expect("Contract initialization results in error");
+
+        let account1 = new_account();
+        let params = GetVoteParams {
+            proposal_id: 0 as ProposalId,
+            direction: VoteDirection::For,
+        };
+        let parameter_bytes = to_bytes(&params);
+        let slot_time = 0u64;
+        let ctx = receive_ctx(ACCOUNT_0, account1, slot_time, &parameter_bytes);
+
+        let res_1: ContractResult<ActionsTree> =
+            contract_vote(&ctx, &mut LogRecorder::init(), &mut state);
+        let actions_1 = res_1.expect_report("contract voting results in error.");
+        claim_eq!(actions_1, ActionsTree::accept(), "No action should be produced.");
+
+        claim_eq!(state.status, Status::InProcess, "Status should be InProcess");
+
+        claim_eq!(
+            state.voters.get(&Address::Account(account1)).unwrap().voted,
+            true,
+            "voted status should be true"
+        );
+
+        claim_eq!(
+            state.proposals.get(&0).unwrap().vote_count,
+            1,
+            "something wrong with vote_count"
+        );
+
+        let res_2: ContractResult<ActionsTree> =
+            contract_winning_proposal(&ctx, &mut LogRecorder::init(), &mut state);
+        let actions_2 = res_2.expect_report("contract winning proposal results in error.");
+        claim_eq!(actions_2, ActionsTree::accept(), "No action should be produced.");
+        claim_eq!(state.status, Status::Finished, "Status should be Finished");
+        claim_eq!(state.winning_proposal_id, vec![0], "something wrong with winning_proposal_id");
+    }
+
+    #[concordium_test]
+    fn test_contract_winning_proposal_multi_winning_proposal() {
+        let parameter_bytes = create_parameter_bytes(&init_parameter());
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        let mut state = state_result.expect("Contract initialization results in error");
+
+        let account1 = new_account();
+        let params = GetVoteParams {
+            proposal_id: 0 as ProposalId,
+            direction: VoteDirection::For,
+        };
+        let parameter_bytes = to_bytes(&params);
+        let slot_time = 0u64;
+        let ctx = receive_ctx(ACCOUNT_0, account1, slot_time, &parameter_bytes);
+
+        let res_1: ContractResult<ActionsTree> =
+            contract_vote(&ctx, &mut LogRecorder::init(), &mut state);
+        let actions_1 = res_1.expect_report("contract voting results in error.");
+        claim_eq!(actions_1, ActionsTree::accept(), "No action should be produced.");
+
+        claim_eq!(state.status, Status::InProcess, "Status should be InProcess");
+
+        claim_eq!(
+            state.voters.get(&Address::Account(account1)).unwrap().voted,
+            true,
+            "voted status should be true"
+        );
+
+        claim_eq!(
+            state.proposals.get(&0).unwrap().vote_count,
+            1,
+            "something wrong with vote_count"
+        );
+
+        claim_eq!(
+            state.proposals.get(&1).unwrap().vote_count,
+            0,
+            "something wrong with vote_count"
+        );
+
+        let account2 = new_account();
+        let params = GetVoteParams {
+            proposal_id: 1 as ProposalId,
+            direction: VoteDirection::For,
+        };
+        let parameter_bytes = to_bytes(&params);
+        let slot_time = 0u64;
+        let ctx = receive_ctx(ACCOUNT_1, account2, slot_time, &parameter_bytes);
+
+        let res_2: ContractResult<ActionsTree> =
+            contract_vote(&ctx, &mut LogRecorder::init(), &mut state);
+        let actions_2 = res_2.expect_report("contract voting results in error.");
+        claim_eq!(actions_2, ActionsTree::accept(), "No action should be produced.");
+
+        claim_eq!(state.status, Status::InProcess, "Status should be InProcess");
+
+        claim_eq!(
+            state.voters.get(&Address::Account(account1)).unwrap().voted,
+            true,
+            "voted status should be true"
+        );
+
+        claim_eq!(
+            state.proposals.get(&0).unwrap().vote_count,
+            1,
+            "something wrong with vote_count"
+        );
+
+        claim_eq!(
+            state.proposals.get(&1).unwrap().vote_count,
+            1,
+            "something wrong with vote_count"
+        );
+
+        let res_3: ContractResult<ActionsTree> =
+            contract_winning_proposal(&ctx, &mut LogRecorder::init(), &mut state);
+        let actions_3 = res_3.expect_report("contract winning proposal results in error.");
+
+        claim_eq!(actions_3, ActionsTree::accept(), "No action should be produced.");
+        claim_eq!(state.status, Status::Finished, "Status should be Finished");
+        claim_eq!(
+            state.winning_proposal_id,
+            vec![1, 0],
+            "something wrong with winning_proposal_id"
+        );
+    }
+
+    #[concordium_test]
+    fn test_cancel_vote() {
+        let parameter_bytes = create_parameter_bytes(&init_parameter());
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        let mut state = state_result.expect("Contract initialization results in error");
+
+        let account1 = new_account();
+        let params = GetVoteParams {
+            proposal_id: 1 as ProposalId,
+            direction: VoteDirection::For,
+        };
+        let parameter_bytes = to_bytes(&params);
+        let slot_time = 0u64;
+        let ctx = receive_ctx(ACCOUNT_0, account1, slot_time, &parameter_bytes);
+        let res: ContractResult<ActionsTree> =
+            contract_vote(&ctx, &mut LogRecorder::init(), &mut state);
+        let actions = res.expect_report("contract voting results in error.");
+        claim_eq!(actions, ActionsTree::accept(), "No action should be produced.");
+        claim_eq!(
+            state.voters.get(&Address::Account(account1)).unwrap().voted,
+            true,
+            "voted status should be true"
+        );
+        claim_eq!(
+            state.proposals.get(&1).unwrap().vote_count,
+            1,
+            "something wrong with vote_count"
+        );
+
+        let res: ContractResult<ActionsTree> = cancel_vote(&ctx, &mut state);
+        let actions = res.expect_report("cancel voting results in error.");
+        claim_eq!(actions, ActionsTree::accept(), "No action should be produced.");
+        claim_eq!(
+            state.voters.get(&Address::Account(account1)).unwrap().voted,
+            false,
+            "voted status should be true"
+        );
+        claim_eq!(
+            state.proposals.get(&1).unwrap().vote_count,
+            0,
+            "something wrong with vote_count"
+        );
+    }
+
+    #[concordium_test]
+    fn test_compare_proposals() {
+        let parameter_bytes = create_parameter_bytes(&init_parameter());
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        let mut state = state_result.expect("Contract initialization results in error");
+        state.proposals.get_mut(&0).unwrap().vote_count = 3;
+        state.proposals.get_mut(&1).unwrap().vote_count = 5;
+
+        let account1 = new_account();
+        let params = CompareProposalsParams {
+            a: 0,
+            b: 1,
+        };
+        let parameter_bytes = to_bytes(&params);
+        let slot_time = 0u64;
+        let ctx = receive_ctx(ACCOUNT_0, account1, slot_time, &parameter_bytes);
+        let mut logger = LogRecorder::init();
+        let res: ContractResult<ActionsTree> =
+            contract_compare_proposals(&ctx, &mut logger, &mut state);
+        let actions = res.expect_report("comparing proposals results in error.");
+        claim_eq!(actions, ActionsTree::accept(), "No action should be produced.");
+        claim_eq!(
+            logger.logs,
+            vec![to_bytes(&Event::ProposalComparison {
+                a: 0,
+                a_vote_count: 3,
+                b: 1,
+                b_vote_count: 5,
+                difference: 2,
+                leader: Some(1),
+            })],
+            "something wrong with comparison event"
+        );
+
+        let params = CompareProposalsParams {
+            a: 0,
+            b: 2,
+        };
+        let parameter_bytes = to_bytes(&params);
+        let ctx = receive_ctx(ACCOUNT_0, account1, slot_time, &parameter_bytes);
+        let mut logger = LogRecorder::init();
+        let res: ContractResult<ActionsTree> =
+            contract_compare_proposals(&ctx, &mut logger, &mut state);
+        claim_eq!(
+            res,
+            Err(ContractError::ProposalIsNotFound),
+            "Result should be ProposalIsNotFound."
+        );
+    }
+
+    #[concordium_test]
+    fn test_contract_vote_logs_new_vote_count() {
+        let parameter_bytes = create_parameter_bytes(&init_parameter());
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        let mut state = state_result.expect("Contract initialization results in error");
+
+        let params = GetVoteParams {
+            proposal_id: 1 as ProposalId,
+            direction: VoteDirection::For,
+        };
+        let parameter_bytes = to_bytes(&params);
+        let slot_time = 0u64;
+
+        for expected_count in 1..=3u64 {
+            let account = new_account();
+            let ctx = receive_ctx(ACCOUNT_0, account, slot_time, &parameter_bytes);
+            let mut logger = LogRecorder::init();
+            let res: ContractResult<ActionsTree> = contract_vote(&ctx, &mut logger, &mut state);
+            let actions = res.expect_report("contract voting results in error.");
+            claim_eq!(actions, ActionsTree::accept(), "No action should be produced.");
+            claim_eq!(
+                logger.logs,
+                vec![
+                    to_bytes(&Event::FirstVote {
+                        voter: Address::Account(account),
+                        proposal_id: 1,
+                        vote_count: expected_count,
+                    }),
+                    receipt_event(Address::Account(account), 1, 1, slot_time),
+                ],
+                "something wrong with logged vote_count"
+            );
+        }
+    }
+
+    #[concordium_test]
+    fn test_sample_voters_reproducible() {
+        // 同じ投票者を逆の順番で登録した状態を二つ作る。
+        let accounts: Vec<AccountAddress> = (100..105).map(|i| AccountAddress([i; 32])).collect();
+        let vote_bytes = to_bytes(&GetVoteParams {
+            proposal_id: 0 as ProposalId,
+            direction: VoteDirection::For,
+        });
+        let state_with = |accounts: &[AccountAddress]| {
+            let parameter_bytes = create_parameter_bytes(&init_parameter());
+            let ctx = parametrized_init_ctx(&parameter_bytes);
+            let state_result = contract_init(&ctx, &mut LogRecorder::init());
+            let mut state = state_result.expect("Contract initialization results in error");
+            for account in accounts {
+                let ctx = receive_ctx(ACCOUNT_0, *account, 0u64, &vote_bytes);
+                let res: ContractResult<ActionsTree> =
+                    contract_vote(&ctx, &mut LogRecorder::init(), &mut state);
+                res.expect_report("contract voting results in error.");
+            }
+            state
+        };
+        let mut state_1 = state_with(&accounts);
+        let reversed: Vec<AccountAddress> = accounts.iter().rev().copied().collect();
+        let mut state_2 = state_with(&reversed);
+
+        let params = SampleVotersParams {
+            count: 3,
+        };
+        let parameter_bytes = to_bytes(&params);
+        let slot_time = 42u64;
+        let ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, slot_time, &parameter_bytes);
+        let mut logger_1 = LogRecorder::init();
+        let res_1: ContractResult<ActionsTree> =
+            contract_sample_voters(&ctx, &mut logger_1, &mut state_1);
+        res_1.expect_report("sampling voters results in error.");
+        let mut logger_2 = LogRecorder::init();
+        let res_2: ContractResult<ActionsTree> =
+            contract_sample_voters(&ctx, &mut logger_2, &mut state_2);
+        res_2.expect_report("sampling voters results in error.");
+        claim_eq!(
+            logger_1.logs,
+            logger_2.logs,
+            "the sample should not depend on the order the voters were added"
+        );
+
+        // 種が固定されていれば標本も固定される。
+        let expected: Vec<Address> =
+            [104u8, 102, 101].iter().map(|i| Address::Account(AccountAddress([*i; 32]))).collect();
+        claim_eq!(logger_1.logs, vec![to_bytes(&expected)], "something wrong with logged sample");
+
+        claim_eq!(state_1.sample_voters(10, slot_time).len(), 5, "sample is capped by voter count");
+    }
+
+    #[concordium_test]
+    fn test_cancel_vote_for_proposal_zero() {
+        let parameter_bytes = create_parameter_bytes(&init_parameter());
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        let mut state = state_result.expect("Contract initialization results in error");
+
+        let account1 = new_account();
+        let params = GetVoteParams {
+            proposal_id: 0 as ProposalId,
+            direction: VoteDirection::For,
+        };
+        let parameter_bytes = to_bytes(&params);
+        let slot_time = 0u64;
+        let ctx = receive_ctx(ACCOUNT_0, account1, slot_time, &parameter_bytes);
+        let res: ContractResult<ActionsTree> =
+            contract_vote(&ctx, &mut LogRecorder::init(), &mut state);
+        res.expect_report("contract voting results in error.");
+        claim_eq!(
+            state.voters.get(&Address::Account(account1)).unwrap().vote,
+            Some(0),
+            "voter should vote for proposal 0"
+        );
+
+        let res: ContractResult<ActionsTree> = cancel_vote(&ctx, &mut state);
+        res.expect_report("cancel voting results in error.");
+        let voter = state.voters.get(&Address::Account(account1)).unwrap();
+        claim_eq!(voter.voted, false, "voted status should be false");
+        claim_eq!(voter.vote, None, "voter should have no active vote");
+        claim_eq!(
+            state.proposals.get(&0).unwrap().vote_count,
+            0,
+            "something wrong with vote_count"
+        );
+
+        // Voting again after the cancel must not subtract from proposal 0.
+        let params = GetVoteParams {
+            proposal_id: 1 as ProposalId,
+            direction: VoteDirection::For,
+        };
+        let parameter_bytes = to_bytes(&params);
+        let ctx = receive_ctx(ACCOUNT_0, account1, slot_time, &parameter_bytes);
+        let res: ContractResult<ActionsTree> =
+            contract_vote(&ctx, &mut LogRecorder::init(), &mut state);
+        res.expect_report("contract voting results in error.");
+        claim_eq!(
+            state.proposals.get(&0).unwrap().vote_count,
+            0,
+            "something wrong with vote_count"
+        );
+        claim_eq!(
+            state.proposals.get(&1).unwrap().vote_count,
+            1,
+            "something wrong with vote_count"
+        );
+    }
+
+    #[concordium_test]
+    fn test_has_voted() {
+        let parameter_bytes = create_parameter_bytes(&init_parameter());
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        let mut state = state_result.expect("Contract initialization results in error");
+
+        let account1 = new_account();
+        let account2 = new_account();
+        let account3 = new_account();
+        let vote_bytes = to_bytes(&GetVoteParams {
+            proposal_id: 1 as ProposalId,
+            direction: VoteDirection::For,
+        });
+        let slot_time = 0u64;
+        for account in [account1, account2].iter() {
+            let ctx = receive_ctx(ACCOUNT_0, *account, slot_time, &vote_bytes);
+            let res: ContractResult<ActionsTree> =
+                contract_vote(&ctx, &mut LogRecorder::init(), &mut state);
+            res.expect_report("contract voting results in error.");
+        }
+        let ctx = receive_ctx(ACCOUNT_0, account2, slot_time, &vote_bytes);
+        let res: ContractResult<ActionsTree> = cancel_vote(&ctx, &mut state);
+        res.expect_report("cancel voting results in error.");
+
+        for (account, expected) in [(account1, true), (account2, false), (account3, false)].iter() {
+            let params = GetVoterParams {
+                voter_address: Address::Account(*account),
+            };
+            let parameter_bytes = to_bytes(&params);
+            let ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, slot_time, &parameter_bytes);
+            let mut logger = LogRecorder::init();
+            let res: ContractResult<ActionsTree> =
+                contract_has_voted(&ctx, &mut logger, &mut state);
+            let actions = res.expect_report("hasVoted results in error.");
+            claim_eq!(actions, ActionsTree::accept(), "No action should be produced.");
+            claim_eq!(logger.logs, vec![to_bytes(expected)], "something wrong with hasVoted");
+        }
+    }
+
+    #[concordium_test]
+    fn test_get_countdown() {
+        let expiry = 100_000_000u64;
+        let parameter_bytes = create_parameter_bytes(&InitParams {
+            expiry: Timestamp::from_timestamp_millis(expiry),
+            ..init_parameter()
+        });
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        let mut state = state_result.expect("Contract initialization results in error");
+
+        let parameter_bytes = Vec::new();
+        let slot_time = expiry - 90_061_000;
+        let ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, slot_time, &parameter_bytes);
+        let mut logger = LogRecorder::init();
+        let res: ContractResult<ActionsTree> =
+            contract_get_countdown(&ctx, &mut logger, &mut state);
+        let actions = res.expect_report("getCountdown results in error.");
+        claim_eq!(actions, ActionsTree::accept(), "No action should be produced.");
+        claim_eq!(
+            logger.logs,
+            vec![to_bytes(&Countdown {
+                days: 1,
+                hours: 1,
+                minutes: 1,
+                seconds: 1,
+            })],
+            "something wrong with countdown"
+        );
+
+        claim_eq!(
+            state.countdown(Timestamp::from_timestamp_millis(expiry + 5_000)),
+            Countdown {
+                days: 0,
+                hours: 0,
+                minutes: 0,
+                seconds: 0,
+            },
+            "countdown should saturate at zero"
+        );
+
+        // 猶予期間の終わりまで数える。
+        state.grace_period_ms = 10_000;
+        claim_eq!(
+            state.countdown(Timestamp::from_timestamp_millis(expiry + 5_000)),
+            Countdown {
+                days: 0,
+                hours: 0,
+                minutes: 0,
+                seconds: 5,
+            },
+            "countdown should include the grace period"
+        );
+    }
+
+    #[concordium_test]
+    fn test_finalization_rules_quorum_fails() {
+        let (state, logger) = tally_fixture(
+            InitParams {
+                finalization_rules: FinalizationRules {
+                    quorum: Some(10),
+                    ..Default::default()
+                },
+                ..init_parameter()
+            },
+            &[3, 1],
+            0,
+        );
+        claim_eq!(state.winning_proposal_id, vec![], "No winner should be declared");
+        claim_eq!(
+            logger.logs,
+            vec![to_bytes(&Event::NoWinner {
+                failed_rule: FinalizationRule::Quorum,
+            })],
+            "Quorum should be the failed rule"
+        );
+    }
+
+    #[concordium_test]
+    fn test_finalization_rules_win_threshold_fails() {
+        let (state, logger) = tally_fixture(
+            InitParams {
+                finalization_rules: FinalizationRules {
+                    quorum: Some(5),
+                    win_threshold_bp: Some(6000),
+                    ..Default::default()
+                },
+                ..init_parameter()
+            },
+            &[5, 4],
+            0,
+        );
+        claim_eq!(state.winning_proposal_id, vec![], "No winner should be declared");
+        claim_eq!(
+            logger.logs,
+            vec![to_bytes(&Event::NoWinner {
+                failed_rule: FinalizationRule::WinThreshold,
+            })],
+            "WinThreshold should be the failed rule"
+        );
+    }
+
+    #[concordium_test]
+    fn test_finalization_rules_min_margin_fails() {
+        let (state, logger) = tally_fixture(
+            InitParams {
+                finalization_rules: FinalizationRules {
+                    quorum: Some(5),
+                    win_threshold_bp: Some(5000),
+                    min_margin: Some(2),
+                },
+                ..init_parameter()
+            },
+            &[3, 2],
+            0,
+        );
+        claim_eq!(state.winning_proposal_id, vec![], "No winner should be declared");
+        claim_eq!(
+            logger.logs,
+            vec![to_bytes(&Event::NoWinner {
+                failed_rule: FinalizationRule::MinMargin,
+            })],
+            "MinMargin should be the failed rule"
+        );
+    }
+
+    #[concordium_test]
+    fn test_finalization_rules_all_pass() {
+        let (state, logger) = tally_fixture(
+            InitParams {
+                finalization_rules: FinalizationRules {
+                    quorum: Some(5),
+                    win_threshold_bp: Some(6000),
+                    min_margin: Some(2),
+                },
+                ..init_parameter()
+            },
+            &[1, 4],
+            0,
+        );
+        claim_eq!(state.winning_proposal_id, vec![1], "something wrong with winning_proposal_id");
+        claim_eq!(logger.logs.len(), 0, "No event should be logged");
+    }
+
+    #[concordium_test]
+    fn test_delegate() {
+        let parameter_bytes = create_parameter_bytes(&init_parameter());
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        let mut state = state_result.expect("Contract initialization results in error");
+
+        let delegator = new_account();
+        let delegatee = new_account();
+        let slot_time = 0u64;
+        let vote_bytes = to_bytes(&GetVoteParams {
+            proposal_id: 1 as ProposalId,
+            direction: VoteDirection::For,
+        });
+        let ctx = receive_ctx(ACCOUNT_0, delegatee, slot_time, &vote_bytes);
+        let res: ContractResult<ActionsTree> =
+            contract_vote(&ctx, &mut LogRecorder::init(), &mut state);
+        res.expect_report("contract voting results in error.");
+
+        let params = DelegateParams {
+            to: Address::Account(delegatee),
+        };
+        let parameter_bytes = to_bytes(&params);
+        let ctx = receive_ctx(ACCOUNT_0, delegator, slot_time, &parameter_bytes);
+        let res: ContractResult<ActionsTree> =
+            contract_delegate(&ctx, &mut LogRecorder::init(), &mut state);
+        let actions = res.expect_report("delegation results in error.");
+        claim_eq!(actions, ActionsTree::accept(), "No action should be produced.");
+        claim_eq!(
+            state.effective_weight(&Address::Account(delegatee)),
+            Ok(2),
+            "delegated weight should be added to the delegatee"
+        );
+        claim_eq!(
+            state.proposals.get(&1).unwrap().vote_count,
+            2,
+            "something wrong with vote_count"
+        );
+
+        // Voting directly revokes the delegation.
+        let vote_bytes = to_bytes(&GetVoteParams {
+            proposal_id: 0 as ProposalId,
+            direction: VoteDirection::For,
+        });
+        let ctx = receive_ctx(ACCOUNT_0, delegator, slot_time, &vote_bytes);
+        let res: ContractResult<ActionsTree> =
+            contract_vote(&ctx, &mut LogRecorder::init(), &mut state);
+        res.expect_report("contract voting results in error.");
+        claim_eq!(
+            state.voters.get(&Address::Account(delegator)).unwrap().delegate,
+            None,
+            "delegation should be revoked"
+        );
+        claim_eq!(
+            state.proposals.get(&0).unwrap().vote_count,
+            1,
+            "something wrong with vote_count"
+        );
+        claim_eq!(
+            state.proposals.get(&1).unwrap().vote_count,
+            1,
+            "something wrong with vote_count"
+        );
+    }
+
+    #[concordium_test]
+    fn test_delegate_cycle() {
+        let parameter_bytes = create_parameter_bytes(&init_parameter());
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        let mut state = state_result.expect("Contract initialization results in error");
+
+        let account1 = new_account();
+        let account2 = new_account();
+        let slot_time = 0u64;
+        let parameter_bytes = to_bytes(&DelegateParams {
+            to: Address::Account(account2),
+        });
+        let ctx = receive_ctx(ACCOUNT_0, account1, slot_time, &parameter_bytes);
+        let res: ContractResult<ActionsTree> =
+            contract_delegate(&ctx, &mut LogRecorder::init(), &mut state);
+        res.expect_report("delegation results in error.");
+
+        let parameter_bytes = to_bytes(&DelegateParams {
+            to: Address::Account(account1),
+        });
+        let ctx = receive_ctx(ACCOUNT_0, account2, slot_time, &parameter_bytes);
+        let res: ContractResult<ActionsTree> =
+            contract_delegate(&ctx, &mut LogRecorder::init(), &mut state);
+        claim_eq!(res, Err(ContractError::DelegationCycle), "Result should be DelegationCycle.");
+    }
+
+    #[concordium_test]
+    fn test_self_delegation() {
+        let parameter_bytes = create_parameter_bytes(&init_parameter());
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        let mut state = state_result.expect("Contract initialization results in error");
+
+        let account1 = new_account();
+        let parameter_bytes = to_bytes(&DelegateParams {
+            to: Address::Account(account1),
+        });
+        let ctx = receive_ctx(ACCOUNT_0, account1, 0u64, &parameter_bytes);
+        let res: ContractResult<ActionsTree> =
+            contract_delegate(&ctx, &mut LogRecorder::init(), &mut state);
+        claim_eq!(res, Err(ContractError::SelfDelegation), "Result should be SelfDelegation.");
+        claim!(state.voters.is_empty(), "no voter entry should be created");
+    }
+
+    fn why_cant_i_vote(state: &mut State, sender: AccountAddress, slot_time: u64) -> Vec<Vec<u8>> {
+        let parameter_bytes = Vec::new();
+        let ctx = receive_ctx(ACCOUNT_0, sender, slot_time, &parameter_bytes);
+        let mut logger = LogRecorder::init();
+        let res: ContractResult<ActionsTree> = contract_why_cant_i_vote(&ctx, &mut logger, state);
+        res.expect_report("whyCantIVote results in error.");
+        logger.logs
+    }
+
+    #[concordium_test]
+    fn test_why_cant_i_vote() {
+        let parameter_bytes = create_parameter_bytes(&init_parameter());
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        let mut state = state_result.expect("Contract initialization results in error");
+
+        let account1 = new_account();
+        claim_eq!(
+            why_cant_i_vote(&mut state, account1, 0),
+            vec![to_bytes(&None::<String>)],
+            "Voter should be able to vote"
+        );
+
+        state.status = Status::Finished;
+        claim_eq!(
+            why_cant_i_vote(&mut state, account1, 0),
+            vec![to_bytes(&Some("Voting has already finished.".to_string()))],
+            "something wrong with the blocking reason"
+        );
+    }
+
+    #[concordium_test]
+    fn test_why_cant_i_vote_gates() {
+        let init = |params: InitParams| {
+            let parameter_bytes = create_parameter_bytes(&params);
+            let ctx = parametrized_init_ctx(&parameter_bytes);
+            contract_init(&ctx, &mut LogRecorder::init())
+                .expect("Contract initialization results in error")
+        };
+        let vote = |state: &mut State, voter: AccountAddress, proposal_id: ProposalId| {
+            let parameter_bytes = to_bytes(&GetVoteParams {
+                proposal_id,
+                direction: VoteDirection::For,
+            });
+            let ctx = receive_ctx(ACCOUNT_0, voter, 0u64, &parameter_bytes);
+            let res: ContractResult<ActionsTree> =
+                contract_vote(&ctx, &mut LogRecorder::init(), state);
+            res.expect_report("contract voting results in error.");
+        };
+        let reason = |text: &str| vec![to_bytes(&Some(text.to_string()))];
+
+        let mut state = init(init_parameter());
+        let account1 = new_account();
+        claim_eq!(
+            why_cant_i_vote(&mut state, account1, EXPIRY + 1),
+            reason("Voting has expired."),
+            "voting should be blocked after the expiry"
+        );
+        state.commit_reveal = true;
+        claim_eq!(
+            why_cant_i_vote(&mut state, account1, 0),
+            reason("Votes must be committed and revealed."),
+            "vote should be blocked in commit-reveal mode"
+        );
+        state.sealed = true;
+        claim_eq!(
+            why_cant_i_vote(&mut state, account1, 0),
+            reason("The poll has been sealed."),
+            "voting should be blocked once sealed"
+        );
+
+        let mut state = init(InitParams {
+            owner_can_vote: false,
+            ..init_parameter()
+        });
+        claim_eq!(
+            why_cant_i_vote(&mut state, ACCOUNT_0, 0),
+            reason("The owner cannot vote in this poll."),
+            "a neutral owner should be blocked"
+        );
+        claim_eq!(
+            why_cant_i_vote(&mut state, account1, 0),
+            vec![to_bytes(&None::<String>)],
+            "other voters should not be blocked"
+        );
+
+        let mut state = init(InitParams {
+            max_voters: Some(1),
+            ..init_parameter()
+        });
+        vote(&mut state, account1, 0);
+        claim_eq!(
+            why_cant_i_vote(&mut state, new_account(), 0),
+            reason("The poll has reached its voter limit."),
+            "a new voter should be blocked at the voter limit"
+        );
+
+        let mut state = init(InitParams {
+            eligible: vec![Address::Account(account1)],
+            ..init_parameter()
+        });
+        claim_eq!(
+            why_cant_i_vote(&mut state, new_account(), 0),
+            reason("You are not on the list of eligible voters."),
+            "a non-listed address should be blocked"
+        );
+        state.voters.get_mut(&Address::Account(account1)).unwrap().weight = 0;
+        claim_eq!(
+            why_cant_i_vote(&mut state, account1, 0),
+            reason("Your right to vote has been revoked."),
+            "a revoked voter should be blocked"
+        );
+
+        let mut state = init(InitParams {
+            allow_revote: false,
+            ..init_parameter()
+        });
+        vote(&mut state, account1, 0);
+        claim_eq!(
+            why_cant_i_vote(&mut state, account1, 0),
+            reason("You have already voted."),
+            "a second vote should be blocked without revoting"
+        );
+
+        let mut state = init(InitParams {
+            max_changes: Some(1),
+            ..init_parameter()
+        });
+        vote(&mut state, account1, 0);
+        vote(&mut state, account1, 1);
+        claim_eq!(
+            why_cant_i_vote(&mut state, account1, 0),
+            reason("You have changed your vote the maximum number of times."),
+            "a change beyond max_changes should be blocked"
+        );
+
+        let mut state = init(InitParams {
+            cooldown_ms: 100,
+            ..init_parameter()
+        });
+        vote(&mut state, account1, 0);
+        claim_eq!(
+            why_cant_i_vote(&mut state, account1, 0),
+            reason("Your last vote was cast too recently."),
+            "a change within the cooldown should be blocked"
+        );
+
+        let mut state = init(InitParams {
+            aggregate_only: true,
+            ..init_parameter()
+        });
+        vote(&mut state, account1, 0);
+        claim_eq!(
+            why_cant_i_vote(&mut state, account1, 0),
+            reason("Votes cannot be changed in this poll."),
+            "a second aggregate vote should be blocked"
+        );
+    }
+
+    #[concordium_test]
+    fn test_vote_timestamp() {
+        let parameter_bytes = create_parameter_bytes(&InitParams {
+            expiry: Timestamp::from_timestamp_millis(100),
+            ..init_parameter()
+        });
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        let mut state = state_result.expect("Contract initialization results in error");
+
+        let account1 = new_account();
+        let vote_bytes = to_bytes(&GetVoteParams {
+            proposal_id: 1 as ProposalId,
+            direction: VoteDirection::For,
+        });
+        let slot_time = 7u64;
+        let ctx = receive_ctx(ACCOUNT_0, account1, slot_time, &vote_bytes);
+        let res: ContractResult<ActionsTree> =
+            contract_vote(&ctx, &mut LogRecorder::init(), &mut state);
+        res.expect_report("contract voting results in error.");
+
+        let parameter_bytes = to_bytes(&GetVoterParams {
+            voter_address: Address::Account(account1),
+        });
+        let ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, slot_time, &parameter_bytes);
+        let mut logger = LogRecorder::init();
+        let res: ContractResult<ActionsTree> = contract_get_voter(&ctx, &mut logger, &mut state);
+        res.expect_report("getVoter results in error.");
+        let voter = state.voters.get(&Address::Account(account1)).unwrap();
+        claim_eq!(
+            voter.voted_at,
+            Some(Timestamp::from_timestamp_millis(slot_time)),
+            "voted_at should match the slot time"
+        );
+        claim_eq!(logger.logs, vec![to_bytes(voter)], "something wrong with logged voter");
+
+        let ctx = receive_ctx(ACCOUNT_0, account1, slot_time, &vote_bytes);
+        let res: ContractResult<ActionsTree> = cancel_vote(&ctx, &mut state);
+        res.expect_report("cancel voting results in error.");
+        claim_eq!(
+            state.voters.get(&Address::Account(account1)).unwrap().voted_at,
+            None,
+            "voted_at should be cleared by cancel"
+        );
+    }
+
+    #[concordium_test]
+    fn test_tie_break_return_all() {
+        let (state, _) = tally_fixture(
+            InitParams {
+                tie_break: TieBreak::ReturnAll,
+                ..init_parameter()
+            },
+            &[2, 2],
+            0,
+        );
+        let mut winners = state.winning_proposal_id.clone();
+        winners.sort();
+        claim_eq!(winners, vec![0, 1], "All tied proposals should win");
+    }
+
+    #[concordium_test]
+    fn test_tie_break_lowest_id() {
+        let (state, _) = tally_fixture(
+            InitParams {
+                tie_break: TieBreak::LowestId,
+                ..init_parameter()
+            },
+            &[2, 2],
+            0,
+        );
+        claim_eq!(state.winning_proposal_id, vec![0], "The lowest id should win");
+    }
+
+    #[concordium_test]
+    fn test_tie_break_alphabetical_name() {
+        let (state, _) = tally_fixture(
+            InitParams {
+                proposal_names: vec!["Bob".to_string(), "Alice".to_string()],
+                tie_break: TieBreak::AlphabeticalName,
+                ..init_parameter()
+            },
+            &[2, 2],
+            0,
+        );
+        claim_eq!(state.winning_proposal_id, vec![1], "Alice should win the tie");
+    }
+
+    #[concordium_test]
+    fn test_vote_batch() {
+        let parameter_bytes = create_parameter_bytes(&init_parameter());
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        let mut state = state_result.expect("Contract initialization results in error");
+
+        let account1 = Address::Account(new_account());
+        let account2 = Address::Account(new_account());
+        let params = VoteBatchParams {
+            ballots: vec![(account1, 0), (account2, 1), (account1, 1)],
+        };
+        let parameter_bytes = to_bytes(&params);
+        let ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, 0u64, &parameter_bytes);
+        let mut logger = LogRecorder::init();
+        let res: ContractResult<ActionsTree> = contract_vote_batch(&ctx, &mut logger, &mut state);
+        let actions = res.expect_report("batch voting results in error.");
+        claim_eq!(actions, ActionsTree::accept(), "No action should be produced.");
+        claim_eq!(logger.logs.len(), 6, "Every ballot should be logged with a receipt");
+        claim_eq!(
+            logger.logs[1],
+            receipt_event(account1, 0, 1, 0),
+            "the first ballot should get a receipt"
+        );
+        claim_eq!(
+            state.proposals.get(&0).unwrap().vote_count,
+            0,
+            "something wrong with vote_count"
+        );
+        claim_eq!(
+            state.proposals.get(&1).unwrap().vote_count,
+            2,
+            "something wrong with vote_count"
+        );
+    }
+
+    #[concordium_test]
+    fn test_vote_batch_with_unknown_proposal() {
+        let parameter_bytes = create_parameter_bytes(&init_parameter());
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        let mut state = state_result.expect("Contract initialization results in error");
+
+        let params = VoteBatchParams {
+            ballots: vec![
+                (Address::Account(new_account()), 0),
+                (Address::Account(new_account()), 2),
+            ],
+        };
+        let parameter_bytes = to_bytes(&params);
+        let ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, 0u64, &parameter_bytes);
+        let res: ContractResult<ActionsTree> =
+            contract_vote_batch(&ctx, &mut LogRecorder::init(), &mut state);
+        claim_eq!(
+            res,
+            Err(ContractError::ProposalIsNotFound),
+            "Result should be ProposalIsNotFound."
+        );
+
+        let ctx = receive_ctx(ACCOUNT_0, ACCOUNT_1, 0u64, &parameter_bytes);
+        let res: ContractResult<ActionsTree> =
+            contract_vote_batch(&ctx, &mut LogRecorder::init(), &mut state);
+        claim_eq!(
+            res,
+            Err(ContractError::FromIsNotTheOwner),
+            "Result should be FromIsNotTheOwner."
+        );
+    }
+
+    #[concordium_test]
+    fn test_max_voters() {
+        let parameter_bytes = create_parameter_bytes(&InitParams {
+            max_voters: Some(2),
+            ..init_parameter()
+        });
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        let mut state = state_result.expect("Contract initialization results in error");
+
+        let vote_0 = to_bytes(&GetVoteParams {
+            proposal_id: 0 as ProposalId,
+            direction: VoteDirection::For,
+        });
+        let vote_1 = to_bytes(&GetVoteParams {
+            proposal_id: 1 as ProposalId,
+            direction: VoteDirection::For,
+        });
+        let account1 = new_account();
+        let account2 = new_account();
+        let account3 = new_account();
+        for account in [account1, account2].iter() {
+            let ctx = receive_ctx(ACCOUNT_0, *account, 0u64, &vote_0);
+            let res: ContractResult<ActionsTree> =
+                contract_vote(&ctx, &mut LogRecorder::init(), &mut state);
+            res.expect_report("contract voting results in error.");
+        }
+
+        // A new voter is rejected at the cap.
+        let ctx = receive_ctx(ACCOUNT_0, account3, 0u64, &vote_0);
+        let res: ContractResult<ActionsTree> =
+            contract_vote(&ctx, &mut LogRecorder::init(), &mut state);
+        claim_eq!(
+            res,
+            Err(ContractError::VoterLimitReached),
+            "Result should be VoterLimitReached."
+        );
+
+        // An existing voter can still change their vote.
+        let ctx = receive_ctx(ACCOUNT_0, account1, 0u64, &vote_1);
+        let res: ContractResult<ActionsTree> =
+            contract_vote(&ctx, &mut LogRecorder::init(), &mut state);
+        res.expect_report("contract voting results in error.");
+        claim_eq!(
+            state.proposals.get(&0).unwrap().vote_count,
+            1,
+            "something wrong with vote_count"
+        );
+        claim_eq!(
+            state.proposals.get(&1).unwrap().vote_count,
+            1,
+            "something wrong with vote_count"
+        );
+    }
+
+    #[concordium_test]
+    fn test_winning_vote_count() {
+        let parameter_bytes = create_parameter_bytes(&init_parameter());
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        let mut state = state_result.expect("Contract initialization results in error");
+        state.proposals.get_mut(&0).unwrap().vote_count = 2;
+        state.proposals.get_mut(&1).unwrap().vote_count = 5;
+
+        let parameter_bytes = Vec::new();
+        let ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, 0u64, &parameter_bytes);
+        let res: ContractResult<ActionsTree> =
+            contract_winning_proposal(&ctx, &mut LogRecorder::init(), &mut state);
+        res.expect_report("contract winning proposal results in error.");
+        claim_eq!(state.winning_proposal_id, vec![1], "something wrong with winning_proposal_id");
+        claim_eq!(
+            state.winning_vote_count,
+            state.proposals.get(&1).unwrap().vote_count,
+            "winning_vote_count should match the winner's count"
+        );
+
+        let mut logger = LogRecorder::init();
+        let res: ContractResult<ActionsTree> =
+            contract_get_winning_proposal(&ctx, &mut logger, &mut state);
+        res.expect_report("getWinningProposal results in error.");
+        claim_eq!(
+            logger.logs,
+            vec![to_bytes(&TallyResult {
+                winning_proposal_id: vec![1],
+                winning_vote_count: 5,
+                indecisive: false,
+            })],
+            "something wrong with logged result"
+        );
+    }
+
+    fn hex_to_bytes(hex: &str) -> Vec<u8> {
+        (0..hex.len()).step_by(2).map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap()).collect()
+    }
+
+    #[concordium_test]
+    fn test_sha256_known_answers() {
+        claim_eq!(
+            sha256(b"").to_vec(),
+            hex_to_bytes("e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855")
+        );
+        claim_eq!(
+            sha256(b"abc").to_vec(),
+            hex_to_bytes("ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad")
+        );
+        claim_eq!(
+            sha256(b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq").to_vec(),
+            hex_to_bytes("248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1")
+        );
+    }
+
+    fn commit_reveal_state() -> State {
+        let expiry = 100u64;
+        let parameter_bytes = create_parameter_bytes(&InitParams {
+            expiry: Timestamp::from_timestamp_millis(expiry),
+            commit_reveal: true,
+            ..init_parameter()
+        });
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        state_result.expect("Contract initialization results in error")
+    }
+
+    #[concordium_test]
+    fn test_commit_reveal_vote() {
+        let mut state = commit_reveal_state();
+
+        let account1 = new_account();
+        let nonce = [7u8; 32];
+        let params = CommitVoteParams {
+            commitment: sha256(&to_bytes(&(1 as ProposalId, nonce))),
+        };
+        let parameter_bytes = to_bytes(&params);
+        let ctx = receive_ctx(ACCOUNT_0, account1, 50u64, &parameter_bytes);
+        let res: ContractResult<ActionsTree> = contract_commit_vote(&ctx, &mut state);
+        res.expect_report("committing a vote results in error.");
+        claim_eq!(
+            state.proposals.get(&1).unwrap().vote_count,
+            0,
+            "committed vote should not be counted yet"
+        );
+
+        let params = RevealVoteParams {
+            proposal_id: 1,
+            nonce,
+        };
+        let parameter_bytes = to_bytes(&params);
+        let ctx = receive_ctx(ACCOUNT_0, account1, 50u64, &parameter_bytes);
+        let res: ContractResult<ActionsTree> =
+            contract_reveal_vote(&ctx, &mut LogRecorder::init(), &mut state);
+        claim_eq!(res, Err(ContractError::NotExpired), "Result should be NotExpired.");
+
+        let ctx = receive_ctx(ACCOUNT_0, account1, 150u64, &parameter_bytes);
+        let mut logger = LogRecorder::init();
+        let res: ContractResult<ActionsTree> = contract_reveal_vote(&ctx, &mut logger, &mut state);
+        res.expect_report("revealing a vote results in error.");
+        claim_eq!(
+            logger.logs.last(),
+            Some(&receipt_event(Address::Account(account1), 1, 1, 150)),
+            "the revealed vote should get a receipt"
+        );
+        claim_eq!(
+            state.proposals.get(&1).unwrap().vote_count,
+            1,
+            "something wrong with vote_count"
+        );
+
+        let res: ContractResult<ActionsTree> =
+            contract_reveal_vote(&ctx, &mut LogRecorder::init(), &mut state);
+        claim_eq!(res, Err(ContractError::AlreadyRevealed), "Result should be AlreadyRevealed.");
+        claim_eq!(
+            state.proposals.get(&1).unwrap().vote_count,
+            1,
+            "something wrong with vote_count"
+        );
+    }
+
+    #[concordium_test]
+    fn test_commit_reveal_tampered() {
+        let mut state = commit_reveal_state();
+
+        let account1 = new_account();
+        let nonce = [7u8; 32];
+        let params = CommitVoteParams {
+            commitment: sha256(&to_bytes(&(1 as ProposalId, nonce))),
+        };
+        let parameter_bytes = to_bytes(&params);
+        let ctx = receive_ctx(ACCOUNT_0, account1, 50u64, &parameter_bytes);
+        let res: ContractResult<ActionsTree> = contract_commit_vote(&ctx, &mut state);
+        res.expect_report("committing a vote results in error.");
+
+        let params = RevealVoteParams {
+            proposal_id: 0,
+            nonce,
+        };
+        let parameter_bytes = to_bytes(&params);
+        let ctx = receive_ctx(ACCOUNT_0, account1, 150u64, &parameter_bytes);
+        let res: ContractResult<ActionsTree> =
+            contract_reveal_vote(&ctx, &mut LogRecorder::init(), &mut state);
+        claim_eq!(
+            res,
+            Err(ContractError::CommitmentMismatch),
+            "Result should be CommitmentMismatch."
+        );
+        claim_eq!(
+            state.proposals.get(&0).unwrap().vote_count,
+            0,
+            "something wrong with vote_count"
+        );
+    }
+
+    #[concordium_test]
+    fn test_get_proposal() {
+        let parameter_bytes = create_parameter_bytes(&init_parameter());
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        let mut state = state_result.expect("Contract initialization results in error");
+
+        let account1 = new_account();
+        let params = GetProposalParams {
+            proposal_id: 1 as ProposalId,
+        };
+        let parameter_bytes = to_bytes(&params);
+        let ctx = receive_ctx(ACCOUNT_0, account1, 0u64, &parameter_bytes);
+        let mut logger = LogRecorder::init();
+        let res: ContractResult<ActionsTree> = contract_get_proposal(&ctx, &mut logger, &mut state);
+        res.expect_report("getting a proposal results in error.");
+        claim_eq!(
+            logger.logs,
+            vec![to_bytes(&Proposal::new(
+                PROPOSAL_NAME_2.to_string(),
+                PROPOSAL_DESCRIPTION_2.to_string(),
+                None
+            ))],
+            "proposal 1 should be logged"
+        );
+    }
+
+    #[concordium_test]
+    fn test_unknown_proposal_errors_are_consistent() {
+        let parameter_bytes = create_parameter_bytes(&init_parameter());
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        let mut state = state_result.expect("Contract initialization results in error");
+
+        let account1 = new_account();
+        let params = GetVoteParams {
+            proposal_id: 9 as ProposalId,
+            direction: VoteDirection::For,
+        };
+        let parameter_bytes = to_bytes(&params);
+        let ctx = receive_ctx(ACCOUNT_0, account1, 0u64, &parameter_bytes);
+        let res: ContractResult<ActionsTree> =
+            contract_vote(&ctx, &mut LogRecorder::init(), &mut state);
+        claim_eq!(
+            res,
+            Err(ContractError::ProposalIsNotFound),
+            "Result should be ProposalIsNotFound."
+        );
+        let res: ContractResult<ActionsTree> =
+            contract_get_proposal(&ctx, &mut LogRecorder::init(), &mut state);
+        claim_eq!(
+            res,
+            Err(ContractError::ProposalIsNotFound),
+            "Result should be ProposalIsNotFound."
+        );
+    }
+
+    #[concordium_test]
+    fn test_set_expiry() {
+        let parameter_bytes = create_parameter_bytes(&init_parameter());
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        let mut state = state_result.expect("Contract initialization results in error");
+
+        let params = SetExpiryParams {
+            expiry: Timestamp::from_timestamp_millis(200),
+        };
+        let parameter_bytes = to_bytes(&params);
+        let ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, 100u64, &parameter_bytes);
+        let res: ContractResult<ActionsTree> = contract_set_expiry(&ctx, &mut state);
+        res.expect_report("setting the expiry results in error.");
+        claim_eq!(state.expiry, Timestamp::from_timestamp_millis(200), "expiry should be extended");
+
+        let account1 = new_account();
+        let ctx = receive_ctx(ACCOUNT_0, account1, 100u64, &parameter_bytes);
+        let res: ContractResult<ActionsTree> = contract_set_expiry(&ctx, &mut state);
+        claim_eq!(
+            res,
+            Err(ContractError::FromIsNotTheOwner),
+            "Result should be FromIsNotTheOwner."
+        );
+    }
+
+    #[concordium_test]
+    fn test_set_expiry_in_past() {
+        let parameter_bytes = create_parameter_bytes(&init_parameter());
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        let mut state = state_result.expect("Contract initialization results in error");
+
+        // 現在時刻ちょうども過去として扱う。
+        for expiry in [50, 100].iter() {
+            let params = SetExpiryParams {
+                expiry: Timestamp::from_timestamp_millis(*expiry),
+            };
+            let parameter_bytes = to_bytes(&params);
+            let ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, 100u64, &parameter_bytes);
+            let res: ContractResult<ActionsTree> = contract_set_expiry(&ctx, &mut state);
+            claim_eq!(res, Err(ContractError::ExpiryInPast), "Result should be ExpiryInPast.");
+            claim_eq!(
+                state.expiry,
+                Timestamp::from_timestamp_millis(EXPIRY),
+                "expiry should not be changed"
+            );
+        }
+    }
+
+    #[concordium_test]
+    fn test_set_expiry_after_finish() {
+        let parameter_bytes = create_parameter_bytes(&init_parameter());
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        let mut state = state_result.expect("Contract initialization results in error");
+        state.status = Status::Finished;
+
+        let params = SetExpiryParams {
+            expiry: Timestamp::from_timestamp_millis(200),
+        };
+        let parameter_bytes = to_bytes(&params);
+        let ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, 100u64, &parameter_bytes);
+        let res: ContractResult<ActionsTree> = contract_set_expiry(&ctx, &mut state);
+        claim_eq!(res, Err(ContractError::AlreadyFinished), "Result should be AlreadyFinished.");
+    }
+
+    #[concordium_test]
+    fn test_get_proposal_count() {
+        let parameter_bytes = create_parameter_bytes(&init_parameter());
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        let mut state = state_result.expect("Contract initialization results in error");
+
+        let ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, 0u64, &[]);
+        let mut logger = LogRecorder::init();
+        let res: ContractResult<ActionsTree> =
+            contract_get_proposal_count(&ctx, &mut logger, &mut state);
+        res.expect_report("getting the proposal count results in error.");
+        claim_eq!(logger.logs, vec![to_bytes(&2u32)], "there should be 2 proposals");
+    }
+
+    #[concordium_test]
+    fn test_winning_proposal_with_many_proposals() {
+        let proposal_names: Vec<String> = (0..200).map(|i| format!("Proposal {}", i)).collect();
+        let parameter_bytes = create_parameter_bytes(&InitParams {
+            proposal_names,
+            proposal_descriptions: vec![String::new(); 200],
+            proposal_metadata_urls: vec![None; 200],
+            ..init_parameter()
+        });
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        let mut state = state_result.expect("Contract initialization results in error");
+        for (proposal_id, proposal) in state.proposals.iter_mut() {
+            proposal.vote_count = match proposal_id {
+                37 | 150 | 199 => 10,
+                _ => *proposal_id as u64 % 7,
+            };
+        }
+
+        let ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, 0u64, &[]);
+        let res: ContractResult<ActionsTree> =
+            contract_winning_proposal(&ctx, &mut LogRecorder::init(), &mut state);
+        res.expect_report("contract winning proposal results in error.");
+        let mut winners = state.winning_proposal_id.clone();
+        winners.sort();
+        claim_eq!(winners, vec![37, 150, 199], "All tied proposals should win");
+        claim_eq!(state.winning_vote_count, 10, "something wrong with winning_vote_count");
+    }
+
+    #[concordium_test]
+    fn test_abstain() {
+        let parameter_bytes = create_parameter_bytes(&InitParams {
+            finalization_rules: FinalizationRules {
+                quorum: Some(2),
+                ..FinalizationRules::default()
+            },
+            ..init_parameter()
+        });
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        let mut state = state_result.expect("Contract initialization results in error");
+
+        let account1 = new_account();
+        let params = GetVoteParams {
+            proposal_id: 1 as ProposalId,
+            direction: VoteDirection::For,
+        };
+        let parameter_bytes = to_bytes(&params);
+        let ctx = receive_ctx(ACCOUNT_0, account1, 0u64, &parameter_bytes);
+        let res: ContractResult<ActionsTree> =
+            contract_vote(&ctx, &mut LogRecorder::init(), &mut state);
+        res.expect_report("contract voting results in error.");
+
+        let account2 = new_account();
+        let ctx = receive_ctx(ACCOUNT_0, account2, 0u64, &[]);
+        let res: ContractResult<ActionsTree> =
+            contract_abstain(&ctx, &mut LogRecorder::init(), &mut state);
+        res.expect_report("abstaining results in error.");
+        // 二重に棄権しても一回と数える。
+        let res: ContractResult<ActionsTree> =
+            contract_abstain(&ctx, &mut LogRecorder::init(), &mut state);
+        res.expect_report("abstaining results in error.");
+
+        let voter = state.voters.get(&Address::Account(account2)).unwrap();
+        claim_eq!(voter.voted, true, "voted status should be true");
+        claim_eq!(voter.abstained, true, "abstained status should be true");
+        claim_eq!(voter.vote, None, "abstainer should have no proposal");
+        claim_eq!(state.abstain_count, 1, "something wrong with abstain_count");
+        claim_eq!(
+            state.proposals.get(&0).unwrap().vote_count,
+            0,
+            "something wrong with vote_count"
+        );
+        claim_eq!(
+            state.proposals.get(&1).unwrap().vote_count,
+            1,
+            "something wrong with vote_count"
+        );
+
+        // 棄権は定足数に数えられる。
+        let ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, 0u64, &[]);
+        let mut logger = LogRecorder::init();
+        let res: ContractResult<ActionsTree> =
+            contract_winning_proposal(&ctx, &mut logger, &mut state);
+        res.expect_report("contract winning proposal results in error.");
+        claim_eq!(logger.logs.len(), 0, "quorum should be reached");
+        claim_eq!(state.winning_proposal_id, vec![1], "something wrong with winning_proposal_id");
+    }
+
+    #[concordium_test]
+    fn test_abstain_then_vote() {
+        let parameter_bytes = create_parameter_bytes(&init_parameter());
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        let mut state = state_result.expect("Contract initialization results in error");
+
+        let account1 = new_account();
+        let ctx = receive_ctx(ACCOUNT_0, account1, 0u64, &[]);
+        let res: ContractResult<ActionsTree> =
+            contract_abstain(&ctx, &mut LogRecorder::init(), &mut state);
+        res.expect_report("abstaining results in error.");
+        claim_eq!(state.abstain_count, 1, "something wrong with abstain_count");
+
+        let params = GetVoteParams {
+            proposal_id: 0 as ProposalId,
+            direction: VoteDirection::For,
+        };
+        let parameter_bytes = to_bytes(&params);
+        let ctx = receive_ctx(ACCOUNT_0, account1, 0u64, &parameter_bytes);
+        let res: ContractResult<ActionsTree> =
+            contract_vote(&ctx, &mut LogRecorder::init(), &mut state);
+        res.expect_report("contract voting results in error.");
+        claim_eq!(state.abstain_count, 0, "something wrong with abstain_count");
+        claim_eq!(
+            state.proposals.get(&0).unwrap().vote_count,
+            1,
+            "something wrong with vote_count"
+        );
+
+        let res: ContractResult<ActionsTree> =
+            contract_abstain(&ctx, &mut LogRecorder::init(), &mut state);
+        res.expect_report("abstaining results in error.");
+        claim_eq!(
+            state.proposals.get(&0).unwrap().vote_count,
+            0,
+            "something wrong with vote_count"
+        );
+        let res: ContractResult<ActionsTree> = cancel_vote(&ctx, &mut state);
+        res.expect_report("cancel voting results in error.");
+        claim_eq!(state.abstain_count, 0, "something wrong with abstain_count");
+        claim_eq!(
+            state.voters.get(&Address::Account(account1)).unwrap().abstained,
+            false,
+            "abstained status should be false"
+        );
+    }
+
+    #[concordium_test]
+    fn test_reject_codes() {
+        let codes = [
+            (ContractError::ParseParams, -1),
+            (ContractError::LogFull, -2),
+            (ContractError::LogMalformed, -3),
+            (ContractError::FromIsNotTheOwner, -4),
+            (ContractError::AlreadyFinished, -5),
+            (ContractError::Expired, -6),
+            (ContractError::NotExpired, -7),
+            (ContractError::VoterIsNotFound, -8),
+            (ContractError::NotVoted, -9),
+            (ContractError::ProposalIsNotFound, -10),
+            (ContractError::DelegationCycle, -11),
+            (ContractError::VoterLimitReached, -12),
+            (ContractError::WrongVotingMode, -13),
+            (ContractError::CommitmentMismatch, -14),
+            (ContractError::AlreadyRevealed, -15),
+            (ContractError::ExpiryInPast, -16),
+            (ContractError::NoRightToVote, -17),
+            (ContractError::ProposalFull, -18),
+            (ContractError::VotingNotStarted, -19),
+            (ContractError::NotYetTallied, -20),
+            (ContractError::VoteCountOverflow, -21),
+            (ContractError::ProposalListMismatch, -22),
+            (ContractError::CorrectionWindowClosed, -23),
+            (ContractError::UnsupportedVersion, -24),
+            (ContractError::AlreadyVoted, -25),
+            (ContractError::AlreadyHasRightToVote, -26),
+            (ContractError::MetadataUrlTooLong, -27),
+            (ContractError::DelegationTooDeep, -28),
+            (ContractError::InvariantViolation, -29),
+            (ContractError::VotingAlreadyStarted, -30),
+            (ContractError::WriteInsDisabled, -31),
+            (ContractError::ProposalLimitReached, -32),
+            (ContractError::OwnerCannotVote, -33),
+            (ContractError::TooManyChanges, -34),
+            (ContractError::AlreadyCancelled, -35),
+            (ContractError::InvalidWeight, -36),
+            (ContractError::SelfDelegation, -37),
+            (ContractError::InvalidApprovalThreshold, -38),
+            (ContractError::InvalidSeats, -39),
+            (ContractError::NoProposals, -40),
+            (ContractError::TitleTooLong, -41),
+            (ContractError::DescriptionTooLong, -42),
+            (ContractError::ContractSealed, -43),
+            (ContractError::ChangesDisabled, -44),
+            (ContractError::NotTheTokenContract, -45),
+            (ContractError::CooldownActive, -46),
+            (ContractError::VotersCleared, -47),
+            (ContractError::IncompatibleModes, -48),
+            (ContractError::ProposalNameTooLong, -49),
+        ];
+        for (error, code) in codes.iter() {
+            claim_eq!(Reject::from(*error).error_code.get(), *code, "reject code changed");
+        }
+    }
+
+    #[concordium_test]
+    fn test_revoke_right_to_vote() {
+        let parameter_bytes = create_parameter_bytes(&init_parameter());
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        let mut state = state_result.expect("Contract initialization results in error");
+
+        let account1 = new_account();
+        let params = GetVoteParams {
+            proposal_id: 1 as ProposalId,
+            direction: VoteDirection::For,
+        };
+        let vote_bytes = to_bytes(&params);
+        let ctx = receive_ctx(ACCOUNT_0, account1, 0u64, &vote_bytes);
+        let res: ContractResult<ActionsTree> =
+            contract_vote(&ctx, &mut LogRecorder::init(), &mut state);
+        res.expect_report("contract voting results in error.");
+
+        let params = GetVoterParams {
+            voter_address: Address::Account(account1),
+        };
+        let parameter_bytes = to_bytes(&params);
+        let ctx = receive_ctx(ACCOUNT_0, account1, 0u64, &parameter_bytes);
+        let res: ContractResult<ActionsTree> =
+            contract_revoke_right_to_vote(&ctx, &mut LogRecorder::init(), &mut state);
+        claim_eq!(
+            res,
+            Err(ContractError::FromIsNotTheOwner),
+            "Result should be FromIsNotTheOwner."
+        );
+
+        let ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, 0u64, &parameter_bytes);
+        let res: ContractResult<ActionsTree> =
+            contract_revoke_right_to_vote(&ctx, &mut LogRecorder::init(), &mut state);
+        res.expect_report("revoking the right to vote results in error.");
+        claim_eq!(
+            state.voters.get(&Address::Account(account1)).unwrap().weight,
+            0,
+            "weight should be 0"
+        );
+        claim_eq!(
+            state.proposals.get(&1).unwrap().vote_count,
+            0,
+            "something wrong with vote_count"
+        );
+
+        let ctx = receive_ctx(ACCOUNT_0, account1, 0u64, &vote_bytes);
+        let res: ContractResult<ActionsTree> =
+            contract_vote(&ctx, &mut LogRecorder::init(), &mut state);
+        claim_eq!(res, Err(ContractError::NoRightToVote), "Result should be NoRightToVote.");
+        claim_eq!(
+            state.proposals.get(&1).unwrap().vote_count,
+            0,
+            "something wrong with vote_count"
+        );
+    }
+
+    #[concordium_test]
+    fn test_revoke_right_to_vote_not_voted() {
+        let parameter_bytes = create_parameter_bytes(&init_parameter());
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        let mut state = state_result.expect("Contract initialization results in error");
+
+        // 投票してからキャンセルした投票者。
+        let account1 = new_account();
+        let params = GetVoteParams {
+            proposal_id: 0 as ProposalId,
+            direction: VoteDirection::For,
+        };
+        let vote_bytes = to_bytes(&params);
+        let ctx = receive_ctx(ACCOUNT_0, account1, 0u64, &vote_bytes);
+        let res: ContractResult<ActionsTree> =
+            contract_vote(&ctx, &mut LogRecorder::init(), &mut state);
+        res.expect_report("contract voting results in error.");
+        let res: ContractResult<ActionsTree> = cancel_vote(&ctx, &mut state);
+        res.expect_report("cancel voting results in error.");
+
+        let params = GetVoterParams {
+            voter_address: Address::Account(account1),
+        };
+        let parameter_bytes = to_bytes(&params);
+        let ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, 0u64, &parameter_bytes);
+        let res: ContractResult<ActionsTree> =
+            contract_revoke_right_to_vote(&ctx, &mut LogRecorder::init(), &mut state);
+        res.expect_report("revoking the right to vote results in error.");
+        claim_eq!(
+            state.voters.get(&Address::Account(account1)).unwrap().weight,
+            0,
+            "weight should be 0"
+        );
+        claim_eq!(
+            state.proposals.get(&0).unwrap().vote_count,
+            0,
+            "something wrong with vote_count"
+        );
+
+        let params = GetVoterParams {
+            voter_address: Address::Account(new_account()),
+        };
+        let parameter_bytes = to_bytes(&params);
+        let ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, 0u64, &parameter_bytes);
+        let res: ContractResult<ActionsTree> =
+            contract_revoke_right_to_vote(&ctx, &mut LogRecorder::init(), &mut state);
+        claim_eq!(res, Err(ContractError::VoterIsNotFound), "Result should be VoterIsNotFound.");
+    }
+
+    #[concordium_test]
+    fn test_emit_snapshot() {
+        let parameter_bytes = create_parameter_bytes(&init_parameter());
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        let mut state = state_result.expect("Contract initialization results in error");
+
+        for proposal_id in [1, 1].iter() {
+            let params = GetVoteParams {
+                proposal_id: *proposal_id,
+                direction: VoteDirection::For,
+            };
+            let parameter_bytes = to_bytes(&params);
+            let ctx = receive_ctx(ACCOUNT_0, new_account(), 0u64, &parameter_bytes);
+            let res: ContractResult<ActionsTree> =
+                contract_vote(&ctx, &mut LogRecorder::init(), &mut state);
+            res.expect_report("contract voting results in error.");
+        }
+
+        let ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, 0u64, &[]);
+        let mut logger = LogRecorder::init();
+        let res: ContractResult<ActionsTree> =
+            contract_emit_snapshot(&ctx, &mut logger, &mut state);
+        res.expect_report("emitting a snapshot results in error.");
+        claim_eq!(
+            logger.logs,
+            vec![to_bytes(&Event::Snapshot {
+                counts: vec![(0, 0), (1, 2)],
+                total: 2,
+            })],
+            "something wrong with the snapshot"
+        );
+    }
+
+    #[concordium_test]
+    fn test_cancel_vote_for_removed_proposal() {
+        let parameter_bytes = create_parameter_bytes(&init_parameter());
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        let mut state = state_result.expect("Contract initialization results in error");
+
+        let account1 = new_account();
+        let params = GetVoteParams {
+            proposal_id: 1 as ProposalId,
+            direction: VoteDirection::For,
+        };
+        let parameter_bytes = to_bytes(&params);
+        let ctx = receive_ctx(ACCOUNT_0, account1, 0u64, &parameter_bytes);
+        let res: ContractResult<ActionsTree> =
+            contract_vote(&ctx, &mut LogRecorder::init(), &mut state);
+        res.expect_report("contract voting results in error.");
+        state.proposals.remove(&1);
+
+        let res: ContractResult<ActionsTree> = cancel_vote(&ctx, &mut state);
+        res.expect_report("cancel voting results in error.");
+        let voter = state.voters.get(&Address::Account(account1)).unwrap();
+        claim_eq!(voter.voted, false, "voted status should be false");
+        claim_eq!(voter.vote, None, "voter should have no active vote");
+        claim_eq!(state.proposals.get(&1), None, "the removed proposal should not come back");
+        claim_eq!(
+            state.proposals.get(&0).unwrap().vote_count,
+            0,
+            "something wrong with vote_count"
+        );
+    }
+
+    fn get_voters(state: &mut State, start: u32, limit: u32) -> Vec<Vec<u8>> {
+        let params = GetVotersParams {
+            start,
+            limit,
+        };
+        let parameter_bytes = to_bytes(&params);
+        let ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, 0u64, &parameter_bytes);
+        let mut logger = LogRecorder::init();
+        let res: ContractResult<ActionsTree> = contract_get_voters(&ctx, &mut logger, state);
+        res.expect_report("getting voters results in error.");
+        logger.logs
+    }
+
+    #[concordium_test]
+    fn test_get_voters() {
+        let parameter_bytes = create_parameter_bytes(&init_parameter());
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        let mut state = state_result.expect("Contract initialization results in error");
+
+        let params = GetVoteParams {
+            proposal_id: 0 as ProposalId,
+            direction: VoteDirection::For,
+        };
+        let parameter_bytes = to_bytes(&params);
+        let mut addresses = vec![];
+        for _ in 0..3 {
+            let account = new_account();
+            let ctx = receive_ctx(ACCOUNT_0, account, 0u64, &parameter_bytes);
+            let res: ContractResult<ActionsTree> =
+                contract_vote(&ctx, &mut LogRecorder::init(), &mut state);
+            res.expect_report("contract voting results in error.");
+            addresses.push(Address::Account(account));
+        }
+        addresses.sort();
+        let entries: Vec<(Address, VoterState)> = addresses
+            .iter()
+            .map(|address| (*address, state.voters.get(address).unwrap().clone()))
+            .collect();
+
+        claim_eq!(
+            get_voters(&mut state, 0, 2),
+            vec![to_bytes(&entries[..2].to_vec())],
+            "first page should hold the first two voters"
+        );
+        claim_eq!(
+            get_voters(&mut state, 2, 2),
+            vec![to_bytes(&entries[2..].to_vec())],
+            "last page should hold the remaining voter"
+        );
+        claim_eq!(
+            get_voters(&mut state, 5, 2),
+            vec![to_bytes(&Vec::<(Address, VoterState)>::new())],
+            "a start past the end should give an empty page"
+        );
+    }
+
+    #[concordium_test]
+    fn test_max_votes_per_proposal() {
+        let parameter_bytes = create_parameter_bytes(&InitParams {
+            max_votes_per_proposal: Some(1),
+            ..init_parameter()
+        });
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        let mut state = state_result.expect("Contract initialization results in error");
+
+        let account1 = new_account();
+        let account2 = new_account();
+        let vote_for_0 = to_bytes(&GetVoteParams {
+            proposal_id: 0 as ProposalId,
+            direction: VoteDirection::For,
+        });
+        let vote_for_1 = to_bytes(&GetVoteParams {
+            proposal_id: 1 as ProposalId,
+            direction: VoteDirection::For,
+        });
+
+        let ctx = receive_ctx(ACCOUNT_0, account1, 0u64, &vote_for_0);
+        let res: ContractResult<ActionsTree> =
+            contract_vote(&ctx, &mut LogRecorder::init(), &mut state);
+        res.expect_report("contract voting results in error.");
+        // 同じproposalへの再投票は上限に数えない。
+        let res: ContractResult<ActionsTree> =
+            contract_vote(&ctx, &mut LogRecorder::init(), &mut state);
+        res.expect_report("contract voting results in error.");
+
+        let ctx = receive_ctx(ACCOUNT_0, account2, 0u64, &vote_for_0);
+        let res: ContractResult<ActionsTree> =
+            contract_vote(&ctx, &mut LogRecorder::init(), &mut state);
+        claim_eq!(res, Err(ContractError::ProposalFull), "Result should be ProposalFull.");
+
+        // 上限に達したproposalから他へ投票し直せる。
+        let ctx = receive_ctx(ACCOUNT_0, account1, 0u64, &vote_for_1);
+        let res: ContractResult<ActionsTree> =
+            contract_vote(&ctx, &mut LogRecorder::init(), &mut state);
+        res.expect_report("contract voting results in error.");
+        let ctx = receive_ctx(ACCOUNT_0, account2, 0u64, &vote_for_0);
+        let res: ContractResult<ActionsTree> =
+            contract_vote(&ctx, &mut LogRecorder::init(), &mut state);
+        res.expect_report("contract voting results in error.");
+        claim_eq!(
+            state.proposals.get(&0).unwrap().vote_count,
+            1,
+            "something wrong with vote_count"
+        );
+        claim_eq!(
+            state.proposals.get(&1).unwrap().vote_count,
+            1,
+            "something wrong with vote_count"
+        );
+    }
+
+    #[concordium_test]
+    fn test_open_voting() {
+        let parameter_bytes = create_parameter_bytes(&InitParams {
+            start_open: false,
+            ..init_parameter()
+        });
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        let mut state = state_result.expect("Contract initialization results in error");
+        claim_eq!(state.status, Status::NotStarted, "Status should be NotStarted");
+
+        let account1 = new_account();
+        claim_eq!(
+            why_cant_i_vote(&mut state, account1, 0),
+            vec![to_bytes(&Some("Voting has not started yet.".to_string()))],
+            "something wrong with the blocking reason"
+        );
+
+        let params = GetVoteParams {
+            proposal_id: 1 as ProposalId,
+            direction: VoteDirection::For,
+        };
+        let vote_bytes = to_bytes(&params);
+        let ctx = receive_ctx(ACCOUNT_0, account1, 0u64, &vote_bytes);
+        let res: ContractResult<ActionsTree> =
+            contract_vote(&ctx, &mut LogRecorder::init(), &mut state);
+        claim_eq!(res, Err(ContractError::VotingNotStarted), "Result should be VotingNotStarted.");
+
+        let ctx = receive_ctx(ACCOUNT_0, account1, 0u64, &[]);
+        let res: ContractResult<ActionsTree> = contract_open_voting(&ctx, &mut state);
+        claim_eq!(
+            res,
+            Err(ContractError::FromIsNotTheOwner),
+            "Result should be FromIsNotTheOwner."
+        );
+
+        let ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, 0u64, &[]);
+        let res: ContractResult<ActionsTree> = contract_open_voting(&ctx, &mut state);
+        res.expect_report("opening voting results in error.");
+        claim_eq!(state.status, Status::InProcess, "Status should be InProcess");
+
+        let ctx = receive_ctx(ACCOUNT_0, account1, 0u64, &vote_bytes);
+        let res: ContractResult<ActionsTree> =
+            contract_vote(&ctx, &mut LogRecorder::init(), &mut state);
+        res.expect_report("contract voting results in error.");
+        claim_eq!(
+            state.proposals.get(&1).unwrap().vote_count,
+            1,
+            "something wrong with vote_count"
+        );
+    }
+
+    #[concordium_test]
+    fn test_add_vote_count_to_unknown_proposal() {
+        let parameter_bytes = create_parameter_bytes(&init_parameter());
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        let mut state = state_result.expect("Contract initialization results in error");
+
+        claim_eq!(
+            state.add_vote_count(&9, VoteDirection::For, 1, Timestamp::from_timestamp_millis(0)),
+            Err(ContractError::ProposalIsNotFound),
+            "Result should be ProposalIsNotFound."
+        );
+        claim_eq!(state.proposals.len(), 2, "no proposal should be created");
+    }
+
+    fn get_distribution(vote_counts: [u64; 2]) -> Vec<Vec<u8>> {
+        let parameter_bytes = create_parameter_bytes(&init_parameter());
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        let mut state = state_result.expect("Contract initialization results in error");
+        state.proposals.get_mut(&0).unwrap().vote_count = vote_counts[0];
+        state.proposals.get_mut(&1).unwrap().vote_count = vote_counts[1];
+
+        let ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, 0u64, &[]);
+        let mut logger = LogRecorder::init();
+        let res: ContractResult<ActionsTree> =
+            contract_get_distribution(&ctx, &mut logger, &mut state);
+        res.expect_report("getting the distribution results in error.");
+        logger.logs
+    }
+
+    #[concordium_test]
+    fn test_get_distribution() {
+        claim_eq!(
+            get_distribution([0, 0]),
+            vec![to_bytes(&vec![(0 as ProposalId, 0u32), (1, 0)])],
+            "no votes should give all zeros"
+        );
+        claim_eq!(
+            get_distribution([3, 3]),
+            vec![to_bytes(&vec![(0 as ProposalId, 5000u32), (1, 5000)])],
+            "something wrong with an even split"
+        );
+        claim_eq!(
+            get_distribution([1, 2]),
+            vec![to_bytes(&vec![(0 as ProposalId, 3333u32), (1, 6667)])],
+            "the shares should be rounded to sum to 10000"
+        );
+    }
+
+    #[concordium_test]
+    fn test_init_with_expiry_in_past() {
+        let parameter_bytes = create_parameter_bytes(&InitParams {
+            expiry: Timestamp::from_timestamp_millis(50),
+            ..init_parameter()
+        });
+        let mut ctx = parametrized_init_ctx(&parameter_bytes);
+        ctx.set_metadata_slot_time(Timestamp::from_timestamp_millis(100));
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        claim_eq!(
+            state_result.err(),
+            Some(ContractError::ExpiryInPast),
+            "Result should be ExpiryInPast."
+        );
+    }
+
+    #[concordium_test]
+    fn test_delegation_events() {
+        let parameter_bytes = create_parameter_bytes(&init_parameter());
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        let mut state = state_result.expect("Contract initialization results in error");
+
+        let delegator = Address::Account(new_account());
+        let delegatee = Address::Account(new_account());
+        let parameter_bytes = to_bytes(&DelegateParams {
+            to: delegatee,
+        });
+        let mut ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, 0u64, &parameter_bytes);
+        ctx.set_sender(delegator);
+        let mut logger = LogRecorder::init();
+        let res: ContractResult<ActionsTree> = contract_delegate(&ctx, &mut logger, &mut state);
+        res.expect_report("delegation results in error.");
+        claim_eq!(
+            logger.logs,
+            vec![to_bytes(&Event::Delegated {
+                from: delegator,
+                to: delegatee,
+                weight: 1,
+            })],
+            "something wrong with the delegation event"
+        );
+
+        let vote_bytes = to_bytes(&GetVoteParams {
+            proposal_id: 0 as ProposalId,
+            direction: VoteDirection::For,
+        });
+        let mut ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, 0u64, &vote_bytes);
+        ctx.set_sender(delegator);
+        let mut logger = LogRecorder::init();
+        let res: ContractResult<ActionsTree> = contract_vote(&ctx, &mut logger, &mut state);
+        res.expect_report("contract voting results in error.");
+        claim_eq!(
+            logger.logs,
+            vec![
+                to_bytes(&Event::DelegationRevoked {
+                    from: delegator,
+                    to: delegatee,
+                    weight: 1,
+                }),
+                to_bytes(&Event::FirstVote {
+                    voter: delegator,
+                    proposal_id: 0,
+                    vote_count: 1,
+                }),
+                receipt_event(delegator, 0, 1, 0),
+            ],
+            "something wrong with the revocation event"
+        );
+    }
+
+    #[concordium_test]
+    fn test_clear_voters() {
+        let account1 = new_account();
+        let parameter_bytes = create_parameter_bytes(&InitParams {
+            correction_window: Duration::from_millis(100),
+            eligible: vec![Address::Account(account1)],
+            ..init_parameter()
+        });
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        let mut state = state_result.expect("Contract initialization results in error");
+
+        let params = GetVoteParams {
+            proposal_id: 1 as ProposalId,
+            direction: VoteDirection::For,
+        };
+        let parameter_bytes = to_bytes(&params);
+        let ctx = receive_ctx(ACCOUNT_0, account1, 0u64, &parameter_bytes);
+        let res: ContractResult<ActionsTree> =
+            contract_vote(&ctx, &mut LogRecorder::init(), &mut state);
+        res.expect_report("contract voting results in error.");
+
+        let ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, 0u64, &[]);
+        let res: ContractResult<ActionsTree> = contract_clear_voters(&ctx, &mut state);
+        claim_eq!(res, Err(ContractError::NotYetTallied), "Result should be NotYetTallied.");
+        claim_eq!(state.voters.len(), 1, "voters should be kept before the tally");
+
+        let res: ContractResult<ActionsTree> =
+            contract_winning_proposal(&ctx, &mut LogRecorder::init(), &mut state);
+        res.expect_report("contract winning proposal results in error.");
+        let res: ContractResult<ActionsTree> = contract_clear_voters(&ctx, &mut state);
+        res.expect_report("clearing voters results in error.");
+        claim_eq!(state.voters.len(), 0, "voters should be cleared");
+        claim_eq!(
+            state.proposals.get(&1).unwrap().vote_count,
+            1,
+            "something wrong with vote_count"
+        );
+        claim_eq!(state.winning_proposal_id, vec![1], "something wrong with winning_proposal_id");
+        claim_eq!(state.winning_vote_count, 1, "something wrong with winning_vote_count");
+        claim_eq!(state.eligible_count, 0, "eligible_count should be cleared");
+
+        let res: ContractResult<ActionsTree> = contract_check_invariants(&ctx, &mut state);
+        claim_eq!(res, Ok(ActionsTree::accept()), "the state should stay consistent");
+
+        let res: ContractResult<ActionsTree> = contract_reopen_tally(&ctx, &mut state);
+        claim_eq!(res, Err(ContractError::VotersCleared), "Result should be VotersCleared.");
+        claim_eq!(state.proposals[&1].vote_count, 1, "the counts should be kept");
+    }
+
+    #[concordium_test]
+    fn test_weight_above_u32() {
+        let parameter_bytes = create_parameter_bytes(&init_parameter());
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        let mut state = state_result.expect("Contract initialization results in error");
+
+        let delegator = Address::Account(new_account());
+        state.voters.insert(
+            delegator,
+            VoterState {
+                weight: u32::MAX as u64,
+                ..VoterState::default()
+            },
+        );
+
+        let account1 = new_account();
+        let params = GetVoteParams {
+            proposal_id: 1 as ProposalId,
+            direction: VoteDirection::For,
+        };
+        let parameter_bytes = to_bytes(&params);
+        let ctx = receive_ctx(ACCOUNT_0, account1, 0u64, &parameter_bytes);
+        let res: ContractResult<ActionsTree> =
+            contract_vote(&ctx, &mut LogRecorder::init(), &mut state);
+        res.expect_report("contract voting results in error.");
+
+        let parameter_bytes = to_bytes(&DelegateParams {
+            to: Address::Account(account1),
+        });
+        let mut ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, 0u64, &parameter_bytes);
+        ctx.set_sender(delegator);
+        let res: ContractResult<ActionsTree> =
+            contract_delegate(&ctx, &mut LogRecorder::init(), &mut state);
+        res.expect_report("delegation results in error.");
+        claim_eq!(
+            state.proposals.get(&1).unwrap().vote_count,
+            u32::MAX as u64 + 1,
+            "something wrong with vote_count"
+        );
+        claim_eq!(state.total_votes(), Ok(u32::MAX as u64 + 1), "something wrong with total votes");
+    }
+
+    #[concordium_test]
+    fn test_vote_count_overflow() {
+        let parameter_bytes = create_parameter_bytes(&init_parameter());
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        let mut state = state_result.expect("Contract initialization results in error");
+        state.proposals.get_mut(&1).unwrap().vote_count = u64::MAX;
+
+        let params = GetVoteParams {
+            proposal_id: 1 as ProposalId,
+            direction: VoteDirection::For,
+        };
+        let parameter_bytes = to_bytes(&params);
+        let ctx = receive_ctx(ACCOUNT_0, new_account(), 0u64, &parameter_bytes);
+        let res: ContractResult<ActionsTree> =
+            contract_vote(&ctx, &mut LogRecorder::init(), &mut state);
+        claim_eq!(
+            res,
+            Err(ContractError::VoteCountOverflow),
+            "Result should be VoteCountOverflow."
+        );
+    }
+
+    #[concordium_test]
+    fn test_time_remaining() {
+        let parameter_bytes = create_parameter_bytes(&InitParams {
+            expiry: Timestamp::from_timestamp_millis(1_000),
+            ..init_parameter()
+        });
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        let mut state = state_result.expect("Contract initialization results in error");
+
+        let ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, 400u64, &[]);
+        let mut logger = LogRecorder::init();
+        let res: ContractResult<ActionsTree> =
+            contract_time_remaining(&ctx, &mut logger, &mut state);
+        res.expect_report("getting the time remaining results in error.");
+        claim_eq!(logger.logs, vec![to_bytes(&600i64)], "something wrong with the time remaining");
+
+        let ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, 1_250u64, &[]);
+        let mut logger = LogRecorder::init();
+        let res: ContractResult<ActionsTree> =
+            contract_time_remaining(&ctx, &mut logger, &mut state);
+        res.expect_report("getting the time remaining results in error.");
+        claim_eq!(logger.logs, vec![to_bytes(&-250i64)], "the time remaining should be negative");
+
+        // 猶予期間の終わりまで数える。
+        state.grace_period_ms = 500;
+        let mut logger = LogRecorder::init();
+        let res: ContractResult<ActionsTree> =
+            contract_time_remaining(&ctx, &mut logger, &mut state);
+        res.expect_report("getting the time remaining results in error.");
+        claim_eq!(
+            logger.logs,
+            vec![to_bytes(&250i64)],
+            "the time remaining should include the grace period"
+        );
+    }
+
+    #[concordium_test]
+    fn test_init_with_mismatched_descriptions() {
+        let parameter_bytes = create_parameter_bytes(&InitParams {
+            proposal_descriptions: vec![PROPOSAL_DESCRIPTION_1.to_string()],
+            ..init_parameter()
+        });
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        claim_eq!(
+            state_result.err(),
+            Some(ContractError::ProposalListMismatch),
+            "Result should be ProposalListMismatch."
+        );
+    }
+
+    #[concordium_test]
+    fn test_init_with_descriptions() {
+        let parameter_bytes = create_parameter_bytes(&init_parameter());
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        let state = state_result.expect("Contract initialization results in error");
+        claim_eq!(
+            state.proposals.get(&0).unwrap().description,
+            PROPOSAL_DESCRIPTION_1.to_string(),
+            "something wrong with the description"
+        );
+        claim_eq!(
+            state.proposals.get(&1).unwrap().description,
+            PROPOSAL_DESCRIPTION_2.to_string(),
+            "something wrong with the description"
+        );
+    }
+
+    #[concordium_test]
+    fn test_revote_same_selection_after_weight_change() {
+        let parameter_bytes = create_parameter_bytes(&init_parameter());
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        let mut state = state_result.expect("Contract initialization results in error");
+
+        let account1 = new_account();
+        let params = GetVoteParams {
+            proposal_id: 1 as ProposalId,
+            direction: VoteDirection::For,
+        };
+        let parameter_bytes = to_bytes(&params);
+        let ctx = receive_ctx(ACCOUNT_0, account1, 0u64, &parameter_bytes);
+        let res: ContractResult<ActionsTree> =
+            contract_vote(&ctx, &mut LogRecorder::init(), &mut state);
+        res.expect_report("contract voting results in error.");
+        claim_eq!(
+            state.proposals.get(&1).unwrap().vote_count,
+            1,
+            "something wrong with vote_count"
+        );
+
+        state.voters.get_mut(&Address::Account(account1)).unwrap().weight = 3;
+        let res: ContractResult<ActionsTree> =
+            contract_vote(&ctx, &mut LogRecorder::init(), &mut state);
+        res.expect_report("contract voting results in error.");
+        claim_eq!(
+            state.proposals.get(&1).unwrap().vote_count,
+            3,
+            "something wrong with vote_count"
+        );
+        claim_eq!(
+            state.voters.get(&Address::Account(account1)).unwrap().counted_weight,
+            3,
+            "something wrong with counted_weight"
+        );
+
+        // 重みが変わらなければ再投票は何もしない。
+        let res: ContractResult<ActionsTree> =
+            contract_vote(&ctx, &mut LogRecorder::init(), &mut state);
+        res.expect_report("contract voting results in error.");
+        claim_eq!(
+            state.proposals.get(&1).unwrap().vote_count,
+            3,
+            "something wrong with vote_count"
+        );
+    }
+
+    #[concordium_test]
+    fn test_init_logs_created() {
+        let parameter_bytes = create_parameter_bytes(&init_parameter());
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let mut logger = LogRecorder::init();
+        let state_result = contract_init(&ctx, &mut logger);
+        state_result.expect("Contract initialization results in error");
+        claim_eq!(
+            logger.logs,
+            vec![to_bytes(&Event::Created {
+                title: TITLE.to_string(),
+                proposal_ids_and_names: vec![
+                    (0, PROPOSAL_NAME_1.to_string()),
+                    (1, PROPOSAL_NAME_2.to_string())
+                ],
+                expiry: Timestamp::from_timestamp_millis(EXPIRY),
+            })],
+            "something wrong with the creation event"
+        );
+    }
+
+    #[concordium_test]
+    fn test_init_logs_created_in_chunks() {
+        let proposal_names: Vec<String> = (0..200).map(|i| format!("Proposal {}", i)).collect();
+        let parameter_bytes = create_parameter_bytes(&InitParams {
+            proposal_names: proposal_names.clone(),
+            proposal_descriptions: vec![String::new(); 200],
+            proposal_metadata_urls: vec![None; 200],
+            ..init_parameter()
+        });
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let mut logger = LogRecorder::init();
+        let state_result = contract_init(&ctx, &mut logger);
+        state_result.expect("Contract initialization results in error");
+        claim!(logger.logs.len() > 1, "the proposals should be spread over several events");
+
+        let mut logged = vec![];
+        for log in logger.logs.iter() {
+            claim!(log.len() <= constants::MAX_LOG_SIZE, "an event should fit in a log entry");
+            match from_bytes::<Event>(log) {
+                Ok(Event::Created {
+                    proposal_ids_and_names,
+                    ..
+                }) => logged.extend(proposal_ids_and_names),
+                _ => fail!("only creation events should be logged"),
+            }
+        }
+        let expected: Vec<(ProposalId, String)> = proposal_names
+            .into_iter()
+            .enumerate()
+            .map(|(i, name)| (i as ProposalId, name))
+            .collect();
+        claim_eq!(logged, expected, "every proposal should be logged once in order");
+    }
+
+    #[concordium_test]
+    fn test_indecisive_margin() {
+        let rules = || FinalizationRules {
+            min_margin: Some(2),
+            ..FinalizationRules::default()
+        };
+
+        let (state, _) = tally_fixture(
+            InitParams {
+                finalization_rules: rules(),
+                ..init_parameter()
+            },
+            &[5, 3],
+            0,
+        );
+        claim_eq!(state.indecisive, false, "a margin of 2 should be decisive");
+        claim_eq!(state.winning_proposal_id, vec![0], "something wrong with winning_proposal_id");
+
+        let (state, _) = tally_fixture(
+            InitParams {
+                finalization_rules: rules(),
+                ..init_parameter()
+            },
+            &[4, 3],
+            0,
+        );
+        claim_eq!(state.indecisive, true, "a margin of 1 should be indecisive");
+        claim_eq!(state.winning_proposal_id, vec![], "No winner should be declared");
+    }
+
+    #[concordium_test]
+    fn test_get_voter_choice() {
+        let parameter_bytes = create_parameter_bytes(&init_parameter());
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        let mut state = state_result.expect("Contract initialization results in error");
+
+        let account1 = new_account();
+        let vote_bytes = to_bytes(&GetVoteParams {
+            proposal_id: 1 as ProposalId,
+            direction: VoteDirection::For,
+        });
+        let ctx = receive_ctx(ACCOUNT_0, account1, 0u64, &vote_bytes);
+        let res: ContractResult<ActionsTree> =
+            contract_vote(&ctx, &mut LogRecorder::init(), &mut state);
+        res.expect_report("contract voting results in error.");
+
+        let parameter_bytes = to_bytes(&GetVoterParams {
+            voter_address: Address::Account(account1),
+        });
+        let query_ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, 0u64, &parameter_bytes);
+        let mut logger = LogRecorder::init();
+        let res: ContractResult<ActionsTree> =
+            contract_get_voter_choice(&query_ctx, &mut logger, &mut state);
+        res.expect_report("getting the voter choice results in error.");
+        claim_eq!(logger.logs, vec![to_bytes(&Some(1 as ProposalId))], "voter should pick 1");
+
+        let res: ContractResult<ActionsTree> = cancel_vote(&ctx, &mut state);
+        res.expect_report("cancel voting results in error.");
+        let mut logger = LogRecorder::init();
+        let res: ContractResult<ActionsTree> =
+            contract_get_voter_choice(&query_ctx, &mut logger, &mut state);
+        res.expect_report("getting the voter choice results in error.");
+        claim_eq!(logger.logs, vec![to_bytes(&None::<ProposalId>)], "voter should have no choice");
+
+        let parameter_bytes = to_bytes(&GetVoterParams {
+            voter_address: Address::Account(new_account()),
+        });
+        let query_ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, 0u64, &parameter_bytes);
+        let res: ContractResult<ActionsTree> =
+            contract_get_voter_choice(&query_ctx, &mut LogRecorder::init(), &mut state);
+        claim_eq!(res, Err(ContractError::VoterIsNotFound), "Result should be VoterIsNotFound.");
+    }
+
+    #[concordium_test]
+    fn test_reopen_tally() {
+        let (mut state, _) = tally_fixture(
+            InitParams {
+                correction_window: Duration::from_millis(50),
+                ..init_parameter()
+            },
+            &[0, 2],
+            100,
+        );
+
+        let ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, 150u64, &[]);
+        let res: ContractResult<ActionsTree> = contract_reopen_tally(&ctx, &mut state);
+        res.expect_report("reopening the tally results in error.");
+        claim_eq!(state.status, Status::InProcess, "Status should be InProcess");
+        claim_eq!(state.winning_proposal_id, vec![], "winning_proposal_id should be cleared");
+        claim_eq!(state.finished_at, None, "finished_at should be cleared");
+    }
+
+    #[concordium_test]
+    fn test_reopen_tally_after_window() {
+        let (mut state, _) = tally_fixture(
+            InitParams {
+                correction_window: Duration::from_millis(50),
+                ..init_parameter()
+            },
+            &[0, 2],
+            100,
+        );
+
+        let ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, 151u64, &[]);
+        let res: ContractResult<ActionsTree> = contract_reopen_tally(&ctx, &mut state);
+        claim_eq!(
+            res,
+            Err(ContractError::CorrectionWindowClosed),
+            "Result should be CorrectionWindowClosed."
+        );
+        claim_eq!(state.status, Status::Finished, "Status should be Finished");
+        claim_eq!(state.winning_proposal_id, vec![1], "winning_proposal_id should be kept");
+    }
+
+    #[concordium_test]
+    fn test_get_proposal_voters() {
+        let parameter_bytes = create_parameter_bytes(&init_parameter());
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        let mut state = state_result.expect("Contract initialization results in error");
+
+        let mut voters_0 = vec![];
+        let mut voters_1 = vec![];
+        for proposal_id in [0, 1, 1].iter() {
+            let account = Address::Account(new_account());
+            let parameter_bytes = to_bytes(&GetVoteParams {
+                proposal_id: *proposal_id,
+                direction: VoteDirection::For,
+            });
+            let mut ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, 0u64, &parameter_bytes);
+            ctx.set_sender(account);
+            let res: ContractResult<ActionsTree> =
+                contract_vote(&ctx, &mut LogRecorder::init(), &mut state);
+            res.expect_report("contract voting results in error.");
+            if *proposal_id == 0 {
+                voters_0.push(account);
+            } else {
+                voters_1.push(account);
+            }
+        }
+        voters_1.sort();
+
+        for (proposal_id, expected) in [(0, voters_0), (1, voters_1)].iter() {
+            let parameter_bytes = to_bytes(&GetProposalParams {
+                proposal_id: *proposal_id,
+            });
+            let ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, 0u64, &parameter_bytes);
+            let mut logger = LogRecorder::init();
+            let res: ContractResult<ActionsTree> =
+                contract_get_proposal_voters(&ctx, &mut logger, &mut state);
+            res.expect_report("getting the proposal voters results in error.");
+            claim_eq!(logger.logs, vec![to_bytes(expected)], "something wrong with the voters");
+        }
+    }
+
+    #[concordium_test]
+    fn test_init_version() {
+        let parameter_bytes = create_parameter_bytes(&InitParams {
+            version: CONTRACT_VERSION,
+            ..init_parameter()
+        });
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        state_result.expect("Contract initialization results in error");
+
+        let parameter_bytes = create_parameter_bytes(&InitParams {
+            version: CONTRACT_VERSION + 1,
+            ..init_parameter()
+        });
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        claim_eq!(
+            state_result.err(),
+            Some(ContractError::UnsupportedVersion),
+            "Result should be UnsupportedVersion."
+        );
+    }
+
+    #[concordium_test]
+    fn test_cancel_vote_with_zero_weight() {
+        let parameter_bytes = create_parameter_bytes(&init_parameter());
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        let mut state = state_result.expect("Contract initialization results in error");
+
+        let account1 = new_account();
+        state.voters.insert(
+            Address::Account(account1),
+            VoterState {
+                weight: 0,
+                voted: true,
+                vote: Some(1),
+                ..VoterState::default()
+            },
+        );
+
+        let ctx = receive_ctx(ACCOUNT_0, account1, 0u64, &[]);
+        let res: ContractResult<ActionsTree> = cancel_vote(&ctx, &mut state);
+        res.expect_report("cancel voting results in error.");
+        let voter = state.voters.get(&Address::Account(account1)).unwrap();
+        claim_eq!(voter.voted, false, "voted status should be false");
+        claim_eq!(voter.vote, None, "voter should have no active vote");
+        claim_eq!(
+            state.proposals.get(&1).unwrap().vote_count,
+            0,
+            "something wrong with vote_count"
+        );
+    }
+
+    #[concordium_test]
+    fn test_anonymize_events() {
+        for anonymize_events in [false, true].iter() {
+            let parameter_bytes = create_parameter_bytes(&InitParams {
+                anonymize_events: *anonymize_events,
+                ..init_parameter()
+            });
+            let ctx = parametrized_init_ctx(&parameter_bytes);
+            let state_result = contract_init(&ctx, &mut LogRecorder::init());
+            let mut state = state_result.expect("Contract initialization results in error");
+
+            let account1 = new_account();
+            let parameter_bytes = to_bytes(&GetVoteParams {
+                proposal_id: 1 as ProposalId,
+                direction: VoteDirection::For,
+            });
+            let ctx = receive_ctx(ACCOUNT_0, account1, 0u64, &parameter_bytes);
+            let mut logger = LogRecorder::init();
+            let res: ContractResult<ActionsTree> = contract_vote(&ctx, &mut logger, &mut state);
+            res.expect_report("contract voting results in error.");
+
+            let voter = Address::Account(account1);
+            let expected = if *anonymize_events {
+                to_bytes(&Event::AnonymousVote {
+                    voter_hash: sha256(&to_bytes(&voter)),
+                    proposal_id: 1,
+                    vote_count: 1,
+                })
+            } else {
+                to_bytes(&Event::FirstVote {
+                    voter,
+                    proposal_id: 1,
+                    vote_count: 1,
+                })
+            };
+            claim_eq!(
+                logger.logs,
+                vec![expected, receipt_event(voter, 1, 1, 0)],
+                "something wrong with the vote event"
+            );
+        }
+    }
+
+    #[concordium_test]
+    fn test_force_finish() {
+        let parameter_bytes = create_parameter_bytes(&InitParams {
+            expiry: Timestamp::from_timestamp_millis(1_000),
+            ..init_parameter()
+        });
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        let mut state = state_result.expect("Contract initialization results in error");
+
+        let parameter_bytes = to_bytes(&GetVoteParams {
+            proposal_id: 0 as ProposalId,
+            direction: VoteDirection::For,
+        });
+        let ctx = receive_ctx(ACCOUNT_0, new_account(), 10u64, &parameter_bytes);
+        let res: ContractResult<ActionsTree> =
+            contract_vote(&ctx, &mut LogRecorder::init(), &mut state);
+        res.expect_report("contract voting results in error.");
+
+        let parameter_bytes = to_bytes(&ForceFinishParams {
+            reason: "The poll was compromised.".to_string(),
+        });
+        let ctx = receive_ctx(ACCOUNT_0, new_account(), 20u64, &parameter_bytes);
+        let res: ContractResult<ActionsTree> =
+            contract_force_finish(&ctx, &mut LogRecorder::init(), &mut state);
+        claim_eq!(
+            res,
+            Err(ContractError::FromIsNotTheOwner),
+            "Result should be FromIsNotTheOwner."
+        );
+
+        let ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, 20u64, &parameter_bytes);
+        let mut logger = LogRecorder::init();
+        let res: ContractResult<ActionsTree> = contract_force_finish(&ctx, &mut logger, &mut state);
+        res.expect_report("force finishing results in error.");
+        claim_eq!(state.status, Status::Finished, "Status should be Finished");
+        claim_eq!(state.winning_proposal_id, vec![0], "something wrong with winning_proposal_id");
+        claim_eq!(
+            logger.logs,
+            vec![to_bytes(&Event::ForceFinished {
+                reason: "The poll was compromised.".to_string(),
+            })],
+            "something wrong with the logged reason"
+        );
+    }
+
+    #[concordium_test]
+    fn test_get_proposals() {
+        let parameter_bytes = create_parameter_bytes(&init_parameter());
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        let mut state = state_result.expect("Contract initialization results in error");
+
+        for proposal_id in [1, 1, 0].iter() {
+            let parameter_bytes = to_bytes(&GetVoteParams {
+                proposal_id: *proposal_id,
+                direction: VoteDirection::For,
+            });
+            let ctx = receive_ctx(ACCOUNT_0, new_account(), 0u64, &parameter_bytes);
+            let res: ContractResult<ActionsTree> =
+                contract_vote(&ctx, &mut LogRecorder::init(), &mut state);
+            res.expect_report("contract voting results in error.");
+        }
+
+        let ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, 0u64, &[]);
+        let mut logger = LogRecorder::init();
+        let res: ContractResult<ActionsTree> =
+            contract_get_proposals(&ctx, &mut logger, &mut state);
+        res.expect_report("getting the proposals results in error.");
+        claim_eq!(
+            logger.logs,
+            vec![to_bytes(&vec![
+                ProposalEntry {
+                    proposal_id: 0,
+                    name: PROPOSAL_NAME_1.to_string(),
+                    vote_count: 1,
+                },
+                ProposalEntry {
+                    proposal_id: 1,
+                    name: PROPOSAL_NAME_2.to_string(),
+                    vote_count: 2,
+                },
+            ])],
+            "something wrong with the proposals"
+        );
+    }
+
+    #[concordium_test]
+    fn test_allow_revote() {
+        for allow_revote in [false, true].iter() {
+            let parameter_bytes = create_parameter_bytes(&InitParams {
+                allow_revote: *allow_revote,
+                ..init_parameter()
+            });
+            let ctx = parametrized_init_ctx(&parameter_bytes);
+            let state_result = contract_init(&ctx, &mut LogRecorder::init());
+            let mut state = state_result.expect("Contract initialization results in error");
+
+            let voter = new_account();
+            let parameter_bytes = to_bytes(&GetVoteParams {
+                proposal_id: 0 as ProposalId,
+                direction: VoteDirection::For,
+            });
+            let ctx = receive_ctx(ACCOUNT_0, voter, 0u64, &parameter_bytes);
+            let res: ContractResult<ActionsTree> =
+                contract_vote(&ctx, &mut LogRecorder::init(), &mut state);
+            res.expect_report("contract voting results in error.");
+
+            let parameter_bytes = to_bytes(&GetVoteParams {
+                proposal_id: 1 as ProposalId,
+                direction: VoteDirection::For,
+            });
+            let ctx = receive_ctx(ACCOUNT_0, voter, 0u64, &parameter_bytes);
+            let res: ContractResult<ActionsTree> =
+                contract_vote(&ctx, &mut LogRecorder::init(), &mut state);
+            if *allow_revote {
+                res.expect_report("re-voting results in error.");
+                claim_eq!(state.proposals[&0].vote_count, 0, "the old vote should be retracted");
+                claim_eq!(state.proposals[&1].vote_count, 1, "the new vote should be counted");
+            } else {
+                claim_eq!(res, Err(ContractError::AlreadyVoted), "Result should be AlreadyVoted.");
+                claim_eq!(state.proposals[&0].vote_count, 1, "the first vote should be kept");
+                claim_eq!(state.proposals[&1].vote_count, 0, "the second vote should be rejected");
+            }
+        }
+    }
+
+    #[concordium_test]
+    fn test_allow_revote_final_ballot() {
+        let parameter_bytes = create_parameter_bytes(&InitParams {
+            allow_revote: false,
+            ..init_parameter()
+        });
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        let mut state = state_result.expect("Contract initialization results in error");
+
+        let vote = |state: &mut State, voter: AccountAddress, proposal_id: ProposalId| {
+            let parameter_bytes = to_bytes(&GetVoteParams {
+                proposal_id,
+                direction: VoteDirection::For,
+            });
+            let ctx = receive_ctx(ACCOUNT_0, voter, 0u64, &parameter_bytes);
+            contract_vote::<ActionsTree>(&ctx, &mut LogRecorder::init(), state)
+        };
+        let delegate = |state: &mut State, voter: AccountAddress, to: AccountAddress| {
+            let parameter_bytes = to_bytes(&DelegateParams {
+                to: Address::Account(to),
+            });
+            let ctx = receive_ctx(ACCOUNT_0, voter, 0u64, &parameter_bytes);
+            contract_delegate::<ActionsTree>(&ctx, &mut LogRecorder::init(), state)
+        };
+
+        // 投票した後は取り消しも棄権も委任もできない。
+        let voter = new_account();
+        vote(&mut state, voter, 0).expect_report("contract voting results in error.");
+        let ctx = receive_ctx(ACCOUNT_0, voter, 0u64, &[]);
+        let res: ContractResult<ActionsTree> = cancel_vote(&ctx, &mut state);
+        claim_eq!(res, Err(ContractError::AlreadyVoted), "cancelVote should be rejected");
+        let res: ContractResult<ActionsTree> =
+            contract_abstain(&ctx, &mut LogRecorder::init(), &mut state);
+        claim_eq!(res, Err(ContractError::AlreadyVoted), "abstain should be rejected");
+        let other = new_account();
+        claim_eq!(
+            delegate(&mut state, voter, other),
+            Err(ContractError::AlreadyVoted),
+            "delegate should be rejected"
+        );
+        claim_eq!(state.proposals[&0].vote_count, 1, "the first vote should be kept");
+
+        // 棄権した後は投票できない。
+        let abstainer = new_account();
+        let ctx = receive_ctx(ACCOUNT_0, abstainer, 0u64, &[]);
+        let res: ContractResult<ActionsTree> =
+            contract_abstain(&ctx, &mut LogRecorder::init(), &mut state);
+        res.expect_report("abstaining results in error.");
+        claim_eq!(
+            vote(&mut state, abstainer, 1),
+            Err(ContractError::AlreadyVoted),
+            "a vote after abstaining should be rejected"
+        );
+
+        // 委任した後は投票も委任先の変更もできない。
+        let delegator = new_account();
+        delegate(&mut state, delegator, voter).expect_report("delegating results in error.");
+        claim_eq!(
+            vote(&mut state, delegator, 1),
+            Err(ContractError::AlreadyVoted),
+            "a vote after delegating should be rejected"
+        );
+        claim_eq!(
+            delegate(&mut state, delegator, other),
+            Err(ContractError::AlreadyVoted),
+            "a second delegation should be rejected"
+        );
+        claim_eq!(state.proposals[&0].vote_count, 2, "the delegated weight should be kept");
+        claim_eq!(state.proposals[&1].vote_count, 0, "no later ballot should count");
+    }
+
+    #[concordium_test]
+    fn test_proposal_metadata_url() {
+        let metadata_url = "https://example.com/proposals/0".to_string();
+        let parameter_bytes = create_parameter_bytes(&InitParams {
+            proposal_metadata_urls: vec![Some(metadata_url.clone()), None],
+            ..init_parameter()
+        });
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        let mut state = state_result.expect("Contract initialization results in error");
+
+        let expected = [
+            Proposal::new(
+                PROPOSAL_NAME_1.to_string(),
+                PROPOSAL_DESCRIPTION_1.to_string(),
+                Some(metadata_url),
+            ),
+            Proposal::new(PROPOSAL_NAME_2.to_string(), PROPOSAL_DESCRIPTION_2.to_string(), None),
+        ];
+        for (proposal_id, proposal) in expected.iter().enumerate() {
+            let parameter_bytes = to_bytes(&GetProposalParams {
+                proposal_id: proposal_id as ProposalId,
+            });
+            let ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, 0u64, &parameter_bytes);
+            let mut logger = LogRecorder::init();
+            let res: ContractResult<ActionsTree> =
+                contract_get_proposal(&ctx, &mut logger, &mut state);
+            res.expect_report("getting a proposal results in error.");
+            claim_eq!(logger.logs, vec![to_bytes(proposal)], "something wrong with the proposal");
+        }
+    }
+
+    #[concordium_test]
+    fn test_init_with_too_long_metadata_url() {
+        let parameter_bytes = create_parameter_bytes(&InitParams {
+            proposal_metadata_urls: vec![None, Some("a".repeat(MAX_METADATA_URL_LENGTH + 1))],
+            ..init_parameter()
+        });
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        claim_eq!(
+            state_result.err(),
+            Some(ContractError::MetadataUrlTooLong),
+            "Result should be MetadataUrlTooLong."
+        );
+    }
+
+    #[concordium_test]
+    fn test_is_open() {
+        let parameter_bytes = create_parameter_bytes(&InitParams {
+            expiry: Timestamp::from_timestamp_millis(100),
+            start_open: false,
+            ..init_parameter()
+        });
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        let mut state = state_result.expect("Contract initialization results in error");
+        let at = Timestamp::from_timestamp_millis;
+
+        claim!(!state.is_open(at(50)), "voting should not be open before it is started");
+        claim_eq!(state.ensure_open(at(50)), Err(ContractError::VotingNotStarted));
+
+        state.status = Status::InProcess;
+        claim!(state.is_open(at(0)), "voting should be open once started");
+        claim!(state.is_open(at(100)), "voting should be open at the expiry");
+        claim!(!state.is_open(at(101)), "voting should be closed after the expiry");
+        claim_eq!(state.ensure_open(at(101)), Err(ContractError::Expired));
+
+        state.status = Status::Finished;
+        claim!(!state.is_open(at(50)), "voting should be closed after the tally");
+        claim_eq!(state.ensure_open(at(50)), Err(ContractError::AlreadyFinished));
+    }
+
+    fn get_winner_names(state: &mut State) -> (ContractResult<ActionsTree>, Vec<Vec<u8>>) {
+        let ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, 0u64, &[]);
+        let mut logger = LogRecorder::init();
+        let res = contract_get_winner_names(&ctx, &mut logger, state);
+        (res, logger.logs)
+    }
+
+    #[concordium_test]
+    fn test_get_winner_names() {
+        let parameter_bytes = create_parameter_bytes(&init_parameter());
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        let mut state = state_result.expect("Contract initialization results in error");
+        let (res, _) = get_winner_names(&mut state);
+        claim_eq!(res, Err(ContractError::NotYetTallied), "Result should be NotYetTallied.");
+
+        let (mut state, _) = tally_fixture(init_parameter(), &[1, 3], 0);
+        let (res, logs) = get_winner_names(&mut state);
+        res.expect_report("getting the winner names results in error.");
+        claim_eq!(
+            logs,
+            vec![to_bytes(&vec![PROPOSAL_NAME_2.to_string()])],
+            "the single winner's name should be logged"
+        );
+
+        let (mut state, _) = tally_fixture(
+            InitParams {
+                tie_break: TieBreak::ReturnAll,
+                ..init_parameter()
+            },
+            &[2, 2],
+            0,
+        );
+        let (res, logs) = get_winner_names(&mut state);
+        res.expect_report("getting the winner names results in error.");
+        claim_eq!(
+            logs,
+            vec![to_bytes(&vec![PROPOSAL_NAME_1.to_string(), PROPOSAL_NAME_2.to_string()])],
+            "all tied winners' names should be logged"
+        );
+    }
+
+    #[concordium_test]
+    fn test_delegation_too_deep() {
+        let parameter_bytes = create_parameter_bytes(&init_parameter());
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        let mut state = state_result.expect("Contract initialization results in error");
+
+        let accounts: Vec<AccountAddress> =
+            (0..=MAX_DELEGATION_DEPTH + 1).map(|_| new_account()).collect();
+        for (i, pair) in accounts.windows(2).enumerate() {
+            let parameter_bytes = to_bytes(&DelegateParams {
+                to: Address::Account(pair[1]),
+            });
+            let ctx = receive_ctx(ACCOUNT_0, pair[0], 0u64, &parameter_bytes);
+            let res: ContractResult<ActionsTree> =
+                contract_delegate(&ctx, &mut LogRecorder::init(), &mut state);
+            if i < MAX_DELEGATION_DEPTH {
+                res.expect_report("delegating results in error.");
+            } else {
+                claim_eq!(
+                    res,
+                    Err(ContractError::DelegationTooDeep),
+                    "Result should be DelegationTooDeep."
+                );
+            }
+        }
+        claim_eq!(
+            state
+                .get_voter(&Address::Account(accounts[MAX_DELEGATION_DEPTH]))
+                .and_then(|a| a.delegate),
+            None,
+            "the delegation past the limit should not be recorded"
+        );
+    }
+
+    #[concordium_test]
+    fn test_state_layout_round_trip() {
+        let parameter_bytes = create_parameter_bytes(&init_parameter());
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        let mut state = state_result.expect("Contract initialization results in error");
+        let parameter_bytes = to_bytes(&GetVoteParams {
+            proposal_id: 1 as ProposalId,
+            direction: VoteDirection::For,
+        });
+        let ctx = receive_ctx(ACCOUNT_0, new_account(), 0u64, &parameter_bytes);
+        let res: ContractResult<ActionsTree> =
+            contract_vote(&ctx, &mut LogRecorder::init(), &mut state);
+        res.expect_report("contract voting results in error.");
+
+        let bytes = to_bytes(&state);
+        claim_eq!(bytes[0], STATE_VERSION, "the state should start with its layout version");
+        claim_eq!(from_bytes::<State>(&bytes), Ok(state), "the state should round trip");
+
+        let mut bytes = bytes;
+        bytes[0] = STATE_VERSION + 1;
+        claim!(from_bytes::<State>(&bytes).is_err(), "an unknown layout should be rejected");
+    }
+
+    #[concordium_test]
+    fn test_state_from_v1() {
+        let voter = Address::Account(new_account());
+        let non_voter = Address::Account(new_account());
+        let mut voters = Map::default();
+        voters.insert(
+            voter,
+            VoterStateV1 {
+                weight: 3,
+                voted: true,
+                vote: 1,
+            },
+        );
+        voters.insert(
+            non_voter,
+            VoterStateV1 {
+                weight: 1,
+                voted: false,
+                vote: 0,
+            },
+        );
+        let mut proposals = Map::default();
+        proposals.insert(
+            0,
+            ProposalV1 {
+                name: PROPOSAL_NAME_1.to_string(),
+                vote_count: 0,
+            },
+        );
+        proposals.insert(
+            1,
+            ProposalV1 {
+                name: PROPOSAL_NAME_2.to_string(),
+                vote_count: 3,
+            },
+        );
+        let mut bytes = vec![1u8];
+        bytes.extend(to_bytes(&StateV1 {
+            voters,
+            proposals,
+            status: Status::Finished,
+            winning_proposal_id: vec![1],
+            title: TITLE.to_string(),
+            description: DESCRIPTION.to_string(),
+            expiry: Timestamp::from_timestamp_millis(EXPIRY),
+        }));
+
+        let state = from_bytes::<State>(&bytes).expect_report("the v1 state should deserialize");
+        claim_eq!(state.layout_version, STATE_VERSION, "the state should be upgraded");
+        claim_eq!(state.status, Status::Finished, "Status should be Finished");
+        claim_eq!(state.title, TITLE.to_string(), "something wrong with the title");
+        claim_eq!(state.winning_proposal_id, vec![1], "something wrong with winning_proposal_id");
+        claim_eq!(state.winning_vote_count, 3, "something wrong with winning_vote_count");
+        claim_eq!(state.proposals[&1].vote_count, 3, "the vote count should be widened");
+        claim_eq!(state.proposals[&1].name, PROPOSAL_NAME_2.to_string(), "the name should be kept");
+        claim_eq!(
+            state.get_voter(&voter),
+            Some(&VoterState {
+                weight: 3,
+                voted: true,
+                vote: Some(1),
+                counted_weight: 3,
+                last_vote: Some(1),
+                ..Default::default()
+            }),
+            "something wrong with the voter who voted"
+        );
+        claim_eq!(
+            state.get_voter(&non_voter),
+            Some(&VoterState {
+                weight: 1,
+                ..Default::default()
+            }),
+            "a voter who did not vote should have no vote"
+        );
+    }
+
+    fn get_rank(vote_counts: &[u64], proposal_id: ProposalId) -> Vec<Vec<u8>> {
+        let proposal_names: Vec<String> =
+            (0..vote_counts.len()).map(|i| format!("Proposal {}", i)).collect();
+        let parameter_bytes = create_parameter_bytes(&InitParams {
+            proposal_descriptions: vec![String::new(); vote_counts.len()],
+            proposal_metadata_urls: vec![None; vote_counts.len()],
+            proposal_names,
+            ..init_parameter()
+        });
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        let mut state = state_result.expect("Contract initialization results in error");
+        for (i, vote_count) in vote_counts.iter().enumerate() {
+            state.proposals.get_mut(&(i as ProposalId)).unwrap().vote_count = *vote_count;
+        }
+
+        let parameter_bytes = to_bytes(&GetProposalParams {
+            proposal_id,
+        });
+        let ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, 0u64, &parameter_bytes);
+        let mut logger = LogRecorder::init();
+        let res: ContractResult<ActionsTree> = contract_get_rank(&ctx, &mut logger, &mut state);
+        res.expect_report("getting the rank results in error.");
+        logger.logs
+    }
+
+    #[concordium_test]
+    fn test_get_rank() {
+        claim_eq!(get_rank(&[5, 9, 2], 1), vec![to_bytes(&1u32)], "the leader should rank 1st");
+        claim_eq!(get_rank(&[5, 9, 2], 0), vec![to_bytes(&2u32)], "proposal 0 should rank 2nd");
+        claim_eq!(get_rank(&[5, 9, 2], 2), vec![to_bytes(&3u32)], "proposal 2 should rank 3rd");
+    }
+
+    #[concordium_test]
+    fn test_get_rank_with_tie() {
+        claim_eq!(get_rank(&[7, 7, 2], 0), vec![to_bytes(&1u32)], "tied leaders should rank 1st");
+        claim_eq!(get_rank(&[7, 7, 2], 1), vec![to_bytes(&1u32)], "tied leaders should rank 1st");
+        claim_eq!(get_rank(&[7, 7, 2], 2), vec![to_bytes(&3u32)], "the rank after a tie skips");
+    }
+
+    #[concordium_test]
+    fn test_linear_decay() {
+        let parameter_bytes = create_parameter_bytes(&InitParams {
+            expiry: Timestamp::from_timestamp_millis(1_000),
+            weight_policy: WeightPolicy::LinearDecay {
+                window: Duration::from_millis(1_000),
+            },
+            ..init_parameter()
+        });
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        let mut state = state_result.expect("Contract initialization results in error");
+
+        // 早い投票ほど重く数えられる。
+        for (proposal_id, slot_time) in [(0, 0u64), (1, 750)].iter() {
+            let parameter_bytes = to_bytes(&GetVoteParams {
+                proposal_id: *proposal_id,
+                direction: VoteDirection::For,
+            });
+            let ctx = receive_ctx(ACCOUNT_0, new_account(), *slot_time, &parameter_bytes);
+            let res: ContractResult<ActionsTree> =
+                contract_vote(&ctx, &mut LogRecorder::init(), &mut state);
+            res.expect_report("contract voting results in error.");
+        }
+        claim_eq!(state.proposals[&0].vote_count, DECAY_SCALE, "an early vote counts in full");
+        claim_eq!(state.proposals[&1].vote_count, DECAY_SCALE / 4, "a late vote counts less");
+
+        // 委任された重みも委任先の投票時刻で数えられる。
+        let voter = new_account();
+        let parameter_bytes = to_bytes(&GetVoteParams {
+            proposal_id: 1,
+            direction: VoteDirection::For,
+        });
+        let ctx = receive_ctx(ACCOUNT_0, voter, 500u64, &parameter_bytes);
+        let res: ContractResult<ActionsTree> =
+            contract_vote(&ctx, &mut LogRecorder::init(), &mut state);
+        res.expect_report("contract voting results in error.");
+        let parameter_bytes = to_bytes(&DelegateParams {
+            to: Address::Account(voter),
+        });
+        let ctx = receive_ctx(ACCOUNT_0, new_account(), 900u64, &parameter_bytes);
+        let res: ContractResult<ActionsTree> =
+            contract_delegate(&ctx, &mut LogRecorder::init(), &mut state);
+        res.expect_report("delegating results in error.");
+        claim_eq!(
+            state.proposals[&1].vote_count,
+            DECAY_SCALE / 4 + DECAY_SCALE,
+            "the delegated weight should decay with the delegate's vote"
+        );
+    }
+
+    #[concordium_test]
+    fn test_check_invariants() {
+        let parameter_bytes = create_parameter_bytes(&init_parameter());
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        let mut state = state_result.expect("Contract initialization results in error");
+
+        let voter = new_account();
+        let parameter_bytes = to_bytes(&GetVoteParams {
+            proposal_id: 0 as ProposalId,
+            direction: VoteDirection::For,
+        });
+        let ctx = receive_ctx(ACCOUNT_0, voter, 0u64, &parameter_bytes);
+        let res: ContractResult<ActionsTree> =
+            contract_vote(&ctx, &mut LogRecorder::init(), &mut state);
+        res.expect_report("contract voting results in error.");
+        let parameter_bytes = to_bytes(&DelegateParams {
+            to: Address::Account(voter),
+        });
+        let ctx = receive_ctx(ACCOUNT_0, new_account(), 0u64, &parameter_bytes);
+        let res: ContractResult<ActionsTree> =
+            contract_delegate(&ctx, &mut LogRecorder::init(), &mut state);
+        res.expect_report("delegating results in error.");
+
+        let ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, 0u64, &[]);
+        let res: ContractResult<ActionsTree> = contract_check_invariants(&ctx, &mut state);
+        res.expect_report("a consistent state should pass the check.");
+
+        state.proposals.get_mut(&1).unwrap().vote_count += 1;
+        let res: ContractResult<ActionsTree> = contract_check_invariants(&ctx, &mut state);
+        claim_eq!(
+            res,
+            Err(ContractError::InvariantViolation),
+            "Result should be InvariantViolation."
+        );
+    }
+
+    #[concordium_test]
+    fn test_edit_proposal() {
+        let parameter_bytes = create_parameter_bytes(&init_parameter());
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        let mut state = state_result.expect("Contract initialization results in error");
+
+        let edit = |proposal_id: ProposalId, new_name: &str| {
+            to_bytes(&EditProposalParams {
+                proposal_id,
+                new_name: new_name.to_string(),
+            })
+        };
+
+        let parameter_bytes = edit(1, "Fixed name");
+        let ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, 0u64, &parameter_bytes);
+        let res: ContractResult<ActionsTree> = contract_edit_proposal(&ctx, &mut state);
+        res.expect_report("editing a proposal results in error.");
+        claim_eq!(state.proposals[&1].name, "Fixed name".to_string(), "the name should change");
+
+        let parameter_bytes = edit(2, "Unknown");
+        let ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, 0u64, &parameter_bytes);
+        let res: ContractResult<ActionsTree> = contract_edit_proposal(&ctx, &mut state);
+        claim_eq!(
+            res,
+            Err(ContractError::ProposalIsNotFound),
+            "Result should be ProposalIsNotFound."
+        );
+
+        let parameter_bytes = to_bytes(&GetVoteParams {
+            proposal_id: 0 as ProposalId,
+            direction: VoteDirection::For,
+        });
+        let ctx = receive_ctx(ACCOUNT_0, new_account(), 0u64, &parameter_bytes);
+        let res: ContractResult<ActionsTree> =
+            contract_vote(&ctx, &mut LogRecorder::init(), &mut state);
+        res.expect_report("contract voting results in error.");
+
+        let parameter_bytes = edit(1, "Too late");
+        let ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, 0u64, &parameter_bytes);
+        let res: ContractResult<ActionsTree> = contract_edit_proposal(&ctx, &mut state);
+        claim_eq!(
+            res,
+            Err(ContractError::VotingAlreadyStarted),
+            "Result should be VotingAlreadyStarted."
+        );
+        claim_eq!(state.proposals[&1].name, "Fixed name".to_string(), "the name should be kept");
+    }
+
+    #[concordium_test]
+    fn test_recount() {
+        let parameter_bytes = create_parameter_bytes(&init_parameter());
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        let mut state = state_result.expect("Contract initialization results in error");
+
+        let voter = new_account();
+        for (sender, proposal_id) in [(voter, 0), (new_account(), 1), (new_account(), 1)].iter() {
+            let parameter_bytes = to_bytes(&GetVoteParams {
+                proposal_id: *proposal_id,
+                direction: VoteDirection::For,
+            });
+            let ctx = receive_ctx(ACCOUNT_0, *sender, 0u64, &parameter_bytes);
+            let res: ContractResult<ActionsTree> =
+                contract_vote(&ctx, &mut LogRecorder::init(), &mut state);
+            res.expect_report("contract voting results in error.");
+        }
+        // 委任の連鎖も数え直す。
+        let delegator = new_account();
+        for (sender, to) in [(delegator, voter), (new_account(), delegator)].iter() {
+            let parameter_bytes = to_bytes(&DelegateParams {
+                to: Address::Account(*to),
+            });
+            let ctx = receive_ctx(ACCOUNT_0, *sender, 0u64, &parameter_bytes);
+            let res: ContractResult<ActionsTree> =
+                contract_delegate(&ctx, &mut LogRecorder::init(), &mut state);
+            res.expect_report("delegating results in error.");
+        }
+
+        state.proposals.get_mut(&0).unwrap().vote_count = 7;
+        state.proposals.get_mut(&1).unwrap().vote_count = 0;
+
+        let ctx = receive_ctx(ACCOUNT_0, new_account(), 0u64, &[]);
+        let res: ContractResult<ActionsTree> = contract_recount(&ctx, &mut state);
+        claim_eq!(
+            res,
+            Err(ContractError::FromIsNotTheOwner),
+            "Result should be FromIsNotTheOwner."
+        );
+
+        let ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, 0u64, &[]);
+        let res: ContractResult<ActionsTree> = contract_recount(&ctx, &mut state);
+        res.expect_report("recounting results in error.");
+        claim_eq!(state.proposals[&0].vote_count, 3, "the delegated weight should be recounted");
+        claim_eq!(state.proposals[&1].vote_count, 2, "the direct votes should be recounted");
+        claim_eq!(state.total_votes(), Ok(5), "the total should match the voters");
+        let res: ContractResult<ActionsTree> = contract_check_invariants(&ctx, &mut state);
+        res.expect_report("the recounted state should be consistent.");
+    }
+
+    fn vote_write_in(state: &mut State, name: &str) -> ContractResult<ActionsTree> {
+        let parameter_bytes = to_bytes(&WriteInParams {
+            name: name.to_string(),
+        });
+        let ctx = receive_ctx(ACCOUNT_0, new_account(), 0u64, &parameter_bytes);
+        contract_vote_write_in(&ctx, &mut LogRecorder::init(), state)
+    }
+
+    #[concordium_test]
+    fn test_vote_write_in() {
+        let parameter_bytes = create_parameter_bytes(&InitParams {
+            allow_write_ins: true,
+            ..init_parameter()
+        });
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        let mut state = state_result.expect("Contract initialization results in error");
+
+        let res = vote_write_in(&mut state, PROPOSAL_NAME_2);
+        res.expect_report("voting an existing name results in error.");
+        claim_eq!(state.proposals.len(), 2, "no proposal should be added");
+        claim_eq!(state.proposals[&1].vote_count, 1, "the existing proposal should get the vote");
+
+        let res = vote_write_in(&mut state, "Write-in");
+        res.expect_report("writing in a proposal results in error.");
+        claim_eq!(state.proposals.len(), 3, "the write-in should be added");
+        claim_eq!(
+            state.proposals[&2].name,
+            "Write-in".to_string(),
+            "something wrong with the name"
+        );
+        claim_eq!(state.proposals[&2].vote_count, 1, "the write-in should get the vote");
+
+        let res = vote_write_in(&mut state, "Write-in");
+        res.expect_report("voting a written-in name results in error.");
+        claim_eq!(state.proposals.len(), 3, "the write-in should not be added twice");
+        claim_eq!(state.proposals[&2].vote_count, 2, "the write-in should get the second vote");
+
+        let account1 = new_account();
+        let parameter_bytes = to_bytes(&WriteInParams {
+            name: "Write-in".to_string(),
+        });
+        let ctx = receive_ctx(ACCOUNT_0, account1, 0u64, &parameter_bytes);
+        let mut logger = LogRecorder::init();
+        let res: ContractResult<ActionsTree> =
+            contract_vote_write_in(&ctx, &mut logger, &mut state);
+        res.expect_report("voting a written-in name results in error.");
+        claim_eq!(
+            logger.logs.last(),
+            Some(&receipt_event(Address::Account(account1), 2, 1, 0)),
+            "the write-in vote should get a receipt"
+        );
+    }
+
+    #[concordium_test]
+    fn test_vote_write_in_disabled() {
+        let parameter_bytes = create_parameter_bytes(&init_parameter());
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        let mut state = state_result.expect("Contract initialization results in error");
+
+        let res = vote_write_in(&mut state, "Write-in");
+        claim_eq!(res, Err(ContractError::WriteInsDisabled), "Result should be WriteInsDisabled.");
+        claim_eq!(state.proposals.len(), 2, "no proposal should be added");
+    }
+
+    fn participation_after_votes(grants: &[u64], votes: &[ProposalId]) -> Vec<Vec<u8>> {
+        let parameter_bytes = create_parameter_bytes(&init_parameter());
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        let mut state = state_result.expect("Contract initialization results in error");
+
+        let accounts: Vec<AccountAddress> =
+            (0..votes.len().max(grants.len())).map(|_| new_account()).collect();
+        for (account, weight) in accounts.iter().zip(grants.iter()) {
+            let parameter_bytes = to_bytes(&GiveRightToVoteParams {
+                voter_address: Address::Account(*account),
+                weight: *weight,
+            });
+            let ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, 0u64, &parameter_bytes);
+            let res: ContractResult<ActionsTree> =
+                contract_give_right_to_vote(&ctx, &mut LogRecorder::init(), &mut state);
+            res.expect_report("giving right results in error.");
+        }
+        for (account, proposal_id) in accounts.iter().zip(votes.iter()) {
+            let parameter_bytes = to_bytes(&GetVoteParams {
+                proposal_id: *proposal_id,
+                direction: VoteDirection::For,
+            });
+            let ctx = receive_ctx(ACCOUNT_0, *account, 0u64, &parameter_bytes);
+            let res: ContractResult<ActionsTree> =
+                contract_vote(&ctx, &mut LogRecorder::init(), &mut state);
+            res.expect_report("contract voting results in error.");
+        }
+
+        let ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, 0u64, &[]);
+        let mut logger = LogRecorder::init();
+        let res: ContractResult<ActionsTree> =
+            contract_get_participation_by_proposal(&ctx, &mut logger, &mut state);
+        res.expect_report("getting the participation results in error.");
+        logger.logs
+    }
+
+    #[concordium_test]
+    fn test_participation_by_proposal_with_eligibility() {
+        claim_eq!(
+            participation_after_votes(&[1, 1, 1, 1], &[0, 1, 1]),
+            vec![to_bytes(&vec![
+                ProposalParticipation {
+                    proposal_id: 0,
+                    vote_count: 1,
+                    participation_bp: 2_500,
+                },
+                ProposalParticipation {
+                    proposal_id: 1,
+                    vote_count: 2,
+                    participation_bp: 5_000,
+                },
+            ])],
+            "the participation should be relative to the eligible voters"
+        );
+
+        // 重みのある投票者は重みで数える。
+        claim_eq!(
+            participation_after_votes(&[3, 1], &[0, 1]),
+            vec![to_bytes(&vec![
+                ProposalParticipation {
+                    proposal_id: 0,
+                    vote_count: 3,
+                    participation_bp: 7_500,
+                },
+                ProposalParticipation {
+                    proposal_id: 1,
+                    vote_count: 1,
+                    participation_bp: 2_500,
+                },
+            ])],
+            "the participation should be relative to the eligible weight"
+        );
+    }
+
+    #[concordium_test]
+    fn test_participation_by_proposal_without_eligibility() {
+        claim_eq!(
+            participation_after_votes(&[], &[0, 1, 1]),
+            vec![to_bytes(&vec![
+                ProposalParticipation {
+                    proposal_id: 0,
+                    vote_count: 1,
+                    participation_bp: 3_333,
+                },
+                ProposalParticipation {
+                    proposal_id: 1,
+                    vote_count: 2,
+                    participation_bp: 6_667,
+                },
+            ])],
+            "the participation should fall back to the share of the votes"
+        );
+    }
+
+    #[concordium_test]
+    fn test_owner_can_vote() {
+        for owner_can_vote in [true, false].iter() {
+            let parameter_bytes = create_parameter_bytes(&InitParams {
+                owner_can_vote: *owner_can_vote,
+                ..init_parameter()
+            });
+            let ctx = parametrized_init_ctx(&parameter_bytes);
+            let state_result = contract_init(&ctx, &mut LogRecorder::init());
+            let mut state = state_result.expect("Contract initialization results in error");
+
+            let parameter_bytes = to_bytes(&GetVoteParams {
+                proposal_id: 0 as ProposalId,
+                direction: VoteDirection::For,
+            });
+            let ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, 0u64, &parameter_bytes);
+            let res: ContractResult<ActionsTree> =
+                contract_vote(&ctx, &mut LogRecorder::init(), &mut state);
+            if *owner_can_vote {
+                res.expect_report("the owner voting results in error.");
+                claim_eq!(state.proposals[&0].vote_count, 1, "the owner's vote should count");
+            } else {
+                claim_eq!(
+                    res,
+                    Err(ContractError::OwnerCannotVote),
+                    "Result should be OwnerCannotVote."
+                );
+                claim_eq!(state.proposals[&0].vote_count, 0, "the owner's vote should not count");
+            }
+
+            // owner以外は設定に関係なく投票できる。
+            let ctx = receive_ctx(ACCOUNT_0, new_account(), 0u64, &parameter_bytes);
+            let res: ContractResult<ActionsTree> =
+                contract_vote(&ctx, &mut LogRecorder::init(), &mut state);
+            res.expect_report("contract voting results in error.");
+        }
+    }
+
+    #[concordium_test]
+    fn test_change_count() {
+        let parameter_bytes = create_parameter_bytes(&InitParams {
+            max_changes: Some(2),
+            ..init_parameter()
+        });
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        let mut state = state_result.expect("Contract initialization results in error");
+
+        let voter = new_account();
+        let vote = |state: &mut State, proposal_id: ProposalId| {
+            let parameter_bytes = to_bytes(&GetVoteParams {
+                proposal_id,
+                direction: VoteDirection::For,
+            });
+            let ctx = receive_ctx(ACCOUNT_0, voter, 0u64, &parameter_bytes);
+            contract_vote::<ActionsTree>(&ctx, &mut LogRecorder::init(), state)
+        };
+        let change_count =
+            |state: &State| state.get_voter(&Address::Account(voter)).unwrap().change_count;
+
+        vote(&mut state, 0).expect_report("contract voting results in error.");
+        claim_eq!(change_count(&state), 0, "the first vote is not a change");
+        vote(&mut state, 0).expect_report("contract voting results in error.");
+        claim_eq!(change_count(&state), 0, "re-voting the same proposal is not a change");
+        vote(&mut state, 1).expect_report("contract voting results in error.");
+        claim_eq!(change_count(&state), 1, "switching should be counted");
+        vote(&mut state, 0).expect_report("contract voting results in error.");
+        claim_eq!(change_count(&state), 2, "switching back should be counted");
+
+        claim_eq!(
+            vote(&mut state, 1),
+            Err(ContractError::TooManyChanges),
+            "Result should be TooManyChanges."
+        );
+        claim_eq!(change_count(&state), 2, "a rejected change should not be counted");
+        claim_eq!(state.proposals[&0].vote_count, 1, "the last allowed vote should be kept");
+
+        // 取り消しや棄権を挟んでも上限は回避できない。
+        let ctx = receive_ctx(ACCOUNT_0, voter, 0u64, &[]);
+        let res: ContractResult<ActionsTree> = cancel_vote(&ctx, &mut state);
+        res.expect_report("cancel voting results in error.");
+        claim_eq!(
+            vote(&mut state, 1),
+            Err(ContractError::TooManyChanges),
+            "a change after cancelling should be limited"
+        );
+        let res: ContractResult<ActionsTree> =
+            contract_abstain(&ctx, &mut LogRecorder::init(), &mut state);
+        res.expect_report("abstaining results in error.");
+        claim_eq!(
+            vote(&mut state, 1),
+            Err(ContractError::TooManyChanges),
+            "a change after abstaining should be limited"
+        );
+        vote(&mut state, 0).expect_report("voting the last proposal again should be allowed.");
+        claim_eq!(change_count(&state), 2, "returning to the last proposal is not a change");
+        claim_eq!(state.proposals[&1].vote_count, 0, "no change past the limit should count");
+    }
+
+    #[concordium_test]
+    fn test_dump_state() {
+        let proposal_names: Vec<String> = (0..40).map(|i| format!("Proposal {}", i)).collect();
+        let parameter_bytes = create_parameter_bytes(&InitParams {
+            proposal_names,
+            proposal_descriptions: vec![String::new(); 40],
+            proposal_metadata_urls: vec![None; 40],
+            ..init_parameter()
+        });
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        let mut state = state_result.expect("Contract initialization results in error");
+        let parameter_bytes = to_bytes(&GetVoteParams {
+            proposal_id: 3 as ProposalId,
+            direction: VoteDirection::For,
+        });
+        let ctx = receive_ctx(ACCOUNT_0, new_account(), 0u64, &parameter_bytes);
+        let res: ContractResult<ActionsTree> =
+            contract_vote(&ctx, &mut LogRecorder::init(), &mut state);
+        res.expect_report("contract voting results in error.");
+
+        let ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, 0u64, &[]);
+        let mut logger = LogRecorder::init();
+        let res: ContractResult<ActionsTree> = contract_dump_state(&ctx, &mut logger, &mut state);
+        res.expect_report("dumping the state results in error.");
+        claim!(logger.logs.len() > 1, "the state should be split into several entries");
+        let bytes = logger.logs.concat();
+        claim_eq!(from_bytes::<State>(&bytes), Ok(state), "the dumped state should round trip");
+    }
+
+    #[concordium_test]
+    fn test_cancel_vote_twice() {
+        let parameter_bytes = create_parameter_bytes(&init_parameter());
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        let mut state = state_result.expect("Contract initialization results in error");
+
+        // 投票権はあるが投票していない。
+        let granted = new_account();
+        let parameter_bytes = to_bytes(&GiveRightToVoteParams {
+            voter_address: Address::Account(granted),
+            weight: 1,
+        });
+        let ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, 0u64, &parameter_bytes);
+        let res: ContractResult<ActionsTree> =
+            contract_give_right_to_vote(&ctx, &mut LogRecorder::init(), &mut state);
+        res.expect_report("giving right results in error.");
+        let ctx = receive_ctx(ACCOUNT_0, granted, 0u64, &[]);
+        let res: ContractResult<ActionsTree> = cancel_vote(&ctx, &mut state);
+        claim_eq!(res, Err(ContractError::NotVoted), "Result should be NotVoted.");
+
+        let voter = new_account();
+        let parameter_bytes = to_bytes(&GetVoteParams {
+            proposal_id: 0 as ProposalId,
+            direction: VoteDirection::For,
+        });
+        let ctx = receive_ctx(ACCOUNT_0, voter, 0u64, &parameter_bytes);
+        let res: ContractResult<ActionsTree> =
+            contract_vote(&ctx, &mut LogRecorder::init(), &mut state);
+        res.expect_report("contract voting results in error.");
+
+        let ctx = receive_ctx(ACCOUNT_0, voter, 0u64, &[]);
+        let res: ContractResult<ActionsTree> = cancel_vote(&ctx, &mut state);
+        res.expect_report("cancelling the vote results in error.");
+        let res: ContractResult<ActionsTree> = cancel_vote(&ctx, &mut state);
+        claim_eq!(res, Err(ContractError::AlreadyCancelled), "Result should be AlreadyCancelled.");
+
+        // 再投票すれば再び取り消せる。
+        let ctx = receive_ctx(ACCOUNT_0, voter, 0u64, &parameter_bytes);
+        let res: ContractResult<ActionsTree> =
+            contract_vote(&ctx, &mut LogRecorder::init(), &mut state);
+        res.expect_report("contract voting results in error.");
+        let ctx = receive_ctx(ACCOUNT_0, voter, 0u64, &[]);
+        let res: ContractResult<ActionsTree> = cancel_vote(&ctx, &mut state);
+        res.expect_report("cancelling the new vote results in error.");
+    }
+
+    #[concordium_test]
+    fn test_give_right_to_vote_batch() {
+        let parameter_bytes = create_parameter_bytes(&init_parameter());
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        let mut state = state_result.expect("Contract initialization results in error");
+
+        let first = [Address::Account(new_account()), Address::Account(new_account())];
+        let parameter_bytes = to_bytes(&GiveRightToVoteBatchParams {
+            voters: first.to_vec(),
+        });
+        let ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, 0u64, &parameter_bytes);
+        let mut logger = LogRecorder::init();
+        let res: ContractResult<ActionsTree> =
+            contract_give_right_to_vote_batch(&ctx, &mut logger, &mut state);
+        res.expect_report("giving rights results in error.");
+        let grant = |to: Address| {
+            to_bytes(&Event::GiveRightToVote {
+                to,
+                added_weight: 1,
+                total_weight: 1,
+            })
+        };
+        claim_eq!(
+            logger.logs,
+            vec![grant(first[0]), grant(first[1])],
+            "every grant should be logged"
+        );
+        claim_eq!(state.eligible_count, 2, "something wrong with eligible_count");
+
+        // 登録済みのアドレスとバッチ内の重複は飛ばされる。
+        let new = Address::Account(new_account());
+        let parameter_bytes = to_bytes(&GiveRightToVoteBatchParams {
+            voters: vec![first[1], new, new],
+        });
+        let ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, 0u64, &parameter_bytes);
+        let mut logger = LogRecorder::init();
+        let res: ContractResult<ActionsTree> =
+            contract_give_right_to_vote_batch(&ctx, &mut logger, &mut state);
+        res.expect_report("giving rights results in error.");
+        claim_eq!(logger.logs, vec![grant(new)], "only the new grant should be logged");
+        claim_eq!(state.eligible_count, 3, "something wrong with eligible_count");
+        claim_eq!(state.voters.len(), 3, "something wrong with the voters");
+    }
+
+    #[concordium_test]
+    fn test_give_right_to_vote_with_weight() {
+        let parameter_bytes = create_parameter_bytes(&init_parameter());
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        let mut state = state_result.expect("Contract initialization results in error");
+
+        for weight in [1u64, 5].iter() {
+            let voter_address = Address::Account(new_account());
+            let parameter_bytes = to_bytes(&GiveRightToVoteParams {
+                voter_address,
+                weight: *weight,
+            });
+            let ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, 0u64, &parameter_bytes);
+            let mut logger = LogRecorder::init();
+            let res: ContractResult<ActionsTree> =
+                contract_give_right_to_vote(&ctx, &mut logger, &mut state);
+            res.expect_report("giving right results in error.");
+            claim_eq!(
+                logger.logs,
+                vec![to_bytes(&Event::GiveRightToVote {
+                    to: voter_address,
+                    added_weight: *weight,
+                    total_weight: *weight,
+                })],
+                "the event should carry the granted weight"
+            );
+            claim_eq!(state.get_voter(&voter_address).unwrap().weight, *weight, "wrong weight");
+        }
+
+        let parameter_bytes = to_bytes(&GiveRightToVoteParams {
+            voter_address: Address::Account(new_account()),
+            weight: 0,
+        });
+        let ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, 0u64, &parameter_bytes);
+        let res: ContractResult<ActionsTree> =
+            contract_give_right_to_vote(&ctx, &mut LogRecorder::init(), &mut state);
+        claim_eq!(res, Err(ContractError::InvalidWeight), "Result should be InvalidWeight.");
+        claim_eq!(state.eligible_count, 2, "a rejected grant should not count");
+    }
+
+    #[concordium_test]
+    fn test_is_expired() {
+        let parameter_bytes = create_parameter_bytes(&InitParams {
+            expiry: Timestamp::from_timestamp_millis(100),
+            ..init_parameter()
+        });
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        let mut state = state_result.expect("Contract initialization results in error");
+
+        for (slot_time, expired) in [(99u64, false), (100, false), (101, true)].iter() {
+            claim_eq!(
+                state.is_expired(Timestamp::from_timestamp_millis(*slot_time)),
+                *expired,
+                "something wrong with is_expired"
+            );
+            let ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, *slot_time, &[]);
+            let mut logger = LogRecorder::init();
+            let res: ContractResult<ActionsTree> =
+                contract_is_expired(&ctx, &mut logger, &mut state);
+            res.expect_report("checking the expiry results in error.");
+            claim_eq!(logger.logs, vec![to_bytes(expired)], "something wrong with isExpired");
+        }
+
+        // 猶予期間中はまだ期限切れではなく、残り時間とも一致する。
+        state.grace_period_ms = 50;
+        for (slot_time, expired) in [(101u64, false), (150, false), (151, true)].iter() {
+            let ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, *slot_time, &[]);
+            let mut logger = LogRecorder::init();
+            let res: ContractResult<ActionsTree> =
+                contract_is_expired(&ctx, &mut logger, &mut state);
+            res.expect_report("checking the expiry results in error.");
+            let res: ContractResult<ActionsTree> =
+                contract_time_remaining(&ctx, &mut logger, &mut state);
+            res.expect_report("getting the time remaining results in error.");
+            claim_eq!(
+                logger.logs,
+                vec![to_bytes(expired), to_bytes(&(150 - *slot_time as i64))],
+                "isExpired and timeRemaining should agree on the grace period"
+            );
+        }
+    }
+
+    fn get_proposal_by_name(
+        state: &mut State,
+        name: &str,
+    ) -> (ContractResult<ActionsTree>, Vec<Vec<u8>>) {
+        let parameter_bytes = to_bytes(&GetProposalByNameParams {
+            name: name.to_string(),
+        });
+        let ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, 0u64, &parameter_bytes);
+        let mut logger = LogRecorder::init();
+        let res = contract_get_proposal_by_name(&ctx, &mut logger, state);
+        (res, logger.logs)
+    }
+
+    #[concordium_test]
+    fn test_get_proposal_by_name() {
+        let parameter_bytes = create_parameter_bytes(&init_parameter());
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        let mut state = state_result.expect("Contract initialization results in error");
+        state.proposals.get_mut(&1).unwrap().vote_count = 4;
+
+        let (res, logs) = get_proposal_by_name(&mut state, PROPOSAL_NAME_2);
+        res.expect_report("looking up a proposal results in error.");
+        claim_eq!(
+            logs,
+            vec![to_bytes(&(1 as ProposalId, 4u64))],
+            "something wrong with the lookup"
+        );
+
+        let (res, logs) = get_proposal_by_name(&mut state, "Unknown");
+        claim_eq!(
+            res,
+            Err(ContractError::ProposalIsNotFound),
+            "Result should be ProposalIsNotFound."
+        );
+        claim!(logs.is_empty(), "nothing should be logged");
+    }
+
+    #[concordium_test]
+    fn test_winning_proposal_twice() {
+        let (mut state, _) = tally_fixture(init_parameter(), &[1, 3], 0);
+        claim_eq!(state.winning_proposal_id, vec![1], "something wrong with winning_proposal_id");
+        let finished_at = state.finished_at;
+
+        let ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, 50u64, &[]);
+        let mut logger = LogRecorder::init();
+        let res: ContractResult<ActionsTree> =
+            contract_winning_proposal(&ctx, &mut logger, &mut state);
+        res.expect_report("a repeated tally results in error.");
+        claim_eq!(
+            logger.logs,
+            vec![to_bytes(&TallyResult {
+                winning_proposal_id: vec![1],
+                winning_vote_count: 3,
+                indecisive: false,
+            })],
+            "the stored result should be logged"
+        );
+        claim_eq!(state.finished_at, finished_at, "the tally should not be recomputed");
+    }
+
+    #[concordium_test]
+    fn test_approval_threshold() {
+        let referendum = || InitParams {
+            approval_threshold_bp: Some(6_600),
+            ..init_parameter()
+        };
+        let (state, _) = tally_fixture(referendum(), &[67, 33], 0);
+        claim_eq!(state.winning_proposal_id, vec![0], "the measure should pass");
+        claim_eq!(state.winning_vote_count, 67, "something wrong with winning_vote_count");
+
+        let (state, _) = tally_fixture(referendum(), &[60, 40], 0);
+        claim_eq!(state.winning_proposal_id, vec![1], "the measure should fail");
+        claim_eq!(state.winning_vote_count, 40, "something wrong with winning_vote_count");
+
+        let (state, _) = tally_fixture(referendum(), &[0, 0], 0);
+        claim_eq!(state.winning_proposal_id, vec![1], "a measure without votes should fail");
+    }
+
+    #[concordium_test]
+    fn test_approval_threshold_needs_two_proposals() {
+        let parameter_bytes = create_parameter_bytes(&InitParams {
+            proposal_names: vec![PROPOSAL_NAME_1.to_string()],
+            proposal_descriptions: vec![PROPOSAL_DESCRIPTION_1.to_string()],
+            proposal_metadata_urls: vec![None],
+            approval_threshold_bp: Some(6_600),
+            ..init_parameter()
+        });
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        claim_eq!(
+            state_result.err(),
+            Some(ContractError::InvalidApprovalThreshold),
+            "Result should be InvalidApprovalThreshold."
+        );
+    }
+
+    #[concordium_test]
+    fn test_right_revoked_event() {
+        let parameter_bytes = create_parameter_bytes(&init_parameter());
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        let mut state = state_result.expect("Contract initialization results in error");
+
+        let account1 = new_account();
+        let voter_address = Address::Account(account1);
+        let parameter_bytes = to_bytes(&GiveRightToVoteParams {
+            voter_address,
+            weight: 3,
+        });
+        let ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, 0u64, &parameter_bytes);
+        let res: ContractResult<ActionsTree> =
+            contract_give_right_to_vote(&ctx, &mut LogRecorder::init(), &mut state);
+        res.expect_report("giving right results in error.");
+        let parameter_bytes = to_bytes(&GetVoteParams {
+            proposal_id: 0 as ProposalId,
+            direction: VoteDirection::For,
+        });
+        let ctx = receive_ctx(ACCOUNT_0, account1, 0u64, &parameter_bytes);
+        let res: ContractResult<ActionsTree> =
+            contract_vote(&ctx, &mut LogRecorder::init(), &mut state);
+        res.expect_report("contract voting results in error.");
+        claim_eq!(state.proposals[&0].vote_count, 3, "the vote should count with weight 3");
+
+        let parameter_bytes = to_bytes(&GetVoterParams {
+            voter_address,
+        });
+        let ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, 0u64, &parameter_bytes);
+        let mut logger = LogRecorder::init();
+        let res: ContractResult<ActionsTree> =
+            contract_revoke_right_to_vote(&ctx, &mut logger, &mut state);
+        res.expect_report("revoking the right results in error.");
+        claim_eq!(state.proposals[&0].vote_count, 0, "the vote should be subtracted");
+        claim_eq!(
+            logger.logs,
+            vec![to_bytes(&Event::RightRevoked {
+                from: voter_address,
+                removed_weight: 3,
+            })],
+            "something wrong with the logged revocation"
+        );
+    }
+
+    #[concordium_test]
+    fn test_vote_in_grace_period() {
+        let parameter_bytes = create_parameter_bytes(&InitParams {
+            expiry: Timestamp::from_timestamp_millis(100),
+            grace_period_ms: 20,
+            ..init_parameter()
+        });
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        let mut state = state_result.expect("Contract initialization results in error");
+
+        let parameter_bytes = to_bytes(&GetVoteParams {
+            proposal_id: 0 as ProposalId,
+            direction: VoteDirection::For,
+        });
+        let ctx = receive_ctx(ACCOUNT_0, new_account(), 120u64, &parameter_bytes);
+        let res: ContractResult<ActionsTree> =
+            contract_vote(&ctx, &mut LogRecorder::init(), &mut state);
+        res.expect_report("voting within the grace period results in error.");
+        claim_eq!(state.proposals[&0].vote_count, 1, "the late vote should be counted");
+
+        let ctx = receive_ctx(ACCOUNT_0, new_account(), 121u64, &parameter_bytes);
+        let res: ContractResult<ActionsTree> =
+            contract_vote(&ctx, &mut LogRecorder::init(), &mut state);
+        claim_eq!(res, Err(ContractError::Expired), "Result should be Expired.");
+        claim_eq!(state.proposals[&0].vote_count, 1, "the vote should not be counted");
+    }
+
+    #[concordium_test]
+    fn test_get_owner() {
+        let parameter_bytes = create_parameter_bytes(&init_parameter());
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        let mut state = state_result.expect("Contract initialization results in error");
+        claim_eq!(state.owner, Some(ctx.init_origin()), "the owner should be the init origin");
+
+        let ctx = receive_ctx(ACCOUNT_0, new_account(), 0u64, &[]);
+        let mut logger = LogRecorder::init();
+        let res: ContractResult<ActionsTree> = contract_get_owner(&ctx, &mut logger, &mut state);
+        res.expect_report("getting the owner results in error.");
+        claim_eq!(logger.logs, vec![to_bytes(&ACCOUNT_0)], "the owner should be logged");
+    }
+
+    #[concordium_test]
+    fn test_set_display_order() {
+        let parameter_bytes = create_parameter_bytes(&InitParams {
+            proposal_names: vec!["A".to_string(), "B".to_string(), "C".to_string()],
+            proposal_descriptions: vec![String::new(), String::new(), String::new()],
+            proposal_metadata_urls: vec![None, None, None],
+            ..init_parameter()
+        });
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        let mut state = state_result.expect("Contract initialization results in error");
+
+        let parameter_bytes = to_bytes(&SetDisplayOrderParams {
+            orders: vec![(0, 2), (2, 1)],
+        });
+        let ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, 0u64, &parameter_bytes);
+        let res: ContractResult<ActionsTree> = contract_set_display_order(&ctx, &mut state);
+        res.expect_report("setting the display order results in error.");
+
+        let ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, 0u64, &[]);
+        let mut logger = LogRecorder::init();
+        let res: ContractResult<ActionsTree> =
+            contract_get_proposals(&ctx, &mut logger, &mut state);
+        res.expect_report("getting the proposals results in error.");
+        let entry = |proposal_id: ProposalId, name: &str| ProposalEntry {
+            proposal_id,
+            name: name.to_string(),
+            vote_count: 0,
+        };
+        claim_eq!(
+            logger.logs,
+            vec![to_bytes(&vec![entry(1, "B"), entry(2, "C"), entry(0, "A")])],
+            "the proposals should be listed in display order"
+        );
+
+        let parameter_bytes = to_bytes(&SetDisplayOrderParams {
+            orders: vec![(1, 0), (3, 0)],
+        });
+        let ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, 0u64, &parameter_bytes);
+        let res: ContractResult<ActionsTree> = contract_set_display_order(&ctx, &mut state);
+        claim_eq!(
+            res,
+            Err(ContractError::ProposalIsNotFound),
+            "Result should be ProposalIsNotFound."
+        );
+
+        let parameter_bytes = to_bytes(&GetVoteParams {
+            proposal_id: 0 as ProposalId,
+            direction: VoteDirection::For,
+        });
+        let ctx = receive_ctx(ACCOUNT_0, new_account(), 0u64, &parameter_bytes);
+        let res: ContractResult<ActionsTree> =
+            contract_vote(&ctx, &mut LogRecorder::init(), &mut state);
+        res.expect_report("contract voting results in error.");
+
+        let parameter_bytes = to_bytes(&SetDisplayOrderParams {
+            orders: vec![(0, 0)],
+        });
+        let ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, 0u64, &parameter_bytes);
+        let res: ContractResult<ActionsTree> = contract_set_display_order(&ctx, &mut state);
+        claim_eq!(
+            res,
+            Err(ContractError::VotingAlreadyStarted),
+            "Result should be VotingAlreadyStarted."
+        );
+        claim_eq!(state.proposals[&0].display_order, 2, "the display order should be kept");
+    }
+
+    #[concordium_test]
+    fn test_seats() {
+        let two_seats = || InitParams {
+            proposal_names: vec![
+                "A".to_string(),
+                "B".to_string(),
+                "C".to_string(),
+                "D".to_string(),
+            ],
+            proposal_descriptions: vec![String::new(); 4],
+            proposal_metadata_urls: vec![None; 4],
+            seats: 2,
+            ..init_parameter()
+        };
+        let (state, _) = tally_fixture(two_seats(), &[5, 9, 1, 7], 0);
+        claim_eq!(state.winning_proposal_id, vec![1, 3], "the top two should win");
+        claim_eq!(state.winning_vote_count, 9, "something wrong with winning_vote_count");
+
+        let (state, _) = tally_fixture(two_seats(), &[5, 9, 5, 1], 0);
+        claim_eq!(
+            state.winning_proposal_id,
+            vec![0, 1, 2],
+            "a tie at the last seat should elect all tied proposals"
+        );
+    }
+
+    #[concordium_test]
+    fn test_invalid_seats() {
+        let parameter_bytes = create_parameter_bytes(&InitParams {
+            seats: 0,
+            ..init_parameter()
+        });
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        claim_eq!(
+            state_result.err(),
+            Some(ContractError::InvalidSeats),
+            "Result should be InvalidSeats."
+        );
+    }
+
+    #[concordium_test]
+    fn test_vote_without_proposals() {
+        let parameter_bytes = create_parameter_bytes(&init_parameter());
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        let mut state = state_result.expect("Contract initialization results in error");
+        state.proposals.clear();
+
+        let parameter_bytes = to_bytes(&GetVoteParams {
+            proposal_id: 0 as ProposalId,
+            direction: VoteDirection::For,
+        });
+        let ctx = receive_ctx(ACCOUNT_0, new_account(), 0u64, &parameter_bytes);
+        let res: ContractResult<ActionsTree> =
+            contract_vote(&ctx, &mut LogRecorder::init(), &mut state);
+        claim_eq!(res, Err(ContractError::NoProposals), "Result should be NoProposals.");
+    }
+
+    #[concordium_test]
+    fn test_cancellable_until() {
+        for (grace_period_ms, cancellable_until) in [(0, 100), (20, 120)].iter() {
+            let parameter_bytes = create_parameter_bytes(&InitParams {
+                expiry: Timestamp::from_timestamp_millis(100),
+                grace_period_ms: *grace_period_ms,
+                ..init_parameter()
+            });
+            let ctx = parametrized_init_ctx(&parameter_bytes);
+            let state_result = contract_init(&ctx, &mut LogRecorder::init());
+            let mut state = state_result.expect("Contract initialization results in error");
+
+            let ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, 0u64, &[]);
+            let mut logger = LogRecorder::init();
+            let res: ContractResult<ActionsTree> =
+                contract_cancellable_until(&ctx, &mut logger, &mut state);
+            res.expect_report("getting the cancellation deadline results in error.");
+            claim_eq!(
+                logger.logs,
+                vec![to_bytes(&Timestamp::from_timestamp_millis(*cancellable_until))],
+                "something wrong with the cancellation deadline"
+            );
+
+            let parameter_bytes = to_bytes(&GetVoteParams {
+                proposal_id: 0 as ProposalId,
+                direction: VoteDirection::For,
+            });
+            let account1 = new_account();
+            let ctx = receive_ctx(ACCOUNT_0, account1, 0u64, &parameter_bytes);
+            let res: ContractResult<ActionsTree> =
+                contract_vote(&ctx, &mut LogRecorder::init(), &mut state);
+            res.expect_report("contract voting results in error.");
+            let ctx = receive_ctx(ACCOUNT_0, account1, cancellable_until + 1, &[]);
+            let res: ContractResult<ActionsTree> = cancel_vote(&ctx, &mut state);
+            claim_eq!(res, Err(ContractError::Expired), "Result should be Expired.");
+            let ctx = receive_ctx(ACCOUNT_0, account1, *cancellable_until, &[]);
+            let res: ContractResult<ActionsTree> = cancel_vote(&ctx, &mut state);
+            res.expect_report("cancelling at the deadline results in error.");
+        }
+    }
+
+    #[concordium_test]
+    fn test_vote_changed_event() {
+        let parameter_bytes = create_parameter_bytes(&init_parameter());
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        let mut state = state_result.expect("Contract initialization results in error");
+
+        let account1 = new_account();
+        let vote = |state: &mut State, proposal_id: ProposalId| {
+            let parameter_bytes = to_bytes(&GetVoteParams {
+                proposal_id,
+                direction: VoteDirection::For,
+            });
+            let ctx = receive_ctx(ACCOUNT_0, account1, 0u64, &parameter_bytes);
+            let mut logger = LogRecorder::init();
+            let res: ContractResult<ActionsTree> = contract_vote(&ctx, &mut logger, state);
+            res.expect_report("contract voting results in error.");
+            logger.logs
+        };
+
+        claim_eq!(
+            vote(&mut state, 0)[0],
+            to_bytes(&Event::FirstVote {
+                voter: Address::Account(account1),
+                proposal_id: 0,
+                vote_count: 1,
+            }),
+            "the first ballot should be logged as FirstVote"
+        );
+        claim_eq!(
+            vote(&mut state, 1)[0],
+            to_bytes(&Event::VoteChanged {
+                voter: Address::Account(account1),
+                old_proposal_id: 0,
+                new_proposal_id: 1,
+                vote_count: 1,
+            }),
+            "the switch should be logged as VoteChanged"
+        );
+    }
+
+    fn get_proposals_page(state: &mut State, start: ProposalId, limit: u8) -> Vec<ProposalId> {
+        let parameter_bytes = to_bytes(&GetProposalsPageParams {
+            start,
+            limit,
+        });
+        let ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, 0u64, &parameter_bytes);
+        let mut logger = LogRecorder::init();
+        let res: ContractResult<ActionsTree> =
+            contract_get_proposals_page(&ctx, &mut logger, state);
+        res.expect_report("getting a page of proposals results in error.");
+        let page: Vec<ProposalEntry> =
+            from_bytes(&logger.logs[0]).expect_report("the page should deserialize.");
+        page.into_iter().map(|entry| entry.proposal_id).collect()
+    }
+
+    #[concordium_test]
+    fn test_get_proposals_page() {
+        let proposal_names: Vec<String> = (0..40).map(|i| format!("Proposal {}", i)).collect();
+        let parameter_bytes = create_parameter_bytes(&InitParams {
+            proposal_descriptions: vec![String::new(); proposal_names.len()],
+            proposal_metadata_urls: vec![None; proposal_names.len()],
+            proposal_names,
+            ..init_parameter()
+        });
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        let mut state = state_result.expect("Contract initialization results in error");
+
+        claim_eq!(get_proposals_page(&mut state, 0, 3), vec![0, 1, 2], "first page");
+        claim_eq!(get_proposals_page(&mut state, 10, 3), vec![10, 11, 12], "middle page");
+        claim_eq!(
+            get_proposals_page(&mut state, 20, 255),
+            (20..36).collect::<Vec<ProposalId>>(),
+            "the limit should be clamped"
+        );
+        claim_eq!(get_proposals_page(&mut state, 38, 5), vec![38, 39], "last page");
+        claim_eq!(get_proposals_page(&mut state, 40, 5), Vec::<ProposalId>::new(), "past the end");
+    }
+
+    #[concordium_test]
+    fn test_get_final_result() {
+        let parameter_bytes = create_parameter_bytes(&init_parameter());
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        let mut state = state_result.expect("Contract initialization results in error");
+
+        let ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, 0u64, &[]);
+        let res: ContractResult<ActionsTree> =
+            contract_get_final_result(&ctx, &mut LogRecorder::init(), &mut state);
+        claim_eq!(res, Err(ContractError::NotYetTallied), "Result should be NotYetTallied.");
 
-    for (proposal_id, proposal) in state.proposals.iter() {
-        if winning_vote_count < proposal.vote_count {
-            winning_vote_count = proposal.vote_count;
-            winning_proposal_id = [*proposal_id].to_vec();
-        } else if winning_vote_count == proposal.vote_count {
-            winning_proposal_id.push(*proposal_id)
+        for proposal_id in [1, 1, 0, 0xff].iter() {
+            let account = new_account();
+            let parameter_bytes = to_bytes(&GiveRightToVoteParams {
+                voter_address: Address::Account(account),
+                weight: 1,
+            });
+            let ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, 0u64, &parameter_bytes);
+            let res: ContractResult<ActionsTree> =
+                contract_give_right_to_vote(&ctx, &mut LogRecorder::init(), &mut state);
+            res.expect_report("giving right results in error.");
+            if *proposal_id == 0xff {
+                continue;
+            }
+            let parameter_bytes = to_bytes(&GetVoteParams {
+                proposal_id: *proposal_id,
+                direction: VoteDirection::For,
+            });
+            let ctx = receive_ctx(ACCOUNT_0, account, 0u64, &parameter_bytes);
+            let res: ContractResult<ActionsTree> =
+                contract_vote(&ctx, &mut LogRecorder::init(), &mut state);
+            res.expect_report("contract voting results in error.");
         }
+
+        let ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, 0u64, &[]);
+        let res: ContractResult<ActionsTree> =
+            contract_winning_proposal(&ctx, &mut LogRecorder::init(), &mut state);
+        res.expect_report("contract winning proposal results in error.");
+
+        let mut logger = LogRecorder::init();
+        let res: ContractResult<ActionsTree> =
+            contract_get_final_result(&ctx, &mut logger, &mut state);
+        res.expect_report("getting the final result results in error.");
+        claim_eq!(
+            logger.logs,
+            vec![to_bytes(&FinalResult {
+                winning_proposal_ids: vec![1],
+                winning_names: vec![PROPOSAL_NAME_2.to_string()],
+                winning_vote_count: 2,
+                total_votes: 3,
+                turnout_bp: 7_500,
+                shares: vec![
+                    WeightShare {
+                        proposal_id: 0,
+                        cast_share_bp: 3_333,
+                        eligible_share_bp: 2_500,
+                    },
+                    WeightShare {
+                        proposal_id: 1,
+                        cast_share_bp: 6_667,
+                        eligible_share_bp: 5_000,
+                    },
+                ],
+            })],
+            "something wrong with the final result"
+        );
     }
 
-    state.status = Status::Finished;
-    state.winning_proposal_id = winning_proposal_id;
+    #[concordium_test]
+    fn test_init_title_and_description_length() {
+        let init = |title: String, description: String| {
+            let parameter_bytes = create_parameter_bytes(&InitParams {
+                title,
+                description,
+                ..init_parameter()
+            });
+            let ctx = parametrized_init_ctx(&parameter_bytes);
+            contract_init(&ctx, &mut LogRecorder::init()).err()
+        };
 
-    Ok(A::accept())
-}
+        claim_eq!(
+            init("投".repeat(MAX_TITLE_LENGTH), "票".repeat(MAX_DESCRIPTION_LENGTH)),
+            None,
+            "a title and description at the limit should be accepted"
+        );
+        claim_eq!(
+            init("a".repeat(MAX_TITLE_LENGTH + 1), DESCRIPTION.to_string()),
+            Some(ContractError::TitleTooLong),
+            "Result should be TitleTooLong."
+        );
+        claim_eq!(
+            init(TITLE.to_string(), "a".repeat(MAX_DESCRIPTION_LENGTH + 1)),
+            Some(ContractError::DescriptionTooLong),
+            "Result should be DescriptionTooLong."
+        );
+    }
 
-/// 投票のキャンセル
-#[receive(contract = "govote_voting", name = "cancelVote")]
-fn cancel_vote<A: HasActions>(
-    ctx: &impl HasReceiveContext,
-    state: &mut State,
-) -> ContractResult<A> {
-    let sender_address = ctx.sender();
+    #[concordium_test]
+    fn test_init_proposal_name_length() {
+        let init = |name_length: usize| {
+            let parameter_bytes = create_parameter_bytes(&InitParams {
+                title: "𝄞".repeat(MAX_TITLE_LENGTH),
+                proposal_names: vec!["a".repeat(name_length), PROPOSAL_NAME_2.to_string()],
+                ..init_parameter()
+            });
+            let ctx = parametrized_init_ctx(&parameter_bytes);
+            let mut logger = LogRecorder::init();
+            contract_init(&ctx, &mut logger).map(|_| logger.logs)
+        };
 
-    // 集計が終わってなければ実行できる。
-    ensure!(state.status != Status::Finished, ContractError::AlreadyFinished);
+        let logs = init(MAX_PROPOSAL_NAME_LENGTH)
+            .expect_report("a name at the limit should be accepted with the longest title.");
+        claim_eq!(logs.len(), 2, "each proposal should get its own entry");
+        claim_eq!(
+            logs[0].len(),
+            constants::MAX_LOG_SIZE,
+            "a name at the limit should fill a log entry exactly"
+        );
+        claim_eq!(
+            init(MAX_PROPOSAL_NAME_LENGTH + 1),
+            Err(ContractError::ProposalNameTooLong),
+            "Result should be ProposalNameTooLong."
+        );
+    }
 
-    // expiryを超えていなければ実行できる。
-    // let slot_time = ctx.metadata().slot_time();
-    // ensure!(slot_time <= state.expiry, ContractError::Expired);
+    #[concordium_test]
+    fn test_vote_receipt() {
+        let parameter_bytes = create_parameter_bytes(&InitParams {
+            expiry: Timestamp::from_timestamp_millis(100),
+            ..init_parameter()
+        });
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        let mut state = state_result.expect("Contract initialization results in error");
 
-    let mut voter = state.voters.get_mut(&sender_address).ok_or(ContractError::VoterIsNotFound)?;
-    ensure!(voter.voted == true, ContractError::NotVoted);
+        let account1 = new_account();
+        let parameter_bytes = to_bytes(&GiveRightToVoteParams {
+            voter_address: Address::Account(account1),
+            weight: 3,
+        });
+        let ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, 0u64, &parameter_bytes);
+        let res: ContractResult<ActionsTree> =
+            contract_give_right_to_vote(&ctx, &mut LogRecorder::init(), &mut state);
+        res.expect_report("giving right results in error.");
 
-    let proposal = state.proposals.get_mut(&voter.vote).ok_or(ContractError::ProposalIsNotFound)?;
-    proposal.vote_count -= voter.weight;
+        let parameter_bytes = to_bytes(&GetVoteParams {
+            proposal_id: 1 as ProposalId,
+            direction: VoteDirection::For,
+        });
+        let ctx = receive_ctx(ACCOUNT_0, account1, 42u64, &parameter_bytes);
+        let mut logger = LogRecorder::init();
+        let res: ContractResult<ActionsTree> = contract_vote(&ctx, &mut logger, &mut state);
+        res.expect_report("contract voting results in error.");
 
-    voter.voted = false;
-    voter.vote = 0;
+        // アドレスのタグ、アドレス、proposal id、重み、時刻の順に並べる。
+        let mut preimage = vec![0u8];
+        preimage.extend_from_slice(&account1.0);
+        preimage.push(1);
+        preimage.extend_from_slice(&3u64.to_le_bytes());
+        preimage.extend_from_slice(&42u64.to_le_bytes());
+        claim_eq!(
+            logger.logs.last(),
+            Some(&to_bytes(&Event::VoteReceipt {
+                receipt: sha256(&preimage),
+            })),
+            "the receipt should match the recomputation"
+        );
+    }
 
-    Ok(A::accept())
-}
+    #[concordium_test]
+    fn test_tie_break_earliest_reached() {
+        let parameter_bytes = create_parameter_bytes(&InitParams {
+            expiry: Timestamp::from_timestamp_millis(100),
+            tie_break: TieBreak::EarliestReached,
+            ..init_parameter()
+        });
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        let mut state = state_result.expect("Contract initialization results in error");
 
-#[concordium_cfg_test]
-mod tests {
-    use super::*;
-    use concordium_std::test_infrastructure::*;
-    use std::sync::atomic::{AtomicU8, Ordering};
+        // proposal 1が先に2票に達し、proposal 0が後から追いつく。
+        for (proposal_id, slot_time) in [(1, 10), (0, 20), (1, 30), (0, 40)].iter() {
+            let parameter_bytes = to_bytes(&GetVoteParams {
+                proposal_id: *proposal_id,
+                direction: VoteDirection::For,
+            });
+            let ctx = receive_ctx(ACCOUNT_0, new_account(), *slot_time, &parameter_bytes);
+            let res: ContractResult<ActionsTree> =
+                contract_vote(&ctx, &mut LogRecorder::init(), &mut state);
+            res.expect_report("contract voting results in error.");
+        }
+        claim_eq!(
+            state.proposals[&1].reached_at,
+            Some(Timestamp::from_timestamp_millis(30)),
+            "something wrong with reached_at"
+        );
 
-    static ADDRESS_COUNTER: AtomicU8 = AtomicU8::new(1);
+        let ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, 50u64, &[]);
+        let res: ContractResult<ActionsTree> =
+            contract_winning_proposal(&ctx, &mut LogRecorder::init(), &mut state);
+        res.expect_report("contract winning proposal results in error.");
+        claim_eq!(
+            state.winning_proposal_id,
+            vec![1],
+            "the proposal that reached the count first should win"
+        );
+    }
 
-    const ACCOUNT_0: AccountAddress = AccountAddress([0u8; 32]);
-    // const ADDRESS_0: Address = Address::Account(ACCOUNT_0);
-    const ACCOUNT_1: AccountAddress = AccountAddress([1u8; 32]);
-    // const ADDRESS_1: Address = Address::Account(ACCOUNT_1);
-    const TITLE: &str = "Test Title";
-    const DESCRIPTION: &str = "This is test description.";
-    const PROPOSAL_NAME_1: &str = "This is first test proposal.";
-    const PROPOSAL_NAME_2: &str = "This is second test proposal.";
-    const EXPIRY: u64 = 1;
+    #[concordium_test]
+    fn test_seal() {
+        let parameter_bytes = create_parameter_bytes(&InitParams {
+            correction_window: Duration::from_millis(100),
+            ..init_parameter()
+        });
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        let mut state = state_result.expect("Contract initialization results in error");
 
-    #[allow(unused)]
-    fn new_account() -> AccountAddress {
-        let account = AccountAddress([ADDRESS_COUNTER.load(Ordering::SeqCst); 32]);
-        ADDRESS_COUNTER.fetch_add(1, Ordering::SeqCst);
-        account
+        let ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, 0u64, &[]);
+        let res: ContractResult<ActionsTree> = contract_seal(&ctx, &mut state);
+        claim_eq!(res, Err(ContractError::NotYetTallied), "Result should be NotYetTallied.");
+
+        let parameter_bytes = to_bytes(&GetVoteParams {
+            proposal_id: 1 as ProposalId,
+            direction: VoteDirection::For,
+        });
+        let ctx = receive_ctx(ACCOUNT_0, new_account(), 0u64, &parameter_bytes);
+        let res: ContractResult<ActionsTree> =
+            contract_vote(&ctx, &mut LogRecorder::init(), &mut state);
+        res.expect_report("contract voting results in error.");
+
+        let ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, 0u64, &[]);
+        let res: ContractResult<ActionsTree> =
+            contract_winning_proposal(&ctx, &mut LogRecorder::init(), &mut state);
+        res.expect_report("contract winning proposal results in error.");
+        let res: ContractResult<ActionsTree> = contract_seal(&ctx, &mut state);
+        res.expect_report("sealing results in error.");
+        claim!(state.sealed, "the poll should be sealed");
+
+        let res: ContractResult<ActionsTree> = contract_reopen_tally(&ctx, &mut state);
+        claim_eq!(res, Err(ContractError::ContractSealed), "Result should be ContractSealed.");
+        let res: ContractResult<ActionsTree> = contract_recount(&ctx, &mut state);
+        claim_eq!(res, Err(ContractError::ContractSealed), "Result should be ContractSealed.");
+        let res: ContractResult<ActionsTree> = contract_clear_voters(&ctx, &mut state);
+        claim_eq!(res, Err(ContractError::ContractSealed), "Result should be ContractSealed.");
+        let res: ContractResult<ActionsTree> = contract_seal(&ctx, &mut state);
+        claim_eq!(res, Err(ContractError::ContractSealed), "Result should be ContractSealed.");
+        claim_eq!(state.status, Status::Finished, "the poll should stay finished");
+
+        let mut logger = LogRecorder::init();
+        let res: ContractResult<ActionsTree> =
+            contract_get_winning_proposal(&ctx, &mut logger, &mut state);
+        res.expect_report("getting the result of a sealed poll results in error.");
+        claim_eq!(
+            logger.logs,
+            vec![to_bytes(&TallyResult {
+                winning_proposal_id: vec![1],
+                winning_vote_count: 1,
+                indecisive: false,
+            })],
+            "the result should still be readable"
+        );
     }
 
-    fn init_parameter() -> InitParams {
-        let mut init_vec = Vec::new();
-        init_vec.push(PROPOSAL_NAME_1.to_string());
-        init_vec.push(PROPOSAL_NAME_2.to_string());
+    #[concordium_test]
+    fn test_get_proposals_by_group() {
+        let parameter_bytes = create_parameter_bytes(&InitParams {
+            proposal_names: vec!["A".to_string(), "B".to_string(), "C".to_string()],
+            proposal_descriptions: vec![String::new(); 3],
+            proposal_metadata_urls: vec![None; 3],
+            proposal_groups: vec![1, 2, 1],
+            ..init_parameter()
+        });
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        let mut state = state_result.expect("Contract initialization results in error");
 
-        InitParams {
-            title: TITLE.to_string(),
-            description: DESCRIPTION.to_string(),
-            proposal_names: init_vec,
-            expiry: Timestamp::from_timestamp_millis(EXPIRY),
+        let entry = |proposal_id: ProposalId, name: &str| ProposalEntry {
+            proposal_id,
+            name: name.to_string(),
+            vote_count: 0,
+        };
+        let cases =
+            [(1, vec![entry(0, "A"), entry(2, "C")]), (2, vec![entry(1, "B")]), (3, vec![])];
+        for (group, expected) in cases.iter() {
+            let parameter_bytes = to_bytes(&GetProposalsByGroupParams {
+                group: *group,
+            });
+            let ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, 0u64, &parameter_bytes);
+            let mut logger = LogRecorder::init();
+            let res: ContractResult<ActionsTree> =
+                contract_get_proposals_by_group(&ctx, &mut logger, &mut state);
+            res.expect_report("getting the proposals of a group results in error.");
+            claim_eq!(logger.logs, vec![to_bytes(expected)], "something wrong with the group");
         }
     }
 
-    fn create_parameter_bytes(parameter: &InitParams) -> Vec<u8> {
-        to_bytes(parameter)
+    #[concordium_test]
+    fn test_init_with_mismatched_groups() {
+        let parameter_bytes = create_parameter_bytes(&InitParams {
+            proposal_groups: vec![1],
+            ..init_parameter()
+        });
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        claim_eq!(
+            state_result.err(),
+            Some(ContractError::ProposalListMismatch),
+            "Result should be ProposalListMismatch."
+        );
     }
 
-    fn parametrized_init_ctx<'a>(parameter_bytes: &'a Vec<u8>) -> InitContextTest<'a> {
-        let mut ctx = InitContextTest::empty();
-        ctx.set_parameter(parameter_bytes);
-        ctx
+    #[concordium_test]
+    fn test_tally_total_overflow() {
+        let parameter_bytes = create_parameter_bytes(&init_parameter());
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        let mut state = state_result.expect("Contract initialization results in error");
+        state.proposals.get_mut(&0).unwrap().vote_count = u64::MAX - 1;
+        state.proposals.get_mut(&1).unwrap().vote_count = 2;
+
+        let ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, 0u64, &[]);
+        let res: ContractResult<ActionsTree> =
+            contract_winning_proposal(&ctx, &mut LogRecorder::init(), &mut state);
+        claim_eq!(
+            res,
+            Err(ContractError::VoteCountOverflow),
+            "Result should be VoteCountOverflow."
+        );
+        claim_eq!(state.status, Status::InProcess, "the poll should not be finished");
+        claim_eq!(state.finished_at, None, "no finish time should be recorded");
     }
 
-    fn receive_ctx<'a>(
-        owner: AccountAddress,
-        sender: AccountAddress,
-        slot_time: u64,
-        parameter_bytes: &'a Vec<u8>,
-    ) -> ReceiveContextTest<'a> {
-        let mut ctx = ReceiveContextTest::empty();
-        ctx.set_sender(Address::Account(sender));
-        ctx.set_owner(owner);
-        ctx.set_metadata_slot_time(Timestamp::from_timestamp_millis(slot_time));
-        ctx.set_parameter(parameter_bytes);
-        ctx
+    #[concordium_test]
+    fn test_get_share() {
+        let parameter_bytes = create_parameter_bytes(&InitParams {
+            proposal_names: vec!["A".to_string(), "B".to_string(), "C".to_string()],
+            proposal_descriptions: vec![String::new(); 3],
+            proposal_metadata_urls: vec![None; 3],
+            ..init_parameter()
+        });
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        let mut state = state_result.expect("Contract initialization results in error");
+
+        let get_share = |state: &mut State, proposal_id: ProposalId| {
+            let parameter_bytes = to_bytes(&GetProposalParams {
+                proposal_id,
+            });
+            let ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, 0u64, &parameter_bytes);
+            let mut logger = LogRecorder::init();
+            let res: ContractResult<ActionsTree> = contract_get_share(&ctx, &mut logger, state);
+            res.map(|_| logger.logs)
+        };
+
+        claim_eq!(get_share(&mut state, 0), Ok(vec![to_bytes(&0u32)]), "no votes, no share");
+
+        state.proposals.get_mut(&0).unwrap().vote_count = 2;
+        state.proposals.get_mut(&1).unwrap().vote_count = 2;
+        claim_eq!(get_share(&mut state, 0), Ok(vec![to_bytes(&5_000u32)]), "half the votes");
+        claim_eq!(get_share(&mut state, 2), Ok(vec![to_bytes(&0u32)]), "none of the votes");
+        claim_eq!(
+            get_share(&mut state, 3),
+            Err(ContractError::ProposalIsNotFound),
+            "Result should be ProposalIsNotFound."
+        );
     }
 
     #[concordium_test]
-    fn test_init() {
-        let mut init_vec = Vec::new();
-        init_vec.push(PROPOSAL_NAME_1.to_string());
-        init_vec.push(PROPOSAL_NAME_2.to_string());
+    fn test_aggregate_only() {
+        let parameter_bytes = create_parameter_bytes(&InitParams {
+            aggregate_only: true,
+            ..init_parameter()
+        });
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        let mut state = state_result.expect("Contract initialization results in error");
+
+        let account1 = new_account();
+        let parameter_bytes = to_bytes(&GetVoteParams {
+            proposal_id: 1 as ProposalId,
+            direction: VoteDirection::For,
+        });
+        let ctx = receive_ctx(ACCOUNT_0, account1, 0u64, &parameter_bytes);
+        let mut logger = LogRecorder::init();
+        let res: ContractResult<ActionsTree> = contract_vote(&ctx, &mut logger, &mut state);
+        res.expect_report("contract voting results in error.");
+        claim_eq!(state.proposals[&1].vote_count, 1, "the vote should be counted");
+        claim!(state.voters.is_empty(), "no voter entry should be created");
+        claim_eq!(
+            logger.logs,
+            Vec::<Vec<u8>>::new(),
+            "no event should pair the voter with the choice"
+        );
+
+        // 一括投票でも同じ。
+        let account2 = Address::Account(new_account());
+        let parameter_bytes = to_bytes(&VoteBatchParams {
+            ballots: vec![(account2, 0)],
+        });
+        let batch_ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, 0u64, &parameter_bytes);
+        let mut logger = LogRecorder::init();
+        let res: ContractResult<ActionsTree> =
+            contract_vote_batch(&batch_ctx, &mut logger, &mut state);
+        res.expect_report("batch voting results in error.");
+        claim_eq!(
+            logger.logs,
+            Vec::<Vec<u8>>::new(),
+            "no event should pair the voter with the choice"
+        );
+
+        let res: ContractResult<ActionsTree> =
+            contract_vote(&ctx, &mut LogRecorder::init(), &mut state);
+        claim_eq!(res, Err(ContractError::ChangesDisabled), "Result should be ChangesDisabled.");
+        let ctx = receive_ctx(ACCOUNT_0, account1, 0u64, &[]);
+        let res: ContractResult<ActionsTree> = cancel_vote(&ctx, &mut state);
+        claim_eq!(res, Err(ContractError::ChangesDisabled), "Result should be ChangesDisabled.");
+        claim_eq!(state.proposals[&1].vote_count, 1, "the vote should be kept");
+    }
 
+    #[concordium_test]
+    fn test_get_expiry() {
         let parameter_bytes = create_parameter_bytes(&init_parameter());
         let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        let mut state = state_result.expect("Contract initialization results in error");
 
-        let state_result = contract_init(&ctx);
-        let state = state_result.expect("Contract initialization results in error");
+        let ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, 0u64, &[]);
+        let mut logger = LogRecorder::init();
+        let res: ContractResult<ActionsTree> = contract_get_expiry(&ctx, &mut logger, &mut state);
+        res.expect_report("getting the expiry results in error.");
+        claim_eq!(
+            logger.logs,
+            vec![to_bytes(&Timestamp::from_timestamp_millis(EXPIRY))],
+            "the expiry should be the init value"
+        );
+    }
+
+    #[concordium_test]
+    fn test_token_weight() {
+        let token = ContractAddress {
+            index: 7,
+            subindex: 0,
+        };
+        let parameter_bytes = create_parameter_bytes(&InitParams {
+            expiry: Timestamp::from_timestamp_millis(100),
+            token_weight: Some(TokenWeight {
+                contract: token,
+                balance_of: OwnedReceiveName::new_unchecked("gov_token.balanceOf".to_string()),
+                token_id: vec![1],
+            }),
+            ..init_parameter()
+        });
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        let mut state = state_result.expect("Contract initialization results in error");
+
+        let parameter_bytes = to_bytes(&GetVoteParams {
+            proposal_id: 1 as ProposalId,
+            direction: VoteDirection::For,
+        });
+        let mut voters = Vec::new();
+        for _ in 0..2 {
+            let account = new_account();
+            let voter = Address::Account(account);
+            let ctx = receive_ctx(ACCOUNT_0, account, 0u64, &parameter_bytes);
+            let mut logger = LogRecorder::init();
+            let res: ContractResult<ActionsTree> = contract_vote(&ctx, &mut logger, &mut state);
+            let actions = res.expect_report("contract voting results in error.");
+            let query = BalanceOfQueryParams {
+                queries: vec![BalanceOfQuery {
+                    token_id: vec![1],
+                    address: voter,
+                }],
+                callback: OwnedReceiveName::new_unchecked(BALANCE_CALLBACK.to_string()),
+            };
+            claim_eq!(
+                actions,
+                ActionsTree::Send {
+                    to: token,
+                    receive_name: OwnedReceiveName::new_unchecked(
+                        "gov_token.balanceOf".to_string()
+                    ),
+                    amount: Amount::zero(),
+                    parameter: to_bytes(&query),
+                },
+                "the balance should be queried"
+            );
+            claim!(logger.logs.is_empty(), "nothing should be logged before the balance");
+            claim_eq!(state.proposals[&1].vote_count, 0, "the vote should wait for the balance");
+            voters.push(voter);
+        }
+
+        // tokenのコントラクトの代わりに残高を返す。
+        let respond = |state: &mut State, sender: Address, voter: Address, balance: u64| {
+            let parameter_bytes = to_bytes(&BalanceOfQueryResponse {
+                balances: vec![(
+                    BalanceOfQuery {
+                        token_id: vec![1],
+                        address: voter,
+                    },
+                    balance,
+                )],
+            });
+            let mut ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, 0u64, &parameter_bytes);
+            ctx.set_sender(sender);
+            let res: ContractResult<ActionsTree> =
+                contract_receive_balance(&ctx, &mut LogRecorder::init(), state);
+            res
+        };
 
         claim_eq!(
-            state,
-            State::new(
-                TITLE.to_string(),
-                DESCRIPTION.to_string(),
-                init_vec,
-                Timestamp::from_timestamp_millis(EXPIRY)
-            ),
-            "State is not equal."
+            respond(&mut state, Address::Account(ACCOUNT_0), voters[0], 40),
+            Err(ContractError::NotTheTokenContract),
+            "Result should be NotTheTokenContract."
+        );
+        respond(&mut state, Address::Contract(token), voters[0], 40)
+            .expect_report("receiving a balance results in error.");
+        claim_eq!(state.proposals[&1].vote_count, 40, "the vote should count the balance");
+        claim_eq!(
+            respond(&mut state, Address::Contract(token), voters[1], 0),
+            Err(ContractError::NoRightToVote),
+            "Result should be NoRightToVote."
         );
+        claim_eq!(state.proposals[&1].vote_count, 40, "a zero balance should not vote");
+
+        // 委任していた投票者は委任した重みを引いてから残高で投票する。
+        let delegator = new_account();
+        let voter_state = state.voter_entry(Address::Account(delegator));
+        voter_state.weight = 5;
+        voter_state.delegate = Some(voters[0]);
+        state
+            .apply_weight(&Address::Account(delegator), Timestamp::from_timestamp_millis(0))
+            .expect_report("applying the delegated weight results in error.");
+        claim_eq!(state.proposals[&1].vote_count, 45, "the delegated weight should count");
+        let parameter_bytes = to_bytes(&GetVoteParams {
+            proposal_id: 0 as ProposalId,
+            direction: VoteDirection::For,
+        });
+        let ctx = receive_ctx(ACCOUNT_0, delegator, 0u64, &parameter_bytes);
+        let res: ContractResult<ActionsTree> =
+            contract_vote(&ctx, &mut LogRecorder::init(), &mut state);
+        res.expect_report("contract voting results in error.");
+        respond(&mut state, Address::Contract(token), Address::Account(delegator), 25)
+            .expect_report("receiving a balance results in error.");
+        claim_eq!(state.proposals[&1].vote_count, 40, "the delegated weight should be retracted");
+        claim_eq!(state.proposals[&0].vote_count, 25, "the balance should count");
+        let ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, 0u64, &[]);
+        let res: ContractResult<ActionsTree> = contract_check_invariants(&ctx, &mut state);
+        claim_eq!(res, Ok(ActionsTree::Accept), "the vote counts should match the ballots");
+
+        // 残高を問い合わせない入口は使えない。
+        let parameter_bytes = to_bytes(&WriteInParams {
+            name: PROPOSAL_NAME_1.to_string(),
+        });
+        let ctx = receive_ctx(ACCOUNT_0, new_account(), 0u64, &parameter_bytes);
+        let res: ContractResult<ActionsTree> =
+            contract_vote_write_in(&ctx, &mut LogRecorder::init(), &mut state);
+        claim_eq!(res, Err(ContractError::WrongVotingMode), "voteWriteIn should be rejected");
+
+        let parameter_bytes = to_bytes(&DelegateParams {
+            to: voters[0],
+        });
+        let ctx = receive_ctx(ACCOUNT_0, new_account(), 0u64, &parameter_bytes);
+        let res: ContractResult<ActionsTree> =
+            contract_delegate(&ctx, &mut LogRecorder::init(), &mut state);
+        claim_eq!(res, Err(ContractError::WrongVotingMode), "delegate should be rejected");
+
+        let parameter_bytes = to_bytes(&VoteBatchParams {
+            ballots: vec![(Address::Account(new_account()), 0)],
+        });
+        let ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, 0u64, &parameter_bytes);
+        let res: ContractResult<ActionsTree> =
+            contract_vote_batch(&ctx, &mut LogRecorder::init(), &mut state);
+        claim_eq!(res, Err(ContractError::WrongVotingMode), "voteBatch should be rejected");
+        claim_eq!(state.proposals[&0].vote_count, 25, "the rejected ballots should not count");
     }
 
-    // #[concordium_test]
-    // fn test_give_right_to_vote() {
-    //     let parameter_bytes = create_parameter_bytes(&init_parameter());
-    //     let ctx = parametrized_init_ctx(&parameter_bytes);
-    //     let state_result = contract_init(&ctx);
-    //     let mut state = state_result.expect("Contract initialization results in error");
+    #[concordium_test]
+    fn test_token_weight_voter_limit() {
+        let token = ContractAddress {
+            index: 7,
+            subindex: 0,
+        };
+        let parameter_bytes = create_parameter_bytes(&InitParams {
+            expiry: Timestamp::from_timestamp_millis(100),
+            max_voters: Some(1),
+            token_weight: Some(TokenWeight {
+                contract: token,
+                balance_of: OwnedReceiveName::new_unchecked("gov_token.balanceOf".to_string()),
+                token_id: vec![1],
+            }),
+            ..init_parameter()
+        });
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        let mut state = state_result.expect("Contract initialization results in error");
 
-    //     let account1 = new_account();
-    //     let params = GetVoterParams {
-    //         voter_address: Address::Account(account1),
-    //     };
-    //     let parameter_bytes = to_bytes(&params);
-    //     let slot_time = 0u64;
-    //     let ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, slot_time, &parameter_bytes);
-    //     let res: ContractResult<ActionsTree> = contract_give_right_to_vote(&ctx, &mut state);
-    //     let actions = res.expect_report("giving right results in error.");
-    //     claim_eq!(actions, ActionsTree::accept(), "No action should be produced.");
+        let parameter_bytes = to_bytes(&GetVoteParams {
+            proposal_id: 1 as ProposalId,
+            direction: VoteDirection::For,
+        });
+        let mut results = Vec::new();
+        for _ in 0..2 {
+            let account = new_account();
+            let ctx = receive_ctx(ACCOUNT_0, account, 0u64, &parameter_bytes);
+            let res: ContractResult<ActionsTree> =
+                contract_vote(&ctx, &mut LogRecorder::init(), &mut state);
+            res.expect_report("contract voting results in error.");
 
-    //     let account2 = new_account();
-    //     let params = GetVoterParams {
-    //         voter_address: Address::Account(account2),
-    //     };
-    //     let parameter_bytes = to_bytes(&params);
-    //     let slot_time = 0u64;
-    //     let ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, slot_time, &parameter_bytes);
-    //     let res: ContractResult<ActionsTree> = contract_give_right_to_vote(&ctx, &mut state);
-    //     let actions = res.expect_report("giving right results in error.");
-    //     claim_eq!(actions, ActionsTree::accept(), "No action should be produced.");
-
-    //     let mut voters = Map::default();
-    //     voters.insert(
-    //         Address::Account(account1),
-    //         VoterState {
-    //             weight: 1,
-    //             ..Default::default()
-    //         },
-    //     );
-    //     voters.insert(
-    //         Address::Account(account2),
-    //         VoterState {
-    //             weight: 1,
-    //             ..Default::default()
-    //         },
-    //     );
-    //     claim_eq!(state.voters, voters);
-    // }
+            let parameter_bytes = to_bytes(&BalanceOfQueryResponse {
+                balances: vec![(
+                    BalanceOfQuery {
+                        token_id: vec![1],
+                        address: Address::Account(account),
+                    },
+                    10,
+                )],
+            });
+            let mut ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, 0u64, &parameter_bytes);
+            ctx.set_sender(Address::Contract(token));
+            let res: ContractResult<ActionsTree> =
+                contract_receive_balance(&ctx, &mut LogRecorder::init(), &mut state);
+            results.push(res.map(|_| ()));
+        }
+        claim_eq!(
+            results,
+            vec![Ok(()), Err(ContractError::VoterLimitReached)],
+            "the second voter should exceed max_voters"
+        );
+        claim_eq!(state.voters.len(), 1, "only one voter entry should be created");
+        claim_eq!(state.proposals[&1].vote_count, 10, "only the first balance should count");
+    }
 
-    // #[concordium_test]
-    // fn test_give_right_to_vote_expired() {
-    //     let parameter_bytes = create_parameter_bytes(&init_parameter());
-    //     let ctx = parametrized_init_ctx(&parameter_bytes);
-    //     let state_result = contract_init(&ctx);
-    //     let mut state = state_result.expect("Contract initialization results in error");
+    #[concordium_test]
+    fn test_preview_winner() {
+        let parameter_bytes = create_parameter_bytes(&init_parameter());
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        let mut state = state_result.expect("Contract initialization results in error");
+        state.proposals.get_mut(&0).unwrap().vote_count = 2;
+        state.proposals.get_mut(&1).unwrap().vote_count = 5;
 
-    //     let account1 = new_account();
-    //     let params = GetVoterParams {
-    //         voter_address: Address::Account(account1),
-    //     };
-    //     let parameter_bytes = to_bytes(&params);
-    //     let slot_time = 10u64;
-    //     let ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, slot_time, &parameter_bytes);
-    //     let res: ContractResult<ActionsTree> = contract_give_right_to_vote(&ctx, &mut state);
-    //     let err = res.expect_err_report("Contract is expected to fail.");
-    //     claim_eq!(err, ContractError::Expired, "Expected to fail with error Expired");
-    // }
+        let ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, 0u64, &[]);
+        let mut logger = LogRecorder::init();
+        let res: ContractResult<ActionsTree> =
+            contract_preview_winner(&ctx, &mut logger, &mut state);
+        res.expect_report("previewing the winner results in error.");
+        claim_eq!(state.status, Status::InProcess, "the preview should not finish the poll");
+        claim_eq!(state.winning_proposal_id, Vec::<ProposalId>::new(), "nothing should be stored");
 
-    // #[concordium_test]
-    // fn test_give_right_to_vote_with_no_authority() {
-    //     let parameter_bytes = create_parameter_bytes(&init_parameter());
-    //     let ctx = parametrized_init_ctx(&parameter_bytes);
-    //     let state_result = contract_init(&ctx);
-    //     let mut state = state_result.expect("Contract initialization results in error");
+        let res: ContractResult<ActionsTree> =
+            contract_winning_proposal(&ctx, &mut LogRecorder::init(), &mut state);
+        res.expect_report("contract winning proposal results in error.");
+        claim_eq!(
+            logger.logs,
+            vec![to_bytes(&state.tally_result())],
+            "the preview should match the finalized result"
+        );
+    }
 
-    //     let account1 = new_account();
-    //     let params = GetVoterParams {
-    //         voter_address: Address::Account(account1),
-    //     };
-    //     let parameter_bytes = to_bytes(&params);
-    //     let slot_time = 0u64;
-    //     let ctx = receive_ctx(ACCOUNT_0, account1, slot_time, &parameter_bytes);
-    //     let res: ContractResult<ActionsTree> = contract_give_right_to_vote(&ctx, &mut state);
-    //     let err = res.expect_err_report("Contract is expected to fail.");
-    //     claim_eq!(
-    //         err,
-    //         ContractError::FromIsNotTheOwner,
-    //         "Expected to fail with error FromIsNotTheOwner"
-    //     );
-    // }
+    #[concordium_test]
+    fn test_cooldown() {
+        let parameter_bytes = create_parameter_bytes(&InitParams {
+            expiry: Timestamp::from_timestamp_millis(100),
+            cooldown_ms: 10,
+            ..init_parameter()
+        });
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        let mut state = state_result.expect("Contract initialization results in error");
+
+        let account1 = new_account();
+        let vote = |state: &mut State, proposal_id: ProposalId, slot_time: u64| {
+            let parameter_bytes = to_bytes(&GetVoteParams {
+                proposal_id,
+                direction: VoteDirection::For,
+            });
+            let ctx = receive_ctx(ACCOUNT_0, account1, slot_time, &parameter_bytes);
+            let res: ContractResult<ActionsTree> =
+                contract_vote(&ctx, &mut LogRecorder::init(), state);
+            res
+        };
+
+        vote(&mut state, 0, 20).expect_report("the first vote should be allowed.");
+        claim_eq!(
+            vote(&mut state, 1, 29),
+            Err(ContractError::CooldownActive),
+            "Result should be CooldownActive."
+        );
+        claim_eq!(state.proposals[&0].vote_count, 1, "the vote should be kept");
+        vote(&mut state, 1, 30).expect_report("changing after the cooldown results in error.");
+        claim_eq!(state.proposals[&1].vote_count, 1, "the vote should be changed");
+
+        // 取り消してから投票しても待機時間は回避できない。
+        let ctx = receive_ctx(ACCOUNT_0, account1, 31u64, &[]);
+        let res: ContractResult<ActionsTree> = cancel_vote(&ctx, &mut state);
+        res.expect_report("cancel voting results in error.");
+        claim_eq!(
+            vote(&mut state, 0, 35),
+            Err(ContractError::CooldownActive),
+            "a vote after cancelling should wait for the cooldown"
+        );
+        vote(&mut state, 0, 40).expect_report("changing after the cooldown results in error.");
+        claim_eq!(state.proposals[&0].vote_count, 1, "the vote should be changed");
+    }
+
+    #[concordium_test]
+    fn test_version() {
+        let parameter_bytes = create_parameter_bytes(&init_parameter());
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        let mut state = state_result.expect("Contract initialization results in error");
+
+        let ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, 0u64, &[]);
+        let mut logger = LogRecorder::init();
+        let res: ContractResult<ActionsTree> = contract_version(&ctx, &mut logger, &mut state);
+        res.expect_report("getting the version results in error.");
+        claim_eq!(logger.logs, vec![to_bytes(&CONTRACT_VERSION)], "the version should be logged");
+    }
 
     #[concordium_test]
-    fn test_contract_vote() {
+    fn test_vote_against() {
         let parameter_bytes = create_parameter_bytes(&init_parameter());
         let ctx = parametrized_init_ctx(&parameter_bytes);
-        let state_result = contract_init(&ctx);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
         let mut state = state_result.expect("Contract initialization results in error");
 
-        let account1 = new_account();
-        let params = GetVoteParams {
-            proposal_id: 1 as ProposalId,
-        };
-        let parameter_bytes = to_bytes(&params);
-        let slot_time = 0u64;
-        let ctx = receive_ctx(ACCOUNT_0, account1, slot_time, &parameter_bytes);
+        let ballots = [
+            (0, VoteDirection::For),
+            (0, VoteDirection::For),
+            (0, VoteDirection::For),
+            (0, VoteDirection::Against),
+            (0, VoteDirection::Against),
+            (1, VoteDirection::For),
+            (1, VoteDirection::For),
+        ];
+        for (proposal_id, direction) in ballots.iter() {
+            let parameter_bytes = to_bytes(&GetVoteParams {
+                proposal_id: *proposal_id,
+                direction: *direction,
+            });
+            let ctx = receive_ctx(ACCOUNT_0, new_account(), 0u64, &parameter_bytes);
+            let res: ContractResult<ActionsTree> =
+                contract_vote(&ctx, &mut LogRecorder::init(), &mut state);
+            res.expect_report("contract voting results in error.");
+        }
+        claim_eq!(state.proposals[&0].vote_count, 3, "something wrong with vote_count");
+        claim_eq!(state.proposals[&0].against_count, 2, "something wrong with against_count");
+        claim_eq!(state.proposals[&0].net_score(), 1, "the net score should be 3 - 2");
 
-        let res: ContractResult<ActionsTree> = contract_vote(&ctx, &mut state);
-        let actions = res.expect_report("contract voting results in error.");
-        claim_eq!(actions, ActionsTree::accept(), "No action should be produced.");
+        let ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, 0u64, &[]);
+        let res: ContractResult<ActionsTree> = contract_check_invariants(&ctx, &mut state);
+        claim_eq!(res, Ok(ActionsTree::accept()), "the counts should match the ballots");
 
-        claim_eq!(
-            state.voters.get(&Address::Account(account1)).unwrap().voted,
-            true,
-            "voted status should be true"
-        );
+        let res: ContractResult<ActionsTree> =
+            contract_winning_proposal(&ctx, &mut LogRecorder::init(), &mut state);
+        res.expect_report("contract winning proposal results in error.");
+        claim_eq!(state.winning_proposal_id, vec![1], "the best net score should win");
+        claim_eq!(state.winning_vote_count, 2, "something wrong with winning_vote_count");
+    }
 
-        claim_eq!(
-            state.proposals.get(&1).unwrap().vote_count,
-            1,
-            "something wrong with vote_count"
-        );
+    #[concordium_test]
+    fn test_net_score_saturates() {
+        let mut proposal = Proposal::new("A".to_string(), String::new(), None);
+        proposal.vote_count = 1;
+        proposal.against_count = 4;
+        claim_eq!(proposal.net_score(), 0, "the net score should not go below 0");
     }
 
     #[concordium_test]
-    fn test_contract_vote_to_wrong_number() {
-        let parameter_bytes = create_parameter_bytes(&init_parameter());
+    fn test_switch_vote_direction() {
+        let parameter_bytes = create_parameter_bytes(&InitParams {
+            allow_revote: true,
+            ..init_parameter()
+        });
         let ctx = parametrized_init_ctx(&parameter_bytes);
-        let state_result = contract_init(&ctx);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
         let mut state = state_result.expect("Contract initialization results in error");
 
         let account1 = new_account();
-        let params = GetVoteParams {
-            proposal_id: 2 as ProposalId,
-        };
-        let parameter_bytes = to_bytes(&params);
-        let slot_time = 0u64;
-        let ctx = receive_ctx(ACCOUNT_0, account1, slot_time, &parameter_bytes);
+        for direction in [VoteDirection::For, VoteDirection::Against].iter() {
+            let parameter_bytes = to_bytes(&GetVoteParams {
+                proposal_id: 0,
+                direction: *direction,
+            });
+            let ctx = receive_ctx(ACCOUNT_0, account1, 0u64, &parameter_bytes);
+            let res: ContractResult<ActionsTree> =
+                contract_vote(&ctx, &mut LogRecorder::init(), &mut state);
+            res.expect_report("contract voting results in error.");
+        }
+        claim_eq!(state.proposals[&0].vote_count, 0, "the vote for should be retracted");
+        claim_eq!(state.proposals[&0].against_count, 1, "the vote against should be counted");
 
-        let res_1: Result<ActionsTree, ContractError> = contract_vote(&ctx, &mut state);
-        claim_eq!(
-            res_1,
-            Err(ContractError::ProposalIsNotFound),
-            "Result should be ProposalIsNotFound."
-        );
+        let ctx = receive_ctx(ACCOUNT_0, account1, 0u64, &[]);
+        let res: ContractResult<ActionsTree> = cancel_vote(&ctx, &mut state);
+        res.expect_report("cancel vote results in error.");
+        claim_eq!(state.proposals[&0].against_count, 0, "the vote against should be retracted");
     }
 
     #[concordium_test]
-    fn test_contract_vote_selection_change() {
+    fn test_get_init_params() {
         let parameter_bytes = create_parameter_bytes(&init_parameter());
         let ctx = parametrized_init_ctx(&parameter_bytes);
-        let state_result = contract_init(&ctx);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
         let mut state = state_result.expect("Contract initialization results in error");
 
-        let account1 = new_account();
-        let params = GetVoteParams {
-            proposal_id: 0 as ProposalId,
-        };
-        let parameter_bytes = to_bytes(&params);
-        let slot_time = 0u64;
-        let ctx = receive_ctx(ACCOUNT_0, account1, slot_time, &parameter_bytes);
-
-        let res_1: ContractResult<ActionsTree> = contract_vote(&ctx, &mut state);
-        let actions_1 = res_1.expect_report("contract voting results in error.");
-        claim_eq!(actions_1, ActionsTree::accept(), "No action should be produced.");
+        let parameter_bytes = to_bytes(&SetExpiryParams {
+            expiry: Timestamp::from_timestamp_millis(EXPIRY + 1),
+        });
+        let ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, 0u64, &parameter_bytes);
+        let res: ContractResult<ActionsTree> = contract_set_expiry(&ctx, &mut state);
+        res.expect_report("contract set expiry results in error.");
 
+        let ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, 0u64, &[]);
+        let mut logger = LogRecorder::init();
+        let res: ContractResult<ActionsTree> =
+            contract_get_init_params(&ctx, &mut logger, &mut state);
+        res.expect_report("contract get init params results in error.");
+        let params = init_parameter();
         claim_eq!(
-            state.proposals.get(&0).unwrap().vote_count,
-            1,
-            "something wrong with vote_count"
+            logger.logs,
+            vec![to_bytes(&Some(InitParamsRecord {
+                title: params.title,
+                description: params.description,
+                proposal_names: params.proposal_names,
+                expiry: params.expiry,
+            }))],
+            "the init parameters should be logged as supplied"
         );
+    }
 
+    #[concordium_test]
+    fn test_eligible_allowlist() {
+        let account1 = new_account();
+        let parameter_bytes = create_parameter_bytes(&InitParams {
+            eligible: vec![Address::Account(account1)],
+            ..init_parameter()
+        });
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        let mut state = state_result.expect("Contract initialization results in error");
         claim_eq!(
-            state.proposals.get(&1).unwrap().vote_count,
-            0,
-            "something wrong with vote_count"
+            state.get_voter(&Address::Account(account1)).map(|voter| voter.weight),
+            Some(1),
+            "the allowlisted voter should have weight 1"
         );
+        claim_eq!(state.eligible_count, 1, "something wrong with eligible_count");
 
-        let params = GetVoteParams {
-            proposal_id: 1 as ProposalId,
-        };
-        let parameter_bytes = to_bytes(&params);
-        let slot_time = 0u64;
-        let ctx = receive_ctx(ACCOUNT_0, account1, slot_time, &parameter_bytes);
-        let res_2: ContractResult<ActionsTree> = contract_vote(&ctx, &mut state);
-        let actions_2 = res_2.expect_report("contract voting results in error.");
-        claim_eq!(actions_2, ActionsTree::accept(), "No action should be produced.");
+        let parameter_bytes = to_bytes(&GetVoteParams {
+            proposal_id: 0,
+            direction: VoteDirection::For,
+        });
+        let ctx = receive_ctx(ACCOUNT_0, account1, 0u64, &parameter_bytes);
+        let res: ContractResult<ActionsTree> =
+            contract_vote(&ctx, &mut LogRecorder::init(), &mut state);
+        res.expect_report("contract voting results in error.");
+        claim_eq!(state.proposals[&0].vote_count, 1, "the allowlisted vote should count");
 
-        claim_eq!(
-            state.voters.get(&Address::Account(account1)).unwrap().voted,
-            true,
-            "voted status should be true"
-        );
+        let ctx = receive_ctx(ACCOUNT_0, new_account(), 0u64, &parameter_bytes);
+        let res: ContractResult<ActionsTree> =
+            contract_vote(&ctx, &mut LogRecorder::init(), &mut state);
+        claim_eq!(res, Err(ContractError::NoRightToVote), "Result should be NoRightToVote.");
+        claim_eq!(state.proposals[&0].vote_count, 1, "the rejected vote should not count");
 
-        claim_eq!(
-            state.proposals.get(&0).unwrap().vote_count,
-            0,
-            "something wrong with vote_count"
-        );
+        // 投票権のないアドレスはどの入口からも投票者になれない。
+        let stranger = new_account();
+        let ctx = receive_ctx(ACCOUNT_0, stranger, 0u64, &[]);
+        let res: ContractResult<ActionsTree> =
+            contract_abstain(&ctx, &mut LogRecorder::init(), &mut state);
+        claim_eq!(res, Err(ContractError::NoRightToVote), "abstain should be rejected");
 
-        claim_eq!(
-            state.proposals.get(&1).unwrap().vote_count,
-            1,
-            "something wrong with vote_count"
+        let parameter_bytes = to_bytes(&DelegateParams {
+            to: Address::Account(account1),
+        });
+        let ctx = receive_ctx(ACCOUNT_0, stranger, 0u64, &parameter_bytes);
+        let res: ContractResult<ActionsTree> =
+            contract_delegate(&ctx, &mut LogRecorder::init(), &mut state);
+        claim_eq!(res, Err(ContractError::NoRightToVote), "delegate should be rejected");
+
+        let parameter_bytes = to_bytes(&WriteInParams {
+            name: PROPOSAL_NAME_1.to_string(),
+        });
+        let ctx = receive_ctx(ACCOUNT_0, stranger, 0u64, &parameter_bytes);
+        let res: ContractResult<ActionsTree> =
+            contract_vote_write_in(&ctx, &mut LogRecorder::init(), &mut state);
+        claim_eq!(res, Err(ContractError::NoRightToVote), "voteWriteIn should be rejected");
+
+        let parameter_bytes = to_bytes(&VoteBatchParams {
+            ballots: vec![(Address::Account(stranger), 0)],
+        });
+        let ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, 0u64, &parameter_bytes);
+        let res: ContractResult<ActionsTree> =
+            contract_vote_batch(&ctx, &mut LogRecorder::init(), &mut state);
+        claim_eq!(res, Err(ContractError::NoRightToVote), "voteBatch should be rejected");
+
+        state.commit_reveal = true;
+        let parameter_bytes = to_bytes(&CommitVoteParams {
+            commitment: sha256(&to_bytes(&(0 as ProposalId, [0u8; 32]))),
+        });
+        let ctx = receive_ctx(ACCOUNT_0, stranger, 0u64, &parameter_bytes);
+        let res: ContractResult<ActionsTree> = contract_commit_vote(&ctx, &mut state);
+        claim_eq!(res, Err(ContractError::NoRightToVote), "commitVote should be rejected");
+
+        claim!(
+            state.get_voter(&Address::Account(stranger)).is_none(),
+            "no voter entry should be created for the stranger"
         );
+        claim_eq!(state.proposals[&0].vote_count, 1, "the rejected ballots should not count");
     }
 
     #[concordium_test]
-    fn test_contract_vote_same_selection() {
-        let parameter_bytes = create_parameter_bytes(&init_parameter());
+    fn test_get_start_time() {
+        let parameter_bytes = create_parameter_bytes(&InitParams {
+            start_open: false,
+            ..init_parameter()
+        });
         let ctx = parametrized_init_ctx(&parameter_bytes);
-        let state_result = contract_init(&ctx);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
         let mut state = state_result.expect("Contract initialization results in error");
 
-        let account1 = new_account();
-        let params = GetVoteParams {
-            proposal_id: 1 as ProposalId,
+        let get_start_time = |state: &mut State| {
+            let ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, 0u64, &[]);
+            let mut logger = LogRecorder::init();
+            let res: ContractResult<ActionsTree> =
+                contract_get_start_time(&ctx, &mut logger, state);
+            res.expect_report("contract get start time results in error.");
+            logger.logs
         };
-        let parameter_bytes = to_bytes(&params);
-        let slot_time = 0u64;
-        let ctx = receive_ctx(ACCOUNT_0, account1, slot_time, &parameter_bytes);
-
-        let res_1: ContractResult<ActionsTree> = contract_vote(&ctx, &mut state);
-        let actions_1 = res_1.expect_report("contract voting results in error.");
-        claim_eq!(actions_1, ActionsTree::accept(), "No action should be produced.");
-
-        let slot_time = 0u64;
-        let ctx = receive_ctx(ACCOUNT_0, account1, slot_time, &parameter_bytes);
-
-        let res_2: ContractResult<ActionsTree> = contract_vote(&ctx, &mut state);
-        let actions_2 = res_2.expect_report("contract voting results in error.");
-        claim_eq!(actions_2, ActionsTree::accept(), "No action should be produced.");
+        claim_eq!(
+            get_start_time(&mut state),
+            vec![to_bytes(&None::<Timestamp>)],
+            "the start time should be unset before voting opens"
+        );
 
+        let ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, 42u64, &[]);
+        let res: ContractResult<ActionsTree> = contract_open_voting(&ctx, &mut state);
+        res.expect_report("opening voting results in error.");
         claim_eq!(
-            state.voters.get(&Address::Account(account1)).unwrap().voted,
-            true,
-            "voted status should be true"
+            get_start_time(&mut state),
+            vec![to_bytes(&Some(Timestamp::from_timestamp_millis(42)))],
+            "the start time should be the slot time of openVoting"
         );
 
+        let ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, 50u64, &[]);
+        let res: ContractResult<ActionsTree> = contract_open_voting(&ctx, &mut state);
+        res.expect_report("opening voting results in error.");
         claim_eq!(
-            state.proposals.get(&1).unwrap().vote_count,
-            1,
-            "something wrong with vote_count"
+            state.started_at,
+            Some(Timestamp::from_timestamp_millis(42)),
+            "opening again should keep the start time"
         );
     }
 
     #[concordium_test]
-    fn test_contract_winning_proposal_no_voters() {
+    fn test_final_result_skewed_weights() {
         let parameter_bytes = create_parameter_bytes(&init_parameter());
         let ctx = parametrized_init_ctx(&parameter_bytes);
-        let state_result = contract_init(&ctx);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
         let mut state = state_result.expect("Contract initialization results in error");
 
-        let account1 = new_account();
-        let params = GetVoteParams {
-            proposal_id: 1 as ProposalId,
-        };
-        let parameter_bytes = to_bytes(&params);
-        let slot_time = 0u64;
-        let ctx = receive_ctx(ACCOUNT_0, account1, slot_time, &parameter_bytes);
-        claim_eq!(state.status, Status::InProcess, "Status should be InProcess");
-        let res_1: ContractResult<ActionsTree> = contract_winning_proposal(&ctx, &mut state);
-        let actions_1 = res_1.expect_report("contract winning proposal results in error.");
-        claim_eq!(actions_1, ActionsTree::accept(), "No action should be produced.");
-        claim_eq!(state.status, Status::Finished, "Status should be Finished");
+        // 重み8の1人が0に、重み1の2人が1に投票し、重み1の4人は投票しない。
+        let ballots =
+            [(8, Some(0)), (1, Some(1)), (1, Some(1)), (1, None), (1, None), (1, None), (1, None)];
+        for (weight, proposal_id) in ballots.iter() {
+            let account = new_account();
+            let parameter_bytes = to_bytes(&GiveRightToVoteParams {
+                voter_address: Address::Account(account),
+                weight: *weight,
+            });
+            let ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, 0u64, &parameter_bytes);
+            let res: ContractResult<ActionsTree> =
+                contract_give_right_to_vote(&ctx, &mut LogRecorder::init(), &mut state);
+            res.expect_report("giving right results in error.");
+            if let Some(proposal_id) = proposal_id {
+                let parameter_bytes = to_bytes(&GetVoteParams {
+                    proposal_id: *proposal_id,
+                    direction: VoteDirection::For,
+                });
+                let ctx = receive_ctx(ACCOUNT_0, account, 0u64, &parameter_bytes);
+                let res: ContractResult<ActionsTree> =
+                    contract_vote(&ctx, &mut LogRecorder::init(), &mut state);
+                res.expect_report("contract voting results in error.");
+            }
+        }
+
+        let ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, 0u64, &[]);
+        let res: ContractResult<ActionsTree> =
+            contract_winning_proposal(&ctx, &mut LogRecorder::init(), &mut state);
+        res.expect_report("contract winning proposal results in error.");
+
+        let result = state.final_result().expect_report("final result results in error.");
+        claim_eq!(result.winning_proposal_ids, vec![0], "the high-weight voter should win");
         claim_eq!(
-            state.winning_proposal_id,
-            vec![1, 0],
-            "something wrong with winning_proposal_id"
+            result.shares,
+            vec![
+                WeightShare {
+                    proposal_id: 0,
+                    cast_share_bp: 8_000,
+                    eligible_share_bp: 5_714,
+                },
+                WeightShare {
+                    proposal_id: 1,
+                    cast_share_bp: 2_000,
+                    eligible_share_bp: 1_428,
+                },
+            ],
+            "the eligible share should be relative to all 14 granted weight"
         );
     }
 
     #[concordium_test]
-    fn test_contract_winning_proposal_one_winning_proposal() {
-        let parameter_bytes = create_parameter_bytes(&init_parameter());
+    fn test_final_result_shares_with_decay() {
+        let parameter_bytes = create_parameter_bytes(&InitParams {
+            expiry: Timestamp::from_timestamp_millis(1_000),
+            weight_policy: WeightPolicy::LinearDecay {
+                window: Duration::from_millis(1_000),
+            },
+            ..init_parameter()
+        });
         let ctx = parametrized_init_ctx(&parameter_bytes);
-        let state_result = contract_init(&ctx);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
         let mut state = state_result.expect("Contract initialization results in error");
 
-        let account1 = new_account();
-        let params = GetVoteParams {
-            proposal_id: 0 as ProposalId,
-        };
-        let parameter_bytes = to_bytes(&params);
-        let slot_time = 0u64;
-        let ctx = receive_ctx(ACCOUNT_0, account1, slot_time, &parameter_bytes);
-
-        let res_1: ContractResult<ActionsTree> = contract_vote(&ctx, &mut state);
-        let actions_1 = res_1.expect_report("contract voting results in error.");
-        claim_eq!(actions_1, ActionsTree::accept(), "No action should be produced.");
+        // 4人に投票権を与え、1人は早く0に、1人は遅く1に投票する。
+        // 投票権のない1人も1に投票するが、有権者の重みには数えない。
+        let ballots = [
+            (true, Some((0, 0u64))),
+            (true, Some((1, 750))),
+            (true, None),
+            (true, None),
+            (false, Some((1, 0))),
+        ];
+        for (granted, ballot) in ballots.iter() {
+            let account = new_account();
+            if *granted {
+                let parameter_bytes = to_bytes(&GiveRightToVoteParams {
+                    voter_address: Address::Account(account),
+                    weight: 1,
+                });
+                let ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, 0u64, &parameter_bytes);
+                let res: ContractResult<ActionsTree> =
+                    contract_give_right_to_vote(&ctx, &mut LogRecorder::init(), &mut state);
+                res.expect_report("giving right results in error.");
+            }
+            if let Some((proposal_id, slot_time)) = ballot {
+                let parameter_bytes = to_bytes(&GetVoteParams {
+                    proposal_id: *proposal_id,
+                    direction: VoteDirection::For,
+                });
+                let ctx = receive_ctx(ACCOUNT_0, account, *slot_time, &parameter_bytes);
+                let res: ContractResult<ActionsTree> =
+                    contract_vote(&ctx, &mut LogRecorder::init(), &mut state);
+                res.expect_report("contract voting results in error.");
+            }
+        }
+        claim_eq!(
+            state.eligible_weight(),
+            4 * u128::from(DECAY_SCALE),
+            "the eligible weight should be in decayed units"
+        );
 
-        claim_eq!(state.status, Status::InProcess, "Status should be InProcess");
+        let ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, 1_001u64, &[]);
+        let res: ContractResult<ActionsTree> =
+            contract_winning_proposal(&ctx, &mut LogRecorder::init(), &mut state);
+        res.expect_report("contract winning proposal results in error.");
 
+        let result = state.final_result().expect_report("final result results in error.");
         claim_eq!(
-            state.voters.get(&Address::Account(account1)).unwrap().voted,
-            true,
-            "voted status should be true"
+            result.shares,
+            vec![
+                WeightShare {
+                    proposal_id: 0,
+                    cast_share_bp: 4_444,
+                    eligible_share_bp: 2_500,
+                },
+                WeightShare {
+                    proposal_id: 1,
+                    cast_share_bp: 5_556,
+                    eligible_share_bp: 3_125,
+                },
+            ],
+            "the shares should compare vote counts and eligible weight in the same units"
         );
 
+        state.proposals.get_mut(&1).unwrap().vote_count = u64::MAX;
         claim_eq!(
-            state.proposals.get(&0).unwrap().vote_count,
-            1,
-            "something wrong with vote_count"
+            state.final_result(),
+            Err(ContractError::VoteCountOverflow),
+            "a share that does not fit should fail instead of being clamped"
         );
-
-        let res_2: ContractResult<ActionsTree> = contract_winning_proposal(&ctx, &mut state);
-        let actions_2 = res_2.expect_report("contract winning proposal results in error.");
-        claim_eq!(actions_2, ActionsTree::accept(), "No action should be produced.");
-        claim_eq!(state.status, Status::Finished, "Status should be Finished");
-        claim_eq!(state.winning_proposal_id, vec![0], "something wrong with winning_proposal_id");
     }
 
     #[concordium_test]
-    fn test_contract_winning_proposal_multi_winning_proposal() {
+    fn test_views_use_net_score() {
         let parameter_bytes = create_parameter_bytes(&init_parameter());
         let ctx = parametrized_init_ctx(&parameter_bytes);
-        let state_result = contract_init(&ctx);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
         let mut state = state_result.expect("Contract initialization results in error");
+        // 0は3票で反対2票、1は2票なので、純得票は1と2。
+        state.proposals.get_mut(&0).unwrap().vote_count = 3;
+        state.proposals.get_mut(&0).unwrap().against_count = 2;
+        state.proposals.get_mut(&1).unwrap().vote_count = 2;
 
-        let account1 = new_account();
-        let params = GetVoteParams {
-            proposal_id: 0 as ProposalId,
-        };
-        let parameter_bytes = to_bytes(&params);
-        let slot_time = 0u64;
-        let ctx = receive_ctx(ACCOUNT_0, account1, slot_time, &parameter_bytes);
-
-        let res_1: ContractResult<ActionsTree> = contract_vote(&ctx, &mut state);
-        let actions_1 = res_1.expect_report("contract voting results in error.");
-        claim_eq!(actions_1, ActionsTree::accept(), "No action should be produced.");
-
-        claim_eq!(state.status, Status::InProcess, "Status should be InProcess");
-
+        claim_eq!(state.rank(&0), Ok(2), "the rank should follow the net score");
+        claim_eq!(state.rank(&1), Ok(1), "the rank should follow the net score");
         claim_eq!(
-            state.voters.get(&Address::Account(account1)).unwrap().voted,
-            true,
-            "voted status should be true"
+            state.distribution(),
+            vec![(0, 3_333), (1, 6_667)],
+            "the distribution should follow the net scores"
         );
 
+        let parameter_bytes = to_bytes(&GetProposalParams {
+            proposal_id: 0,
+        });
+        let ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, 0u64, &parameter_bytes);
+        let mut logger = LogRecorder::init();
+        let res: ContractResult<ActionsTree> = contract_get_share(&ctx, &mut logger, &mut state);
+        res.expect_report("contract get share results in error.");
+        claim_eq!(logger.logs, vec![to_bytes(&3_333u32)], "the share should use the net score");
+
+        let parameter_bytes = to_bytes(&CompareProposalsParams {
+            a: 0,
+            b: 1,
+        });
+        let ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, 0u64, &parameter_bytes);
+        let mut logger = LogRecorder::init();
+        let res: ContractResult<ActionsTree> =
+            contract_compare_proposals(&ctx, &mut logger, &mut state);
+        res.expect_report("comparing proposals results in error.");
         claim_eq!(
-            state.proposals.get(&0).unwrap().vote_count,
-            1,
-            "something wrong with vote_count"
+            logger.logs,
+            vec![to_bytes(&Event::ProposalComparison {
+                a: 0,
+                a_vote_count: 1,
+                b: 1,
+                b_vote_count: 2,
+                difference: 1,
+                leader: Some(1),
+            })],
+            "the comparison should use the net scores"
         );
 
+        let parameter_bytes = to_bytes(&GetProposalByNameParams {
+            name: PROPOSAL_NAME_1.to_string(),
+        });
+        let ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, 0u64, &parameter_bytes);
+        let mut logger = LogRecorder::init();
+        let res: ContractResult<ActionsTree> =
+            contract_get_proposal_by_name(&ctx, &mut logger, &mut state);
+        res.expect_report("contract get proposal by name results in error.");
         claim_eq!(
-            state.proposals.get(&1).unwrap().vote_count,
-            0,
-            "something wrong with vote_count"
+            logger.logs,
+            vec![to_bytes(&(0 as ProposalId, 1u64))],
+            "the lookup should log the net score"
         );
+    }
 
-        let account2 = new_account();
-        let params = GetVoteParams {
-            proposal_id: 1 as ProposalId,
-        };
-        let parameter_bytes = to_bytes(&params);
-        let slot_time = 0u64;
-        let ctx = receive_ctx(ACCOUNT_1, account2, slot_time, &parameter_bytes);
-
-        let res_2: ContractResult<ActionsTree> = contract_vote(&ctx, &mut state);
-        let actions_2 = res_2.expect_report("contract voting results in error.");
-        claim_eq!(actions_2, ActionsTree::accept(), "No action should be produced.");
-
-        claim_eq!(state.status, Status::InProcess, "Status should be InProcess");
+    #[concordium_test]
+    fn test_edit_with_only_against_votes() {
+        let parameter_bytes = create_parameter_bytes(&init_parameter());
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        let mut state = state_result.expect("Contract initialization results in error");
 
-        claim_eq!(
-            state.voters.get(&Address::Account(account1)).unwrap().voted,
-            true,
-            "voted status should be true"
-        );
+        let parameter_bytes = to_bytes(&GetVoteParams {
+            proposal_id: 0,
+            direction: VoteDirection::Against,
+        });
+        let ctx = receive_ctx(ACCOUNT_0, new_account(), 0u64, &parameter_bytes);
+        let res: ContractResult<ActionsTree> =
+            contract_vote(&ctx, &mut LogRecorder::init(), &mut state);
+        res.expect_report("contract voting results in error.");
 
+        let parameter_bytes = to_bytes(&EditProposalParams {
+            proposal_id: 0,
+            new_name: "Renamed".to_string(),
+        });
+        let ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, 0u64, &parameter_bytes);
+        let res: ContractResult<ActionsTree> = contract_edit_proposal(&ctx, &mut state);
         claim_eq!(
-            state.proposals.get(&0).unwrap().vote_count,
-            1,
-            "something wrong with vote_count"
+            res,
+            Err(ContractError::VotingAlreadyStarted),
+            "Result should be VotingAlreadyStarted."
         );
 
+        let parameter_bytes = to_bytes(&SetDisplayOrderParams {
+            orders: vec![(0, 1)],
+        });
+        let ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, 0u64, &parameter_bytes);
+        let res: ContractResult<ActionsTree> = contract_set_display_order(&ctx, &mut state);
         claim_eq!(
-            state.proposals.get(&1).unwrap().vote_count,
-            1,
-            "something wrong with vote_count"
+            res,
+            Err(ContractError::VotingAlreadyStarted),
+            "Result should be VotingAlreadyStarted."
         );
+    }
 
-        let res_3: ContractResult<ActionsTree> = contract_winning_proposal(&ctx, &mut state);
-        let actions_3 = res_3.expect_report("contract winning proposal results in error.");
-
-        claim_eq!(actions_3, ActionsTree::accept(), "No action should be produced.");
-        claim_eq!(state.status, Status::Finished, "Status should be Finished");
+    #[concordium_test]
+    fn test_aggregate_only_rejects_commit_reveal() {
+        let parameter_bytes = create_parameter_bytes(&InitParams {
+            aggregate_only: true,
+            commit_reveal: true,
+            ..init_parameter()
+        });
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
         claim_eq!(
-            state.winning_proposal_id,
-            vec![1, 0],
-            "something wrong with winning_proposal_id"
+            state_result.err(),
+            Some(ContractError::IncompatibleModes),
+            "reveals would store the voters' choices"
         );
     }
 
     #[concordium_test]
-    fn test_cancel_vote() {
+    fn test_vote_batch_uses_voter_weight() {
         let parameter_bytes = create_parameter_bytes(&init_parameter());
         let ctx = parametrized_init_ctx(&parameter_bytes);
-        let state_result = contract_init(&ctx);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
         let mut state = state_result.expect("Contract initialization results in error");
 
-        let account1 = new_account();
-        let params = GetVoteParams {
-            proposal_id: 1 as ProposalId,
+        let heavy = Address::Account(new_account());
+        let params = GiveRightToVoteParams {
+            voter_address: heavy,
+            weight: 3,
         };
         let parameter_bytes = to_bytes(&params);
-        let slot_time = 0u64;
-        let ctx = receive_ctx(ACCOUNT_0, account1, slot_time, &parameter_bytes);
-        let res: ContractResult<ActionsTree> = contract_vote(&ctx, &mut state);
-        let actions = res.expect_report("contract voting results in error.");
-        claim_eq!(actions, ActionsTree::accept(), "No action should be produced.");
+        let ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, 0u64, &parameter_bytes);
+        let res: ContractResult<ActionsTree> =
+            contract_give_right_to_vote(&ctx, &mut LogRecorder::init(), &mut state);
+        res.expect_report("giving right to vote results in error.");
+
+        let params = VoteBatchParams {
+            ballots: vec![(heavy, 0), (Address::Account(new_account()), 1)],
+        };
+        let parameter_bytes = to_bytes(&params);
+        let ctx = receive_ctx(ACCOUNT_0, ACCOUNT_0, 0u64, &parameter_bytes);
+        let res: ContractResult<ActionsTree> =
+            contract_vote_batch(&ctx, &mut LogRecorder::init(), &mut state);
+        res.expect_report("batch voting results in error.");
         claim_eq!(
-            state.voters.get(&Address::Account(account1)).unwrap().voted,
-            true,
-            "voted status should be true"
+            state.proposals.get(&0).unwrap().vote_count,
+            3,
+            "the batch ballot should count the granted weight"
         );
         claim_eq!(
             state.proposals.get(&1).unwrap().vote_count,
             1,
-            "something wrong with vote_count"
+            "an unknown voter should count with weight 1"
         );
+    }
 
-        let res: ContractResult<ActionsTree> = cancel_vote(&ctx, &mut state);
-        let actions = res.expect_report("cancel voting results in error.");
-        claim_eq!(actions, ActionsTree::accept(), "No action should be produced.");
-        claim_eq!(
-            state.voters.get(&Address::Account(account1)).unwrap().voted,
-            false,
-            "voted status should be true"
+    #[concordium_test]
+    fn test_anonymize_delegation_events() {
+        let parameter_bytes = create_parameter_bytes(&InitParams {
+            anonymize_events: true,
+            ..init_parameter()
+        });
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let state_result = contract_init(&ctx, &mut LogRecorder::init());
+        let mut state = state_result.expect("Contract initialization results in error");
+
+        let delegator = new_account();
+        let voter1 = new_account();
+        let voter2 = new_account();
+        let mut logger = LogRecorder::init();
+        for voter in [voter1, voter2].iter() {
+            let parameter_bytes = to_bytes(&GetVoteParams {
+                proposal_id: 0 as ProposalId,
+                direction: VoteDirection::For,
+            });
+            let ctx = receive_ctx(ACCOUNT_0, *voter, 0u64, &parameter_bytes);
+            let res: ContractResult<ActionsTree> = contract_vote(&ctx, &mut logger, &mut state);
+            res.expect_report("contract voting results in error.");
+        }
+        // 委任、委任先の変更、投票、委任してからの棄権。
+        for to in [voter1, voter2].iter() {
+            let parameter_bytes = to_bytes(&DelegateParams {
+                to: Address::Account(*to),
+            });
+            let ctx = receive_ctx(ACCOUNT_0, delegator, 0u64, &parameter_bytes);
+            let res: ContractResult<ActionsTree> = contract_delegate(&ctx, &mut logger, &mut state);
+            res.expect_report("delegating results in error.");
+        }
+        let parameter_bytes = to_bytes(&GetVoteParams {
+            proposal_id: 1 as ProposalId,
+            direction: VoteDirection::For,
+        });
+        let ctx = receive_ctx(ACCOUNT_0, delegator, 0u64, &parameter_bytes);
+        let res: ContractResult<ActionsTree> = contract_vote(&ctx, &mut logger, &mut state);
+        res.expect_report("contract voting results in error.");
+        let parameter_bytes = to_bytes(&DelegateParams {
+            to: Address::Account(voter1),
+        });
+        let ctx = receive_ctx(ACCOUNT_0, delegator, 0u64, &parameter_bytes);
+        let res: ContractResult<ActionsTree> = contract_delegate(&ctx, &mut logger, &mut state);
+        res.expect_report("delegating results in error.");
+        let ctx = receive_ctx(ACCOUNT_0, delegator, 0u64, &[]);
+        let res: ContractResult<ActionsTree> = contract_abstain(&ctx, &mut logger, &mut state);
+        res.expect_report("abstaining results in error.");
+
+        let hash = |account: AccountAddress| sha256(&to_bytes(&Address::Account(account)));
+        claim!(
+            logger.logs.contains(&to_bytes(&Event::AnonymousDelegated {
+                from_hash: hash(delegator),
+                to_hash: hash(voter1),
+                weight: 1,
+            })),
+            "the delegation should be logged anonymously"
         );
-        claim_eq!(
-            state.proposals.get(&1).unwrap().vote_count,
-            0,
-            "something wrong with vote_count"
+        claim!(
+            logger.logs.contains(&to_bytes(&Event::AnonymousDelegationRevoked {
+                from_hash: hash(delegator),
+                to_hash: hash(voter2),
+                weight: 1,
+            })),
+            "the revocation should be logged anonymously"
         );
+        for account in [delegator, voter1, voter2].iter() {
+            claim!(
+                logger.logs.iter().all(|log| !log.windows(account.0.len()).any(|w| w == account.0)),
+                "no log entry should contain a raw voter address"
+            );
+        }
     }
 }